@@ -1,7 +1,68 @@
+use std::fmt;
+
 use bxt_strafe::{Parameters, State};
 use hltas::HLTAS;
 use serde::{Deserialize, Serialize};
 
+/// Version of the data format sent over IPC, most importantly of [`Frame`].
+///
+/// Bump this whenever [`Parameters`], [`State`] or any other type sent between the games changes,
+/// so that games running different builds refuse to connect rather than exchange garbage.
+pub const FRAME_FORMAT_VERSION: u32 = 3;
+
+/// First message that a client sends to the server over IPC.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Handshake<T> {
+    /// [`FRAME_FORMAT_VERSION`] of the client.
+    pub version: u32,
+    /// Channels for communicating with the client.
+    pub channels: T,
+}
+
+impl<T> Handshake<T> {
+    /// Creates a handshake with the current [`FRAME_FORMAT_VERSION`].
+    pub fn new(channels: T) -> Self {
+        Self {
+            version: FRAME_FORMAT_VERSION,
+            channels,
+        }
+    }
+
+    /// Checks that the client uses the same format version as this build.
+    pub fn check_version(&self) -> Result<(), VersionMismatch> {
+        if self.version == FRAME_FORMAT_VERSION {
+            Ok(())
+        } else {
+            Err(VersionMismatch {
+                server: FRAME_FORMAT_VERSION,
+                client: self.version,
+            })
+        }
+    }
+}
+
+/// Error of a client and a server using a different [`FRAME_FORMAT_VERSION`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionMismatch {
+    /// Version of the server.
+    pub server: u32,
+    /// Version of the client.
+    pub client: u32,
+}
+
+impl fmt::Display for VersionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "IPC format version mismatch (server: {}, client: {}), \
+             make sure both games use the same bxt-rs build",
+            self.server, self.client
+        )
+    }
+}
+
+impl std::error::Error for VersionMismatch {}
+
 /// A movement frame.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Frame {
@@ -21,8 +82,40 @@ pub enum ServerRequest {
     Play(Play),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum GameResponse {}
+/// Response from a game playing a request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameResponse {
+    /// Playback failed on a frame.
+    PlaybackError {
+        /// Generation of the project being played.
+        generation: u16,
+        /// Index of the frame where playback failed.
+        frame_idx: u32,
+        /// Description of the error.
+        message: String,
+    },
+    /// The map of the script failed to load.
+    MapLoadFailed {
+        /// The map, or the load command if the map name is unknown.
+        map: String,
+    },
+}
+
+impl fmt::Display for GameResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameResponse::PlaybackError {
+                generation,
+                frame_idx,
+                message,
+            } => write!(
+                f,
+                "playback error on frame {frame_idx} (generation {generation}): {message}"
+            ),
+            GameResponse::MapLoadFailed { map } => write!(f, "failed to load the map: {map}"),
+        }
+    }
+}
 
 /// Data for play request.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,3 +123,50 @@ pub struct Play {
     pub hltas: HLTAS,
     pub generation: u16,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_response_round_trip() {
+        for response in [
+            GameResponse::PlaybackError {
+                generation: 3,
+                frame_idx: 1234,
+                message: "no player data".to_owned(),
+            },
+            GameResponse::MapLoadFailed {
+                map: "c1a0".to_owned(),
+            },
+        ] {
+            let bytes = bincode::serialize(&response).unwrap();
+            let deserialized: GameResponse = bincode::deserialize(&bytes).unwrap();
+            assert_eq!(deserialized, response);
+        }
+    }
+
+    #[test]
+    fn handshake_version_mismatch() {
+        let handshake = Handshake::new(());
+        let bytes = bincode::serialize(&handshake).unwrap();
+        let handshake: Handshake<()> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(handshake.check_version(), Ok(()));
+
+        let handshake = Handshake {
+            version: FRAME_FORMAT_VERSION + 1,
+            channels: (),
+        };
+        let bytes = bincode::serialize(&handshake).unwrap();
+        let handshake: Handshake<()> = bincode::deserialize(&bytes).unwrap();
+        let err = handshake.check_version().unwrap_err();
+        assert_eq!(
+            err,
+            VersionMismatch {
+                server: FRAME_FORMAT_VERSION,
+                client: FRAME_FORMAT_VERSION + 1,
+            }
+        );
+        assert!(err.to_string().contains("version mismatch"));
+    }
+}
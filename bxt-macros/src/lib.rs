@@ -6,39 +6,65 @@ extern crate proc_macro;
 use proc_macro::{Span, TokenStream, TokenTree};
 
 /// Converts the item to a byte pattern.
+///
+/// Bytes are written as two hex characters (`8B`), `??` skips a byte, and `4?` or `?A` match only
+/// the high or the low nibble of a byte respectively.
 #[proc_macro]
 pub fn pattern(input: TokenStream) -> TokenStream {
     let mut output = String::from("&[");
 
+    // The `?` or the single hex character waiting for the second half of the byte.
     let mut question = None;
-    let mut last_token = None;
+    let mut nibble: Option<(TokenTree, u8)> = None;
+    // The `?` of the last `??`, if the last byte was skipped.
+    let mut last_skip = None;
     for token in input {
-        last_token = Some(token.clone());
-
         match token {
             TokenTree::Punct(punct) if punct.as_char() == '?' => {
-                if question.is_some() {
+                if let Some((_, high)) = nibble.take() {
+                    push_byte(&mut output, high << 4, 0xF0);
+                    last_skip = None;
+                } else if question.is_some() {
                     question = None;
-                    output.push_str("None, ");
+                    output.push_str("::bxt_patterns::MaskByte::ANY, ");
+                    last_skip = Some(punct);
                 } else {
                     question = Some(punct);
                 }
             }
             token => {
+                if let Some((token, _)) = nibble {
+                    return error(token.span(), SINGLE_CHARACTER_ERROR);
+                }
+
+                let token_string = token.to_string();
+                if token_string.len() == 1 {
+                    let Ok(value) = u8::from_str_radix(&token_string, 16) else {
+                        return error(token.span(), "token must be a hex number");
+                    };
+
+                    if question.take().is_some() {
+                        push_byte(&mut output, value, 0x0F);
+                        last_skip = None;
+                    } else {
+                        nibble = Some((token, value));
+                    }
+                    continue;
+                }
+
                 if let Some(punct) = question {
                     return error(punct.span(), "missing second `?`");
                 }
 
-                let token_string = token.to_string();
                 if token_string.len() != 2 {
                     return error(token.span(), "token must be 2 characters long");
                 }
 
                 match u8::from_str_radix(&token_string, 16) {
-                    Ok(byte) => write!(output, "Some(0x{byte:X}), ")
-                        .expect("writing to `String` should never error"),
+                    Ok(byte) => push_byte(&mut output, byte, 0xFF),
                     Err(_) => return error(token.span(), "token must be a hex number"),
                 }
+                last_skip = None;
             }
         }
     }
@@ -47,7 +73,11 @@ pub fn pattern(input: TokenStream) -> TokenStream {
         return error(punct.span(), "missing second `?`");
     }
 
-    if let Some(TokenTree::Punct(punct)) = last_token {
+    if let Some((token, _)) = nibble {
+        return error(token.span(), SINGLE_CHARACTER_ERROR);
+    }
+
+    if let Some(punct) = last_skip {
         return error(
             punct.span(),
             "pattern ends on `??` (probably not what you want)",
@@ -58,6 +88,17 @@ pub fn pattern(input: TokenStream) -> TokenStream {
     output.parse().unwrap()
 }
 
+const SINGLE_CHARACTER_ERROR: &str =
+    "single-character token must be preceded or followed by `?` to match a nibble";
+
+fn push_byte(output: &mut String, value: u8, mask: u8) {
+    write!(
+        output,
+        "::bxt_patterns::MaskByte::new(0x{value:X}, 0x{mask:X}), "
+    )
+    .expect("writing to `String` should never error");
+}
+
 fn error(span: Span, msg: &str) -> TokenStream {
     format!(r#"compile_error!("{msg}")"#)
         .parse::<TokenStream>()
@@ -1,10 +1,16 @@
 use bxt_macros::pattern;
+use bxt_patterns::MaskByte;
 
 #[test]
 fn uppercase() {
     assert_eq!(
         pattern!(01 AC ?? 44),
-        &[Some(0x01), Some(0xAC), None, Some(0x44)],
+        &[
+            MaskByte::exact(0x01),
+            MaskByte::exact(0xAC),
+            MaskByte::ANY,
+            MaskByte::exact(0x44)
+        ],
     );
 }
 
@@ -12,6 +18,26 @@ fn uppercase() {
 fn lowercase() {
     assert_eq!(
         pattern!(01 ac ?? 44),
-        &[Some(0x01), Some(0xAC), None, Some(0x44)],
+        &[
+            MaskByte::exact(0x01),
+            MaskByte::exact(0xAC),
+            MaskByte::ANY,
+            MaskByte::exact(0x44)
+        ],
+    );
+}
+
+#[test]
+fn nibbles() {
+    assert_eq!(
+        pattern!(8B 4? ?A ?? ?? 0?),
+        &[
+            MaskByte::exact(0x8B),
+            MaskByte::new(0x40, 0xF0),
+            MaskByte::new(0x0A, 0x0F),
+            MaskByte::ANY,
+            MaskByte::ANY,
+            MaskByte::new(0x00, 0xF0)
+        ],
     );
 }
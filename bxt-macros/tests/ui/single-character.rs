@@ -0,0 +1,5 @@
+use bxt_macros::pattern;
+
+fn main() {
+    pattern!(00 4 00);
+}
@@ -3,13 +3,49 @@
 //! This is extracted into a separate crate to be able to compile it with optimizations even in
 //! debug builds. Searching memory for patterns is really slow otherwise.
 
+/// Byte of a pattern.
+///
+/// A memory byte `mem` matches if `mem & mask == value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaskByte {
+    /// Value of the matched bits.
+    pub value: u8,
+    /// Bits of the memory byte which must match.
+    pub mask: u8,
+}
+
+impl MaskByte {
+    /// Matches any byte.
+    pub const ANY: Self = Self { value: 0, mask: 0 };
+
+    /// Creates a new masked byte, clearing the bits of `value` outside of `mask`.
+    pub const fn new(value: u8, mask: u8) -> Self {
+        Self {
+            value: value & mask,
+            mask,
+        }
+    }
+
+    /// Creates a byte that matches exactly `value`.
+    pub const fn exact(value: u8) -> Self {
+        Self { value, mask: 0xFF }
+    }
+
+    /// Returns whether the memory byte matches.
+    #[inline]
+    pub fn matches(self, mem: u8) -> bool {
+        mem & self.mask == self.value
+    }
+}
+
 /// Set of byte patterns.
 ///
-/// Each pattern can either match bytes (`Some(x)`) or skip them (`None`). For a memory location to
-/// match the pattern, all bytes which aren't skipped in the pattern must be equal to the
+/// Each pattern byte matches memory bytes with a mask (see [`MaskByte`]). A full mask matches the
+/// exact byte, an empty mask skips the byte, and partial masks match only some bits, for example
+/// the high nibble. For a memory location to match the pattern, all pattern bytes must match the
 /// corresponding memory bytes.
 #[derive(Clone, Copy)]
-pub struct Patterns(pub &'static [&'static [Option<u8>]]);
+pub struct Patterns(pub &'static [&'static [MaskByte]]);
 
 impl Patterns {
     /// Finds a unique pattern occurrence in memory. Returns a tuple of (byte offset, pattern
@@ -29,8 +65,76 @@ impl Patterns {
 
         let mut match_offset = None;
 
-        // This is the fastest naive solution I could come up with after profiling debug and
-        // release builds with hawktracer.
+        // Try to match every pattern.
+        for (index, pattern) in self.0.iter().enumerate() {
+            if memory.len() < pattern.len() {
+                continue;
+            }
+
+            // Jump between occurrences of the first exact byte with memchr, which is much faster
+            // than checking every window.
+            let Some(first_idx) = pattern.iter().position(|pat| pat.mask == 0xFF) else {
+                // No exact bytes, go through every pattern-sized window of memory.
+                for (offset, window) in memory.windows(pattern.len()).enumerate() {
+                    if matches_window(pattern, window) {
+                        if match_offset.is_some() {
+                            // Duplicate match.
+                            return None;
+                        }
+
+                        match_offset = Some((offset, index))
+                    }
+                }
+
+                continue;
+            };
+            let first_byte = pattern[first_idx].value;
+
+            let haystack = &memory[first_idx..memory.len() - pattern.len() + first_idx + 1];
+            for offset in memchr::memchr_iter(first_byte, haystack) {
+                let window = &memory[offset..offset + pattern.len()];
+                if !matches_window(pattern, window) {
+                    continue;
+                }
+
+                // We have found a match.
+
+                if match_offset.is_some() {
+                    // Duplicate match.
+                    return None;
+                }
+
+                match_offset = Some((offset, index))
+            }
+        }
+
+        match_offset
+    }
+
+    /// Finds a unique pattern occurrence in memory at or after the `start` byte offset. Returns a
+    /// tuple of (byte offset, pattern index), where the offset is relative to the start of
+    /// `memory`.
+    ///
+    /// Like [`Patterns::find`], but matches before `start` are ignored, including for the
+    /// uniqueness check.
+    pub fn find_from(self, memory: &[u8], start: usize) -> Option<(usize, usize)> {
+        let (offset, index) = self.find(memory.get(start..)?)?;
+        Some((start + offset, index))
+    }
+
+    /// The naive implementation of [`Patterns::find`], checking every window of memory.
+    #[cfg(test)]
+    fn find_naive(self, memory: &[u8]) -> Option<(usize, usize)> {
+        if self.0.is_empty() {
+            return None;
+        }
+
+        let min_len = self.0.iter().map(|pattern| pattern.len()).min().unwrap();
+        if memory.len() < min_len {
+            return None;
+        }
+
+        let mut match_offset = None;
 
         // Try to match every pattern.
         for (index, pattern) in self.0.iter().enumerate() {
@@ -38,8 +142,8 @@ impl Patterns {
             'next_offset: for (offset, window) in memory.windows(pattern.len()).enumerate() {
                 // Check each byte of the window.
                 for (&mem, &pat) in window.iter().zip(pattern.iter()) {
-                    // If a pattern byte isn't equal to the memory byte,
-                    if matches!(pat, Some(byte) if byte != mem) {
+                    // If a pattern byte doesn't match the memory byte,
+                    if !pat.matches(mem) {
                         // try the next memory offset.
                         continue 'next_offset;
                     }
@@ -58,4 +162,202 @@ impl Patterns {
 
         match_offset
     }
+
+    /// Finds all pattern occurrences in memory. Returns a list of (byte offset, pattern index)
+    /// tuples.
+    ///
+    /// Unlike [`Patterns::find`], this does not require the match to be unique, which makes it
+    /// useful for diagnosing patterns that stopped resolving.
+    pub fn find_all(self, memory: &[u8]) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+
+        let Some(min_len) = self.0.iter().map(|pattern| pattern.len()).min() else {
+            return matches;
+        };
+        if memory.len() < min_len {
+            return matches;
+        }
+
+        for (index, pattern) in self.0.iter().enumerate() {
+            for (offset, window) in memory.windows(pattern.len()).enumerate() {
+                if matches_window(pattern, window) {
+                    matches.push((offset, index));
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+/// Returns whether the memory window matches the pattern.
+fn matches_window(pattern: &[MaskByte], window: &[u8]) -> bool {
+    window
+        .iter()
+        .zip(pattern.iter())
+        .all(|(&mem, &pat)| pat.matches(mem))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    const ANY: MaskByte = MaskByte::ANY;
+
+    const fn b(value: u8) -> MaskByte {
+        MaskByte::exact(value)
+    }
+
+    /// Deterministic pseudo-random bytes.
+    fn memory(len: usize) -> Vec<u8> {
+        let mut x = 0x1234_5678u32;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn find_matches_naive() {
+        static PATTERNS: &[&[MaskByte]] = &[
+            &[b(0xde), ANY, b(0xad), b(0xbe)],
+            &[ANY, ANY, b(0xef), b(0x01), ANY],
+        ];
+        let patterns = Patterns(PATTERNS);
+
+        let mut memory = memory(4096);
+        assert_eq!(patterns.find(&memory), patterns.find_naive(&memory));
+
+        memory[1000..1004].copy_from_slice(&[0xde, 0x00, 0xad, 0xbe]);
+        assert_eq!(patterns.find(&memory), Some((1000, 0)));
+        assert_eq!(patterns.find(&memory), patterns.find_naive(&memory));
+
+        // Duplicate match.
+        memory[3000..3004].copy_from_slice(&[0xde, 0xff, 0xad, 0xbe]);
+        assert_eq!(patterns.find(&memory), None);
+        assert_eq!(patterns.find_naive(&memory), None);
+
+        // Match right at the end of memory.
+        let mut memory = vec![0; 16];
+        memory[11..16].copy_from_slice(&[0, 0, 0xef, 0x01, 0]);
+        assert_eq!(patterns.find(&memory), Some((11, 1)));
+        assert_eq!(patterns.find(&memory), patterns.find_naive(&memory));
+
+        // Memory shorter than the patterns.
+        assert_eq!(patterns.find(&[0xde, 0, 0xad]), None);
+    }
+
+    #[test]
+    fn find_fully_skipped() {
+        static PATTERNS: &[&[MaskByte]] = &[&[ANY, ANY]];
+        let patterns = Patterns(PATTERNS);
+
+        assert_eq!(patterns.find(&[1, 2]), Some((0, 0)));
+        assert_eq!(patterns.find(&[1, 2, 3]), None);
+        assert_eq!(patterns.find(&[1, 2]), patterns.find_naive(&[1, 2]));
+        assert_eq!(patterns.find(&[1, 2, 3]), patterns.find_naive(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn find_from() {
+        static PATTERNS: &[&[MaskByte]] = &[&[b(0xde), ANY, b(0xad)]];
+        let patterns = Patterns(PATTERNS);
+
+        let mut memory = memory(4096);
+        memory[100..103].copy_from_slice(&[0xde, 0x00, 0xad]);
+        memory[2000..2003].copy_from_slice(&[0xde, 0x11, 0xad]);
+        assert_eq!(patterns.find(&memory), None);
+
+        assert_eq!(patterns.find_from(&memory, 101), Some((2000, 0)));
+        assert_eq!(patterns.find_from(&memory, 2000), Some((2000, 0)));
+        assert_eq!(patterns.find_from(&memory, 2001), None);
+        assert_eq!(patterns.find_from(&memory, 5000), None);
+    }
+
+    #[test]
+    fn find_all() {
+        static PATTERNS: &[&[MaskByte]] = &[&[b(0xaa), ANY, b(0xaa)], &[b(0xde), b(0xad)]];
+        let patterns = Patterns(PATTERNS);
+
+        // No matches.
+        assert_eq!(patterns.find_all(&[0x00; 16]), vec![]);
+        assert_eq!(patterns.find_all(&[0xaa]), vec![]);
+
+        // Several matches of both patterns, including overlapping ones.
+        let memory = [0xaa, 0x00, 0xaa, 0x00, 0xaa, 0xde, 0xad, 0x00, 0xde, 0xad];
+        assert_eq!(
+            patterns.find_all(&memory),
+            vec![(0, 0), (2, 0), (5, 1), (8, 1)]
+        );
+        assert_eq!(patterns.find(&memory), None);
+
+        // A single match is the same as the one find() returns.
+        let mut memory = vec![0; 4096];
+        memory[1234..1236].copy_from_slice(&[0xde, 0xad]);
+        assert_eq!(patterns.find_all(&memory), vec![(1234, 1)]);
+        assert_eq!(patterns.find(&memory), Some((1234, 1)));
+    }
+
+    #[test]
+    fn find_masked() {
+        static PATTERNS: &[&[MaskByte]] = &[&[b(0x8b), MaskByte::new(0x40, 0xF0), ANY]];
+        let patterns = Patterns(PATTERNS);
+
+        assert_eq!(patterns.find(&[0x8b, 0x4e, 0x00]), Some((0, 0)));
+        assert_eq!(patterns.find(&[0x00, 0x8b, 0x40, 0x00]), Some((1, 0)));
+        assert_eq!(patterns.find(&[0x8b, 0x5e, 0x00]), None);
+
+        static NIBBLES: &[&[MaskByte]] = &[&[MaskByte::new(0x0A, 0x0F)]];
+        let patterns = Patterns(NIBBLES);
+        assert_eq!(patterns.find(&[0x00, 0x3a, 0x11]), Some((1, 0)));
+        assert_eq!(patterns.find(&[0x2a, 0x3a]), None);
+        assert_eq!(
+            patterns.find(&[0x2a, 0x3a]),
+            patterns.find_naive(&[0x2a, 0x3a])
+        );
+    }
+
+    #[test]
+    fn find_benchmark() {
+        if std::env::var_os("RUN_SLOW_TESTS").is_none() {
+            return;
+        }
+
+        static PATTERNS: &[&[MaskByte]] = &[&[
+            b(0x55),
+            b(0x8b),
+            b(0xec),
+            ANY,
+            ANY,
+            b(0x12),
+            b(0x34),
+            b(0x56),
+            b(0x78),
+            b(0x9a),
+        ]];
+        let patterns = Patterns(PATTERNS);
+
+        let mut memory = memory(32 * 1024 * 1024);
+        let offset = memory.len() - 100;
+        memory[offset..offset + 10]
+            .copy_from_slice(&[0x55, 0x8b, 0xec, 0, 0, 0x12, 0x34, 0x56, 0x78, 0x9a]);
+
+        let start = Instant::now();
+        let naive = patterns.find_naive(&memory);
+        let naive_time = start.elapsed();
+
+        let start = Instant::now();
+        let fast = patterns.find(&memory);
+        let fast_time = start.elapsed();
+
+        eprintln!("naive: {naive_time:?}, fast: {fast_time:?}");
+        assert_eq!(naive, Some((offset, 0)));
+        assert_eq!(fast, naive);
+    }
 }
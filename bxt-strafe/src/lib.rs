@@ -1,4 +1,5 @@
 use std::f32::consts::{PI, TAU};
+use std::iter::zip;
 
 use arrayvec::ArrayVec;
 use glam::Vec3;
@@ -6,6 +7,7 @@ use hltas::types::*;
 use serde::{Deserialize, Serialize};
 
 mod steps;
+pub use steps::optimal_accel_yaw;
 use steps::*;
 
 /// Result of a trace operation.
@@ -30,6 +32,17 @@ pub enum Hull {
     Point,
 }
 
+impl Hull {
+    /// Returns the half-height of the hull, or the distance from its origin to its bottom.
+    pub fn half_height(self) -> f32 {
+        match self {
+            Hull::Standing => 36.,
+            Hull::Ducked => 18.,
+            Hull::Point => 0.,
+        }
+    }
+}
+
 /// The game world's tracing function.
 pub trait Trace {
     /// Traces a line from `start` to `end` according to `hull` and returns the outcome.
@@ -70,8 +83,49 @@ impl Player {
     }
 }
 
-/// Movement parameters.
+/// Bunnyhop cap behavior.
 #[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum BhopCapStrategy {
+    /// No bunnyhop cap.
+    #[default]
+    None,
+    /// The standard bunnyhop cap, using [`Parameters::bhop_cap_multiplier`] and
+    /// [`Parameters::bhop_cap_max_speed_scale`].
+    Standard,
+    /// A bunnyhop cap with custom values, for mods which cap differently.
+    Custom {
+        /// Speed multiplier applied when the speed is capped.
+        multiplier: f32,
+        /// Max speed is scaled by this value to get the speed above which the cap applies.
+        max_speed_scale: f32,
+    },
+}
+
+impl BhopCapStrategy {
+    /// Returns the value of this strategy as reported by [`Parameters::diff`].
+    fn as_f32(self) -> f32 {
+        match self {
+            BhopCapStrategy::None => 0.,
+            BhopCapStrategy::Standard => 1.,
+            BhopCapStrategy::Custom { .. } => 2.,
+        }
+    }
+
+    /// Returns the multiplier and the max speed scale of a custom bunnyhop cap, or zeros for the
+    /// other strategies.
+    fn custom_values(self) -> (f32, f32) {
+        match self {
+            BhopCapStrategy::Custom {
+                multiplier,
+                max_speed_scale,
+            } => (multiplier, max_speed_scale),
+            _ => (0., 0.),
+        }
+    }
+}
+
+/// Movement parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Parameters {
     pub frame_time: f32,
     pub max_velocity: f32,
@@ -86,12 +140,353 @@ pub struct Parameters {
     pub ent_gravity: f32,
     pub step_size: f32,
     pub bounce: f32,
-    pub bhop_cap: bool,
+    pub bhop_cap: BhopCapStrategy,
     pub bhop_cap_multiplier: f32,
     pub bhop_cap_max_speed_scale: f32,
     pub use_slow_down: bool,
     pub has_stamina: bool,
     pub duck_animation_slow_down: bool,
+    /// Fraction by which the wish speed is reduced, for example when walking.
+    ///
+    /// `0` means moving at full speed and `0.5` means moving at half speed.
+    pub wish_speed_reduction: f32,
+    /// Cap on the wish speed used for air acceleration.
+    ///
+    /// This is `30` in Half-Life, but some mods change or remove it.
+    pub air_accel_cap: f32,
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Self {
+            frame_time: 0.,
+            max_velocity: 0.,
+            max_speed: 0.,
+            stop_speed: 0.,
+            friction: 0.,
+            edge_friction: 0.,
+            ent_friction: 0.,
+            accelerate: 0.,
+            air_accelerate: 0.,
+            gravity: 0.,
+            ent_gravity: 0.,
+            step_size: 0.,
+            bounce: 0.,
+            bhop_cap: BhopCapStrategy::default(),
+            bhop_cap_multiplier: 0.,
+            bhop_cap_max_speed_scale: 0.,
+            use_slow_down: false,
+            has_stamina: false,
+            duck_animation_slow_down: false,
+            wish_speed_reduction: 0.,
+            // Keep the Half-Life behavior by default, a zero cap would disable air acceleration.
+            air_accel_cap: 30.,
+        }
+    }
+}
+
+impl Parameters {
+    /// Returns a builder for [`Parameters`], initialized with the default Half-Life values.
+    pub fn builder() -> ParametersBuilder {
+        ParametersBuilder::default()
+    }
+
+    /// Returns the fields which differ between `self` and `other`.
+    ///
+    /// Every entry is the field name, the value in `self` and the value in `other`. Boolean fields
+    /// are reported as `0` or `1`. The bunnyhop cap strategy is reported as `0` for
+    /// [`BhopCapStrategy::None`], `1` for [`BhopCapStrategy::Standard`] and `2` for
+    /// [`BhopCapStrategy::Custom`]. The values of a custom bunnyhop cap are reported as the
+    /// separate `bhop_cap_custom_multiplier` and `bhop_cap_custom_max_speed_scale` fields, which
+    /// are `0` for the other strategies.
+    pub fn diff(&self, other: &Parameters) -> Vec<(&'static str, f32, f32)> {
+        zip(self.fields(), other.fields())
+            .map(|((name, a), (_, b))| (name, a, b))
+            .filter(|(_, a, b)| a != b)
+            .collect()
+    }
+
+    /// Returns the value of the field with the given name, as reported by [`Parameters::diff`].
+    ///
+    /// Returns `None` if there's no field with this name.
+    pub fn get_by_name(&self, name: &str) -> Option<f32> {
+        self.fields()
+            .into_iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the names and values of all fields, in the format of [`Parameters::diff`].
+    fn fields(&self) -> [(&'static str, f32); 23] {
+        let (custom_multiplier, custom_max_speed_scale) = self.bhop_cap.custom_values();
+
+        [
+            ("frame_time", self.frame_time),
+            ("max_velocity", self.max_velocity),
+            ("max_speed", self.max_speed),
+            ("stop_speed", self.stop_speed),
+            ("friction", self.friction),
+            ("edge_friction", self.edge_friction),
+            ("ent_friction", self.ent_friction),
+            ("accelerate", self.accelerate),
+            ("air_accelerate", self.air_accelerate),
+            ("gravity", self.gravity),
+            ("ent_gravity", self.ent_gravity),
+            ("step_size", self.step_size),
+            ("bounce", self.bounce),
+            ("bhop_cap", self.bhop_cap.as_f32()),
+            ("bhop_cap_multiplier", self.bhop_cap_multiplier),
+            ("bhop_cap_max_speed_scale", self.bhop_cap_max_speed_scale),
+            ("bhop_cap_custom_multiplier", custom_multiplier),
+            ("bhop_cap_custom_max_speed_scale", custom_max_speed_scale),
+            ("use_slow_down", self.use_slow_down.into()),
+            ("has_stamina", self.has_stamina.into()),
+            (
+                "duck_animation_slow_down",
+                self.duck_animation_slow_down.into(),
+            ),
+            ("wish_speed_reduction", self.wish_speed_reduction),
+            ("air_accel_cap", self.air_accel_cap),
+        ]
+    }
+
+    /// Sets the field with the given name, as reported by [`Parameters::diff`], to `value`.
+    ///
+    /// Boolean fields are set to `true` for any non-zero value. The bunnyhop cap strategy is set to
+    /// [`BhopCapStrategy::None`] for `0`, to [`BhopCapStrategy::Custom`] for `2` and to
+    /// [`BhopCapStrategy::Standard`] for any other value. Setting `bhop_cap_custom_multiplier` or
+    /// `bhop_cap_custom_max_speed_scale` switches the strategy to [`BhopCapStrategy::Custom`].
+    /// When switching to it from another strategy, the custom values start out equal to the
+    /// standard ones. Returns `false` if there's no field with this name.
+    pub fn set_by_name(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "frame_time" => self.frame_time = value,
+            "max_velocity" => self.max_velocity = value,
+            "max_speed" => self.max_speed = value,
+            "stop_speed" => self.stop_speed = value,
+            "friction" => self.friction = value,
+            "edge_friction" => self.edge_friction = value,
+            "ent_friction" => self.ent_friction = value,
+            "accelerate" => self.accelerate = value,
+            "air_accelerate" => self.air_accelerate = value,
+            "gravity" => self.gravity = value,
+            "ent_gravity" => self.ent_gravity = value,
+            "step_size" => self.step_size = value,
+            "bounce" => self.bounce = value,
+            "bhop_cap" => {
+                if value == 0. {
+                    self.bhop_cap = BhopCapStrategy::None;
+                } else if value == 2. {
+                    self.make_bhop_cap_custom();
+                } else {
+                    self.bhop_cap = BhopCapStrategy::Standard;
+                }
+            }
+            "bhop_cap_multiplier" => self.bhop_cap_multiplier = value,
+            "bhop_cap_max_speed_scale" => self.bhop_cap_max_speed_scale = value,
+            "bhop_cap_custom_multiplier" => *self.make_bhop_cap_custom().0 = value,
+            "bhop_cap_custom_max_speed_scale" => *self.make_bhop_cap_custom().1 = value,
+            "use_slow_down" => self.use_slow_down = value != 0.,
+            "has_stamina" => self.has_stamina = value != 0.,
+            "duck_animation_slow_down" => self.duck_animation_slow_down = value != 0.,
+            "wish_speed_reduction" => self.wish_speed_reduction = value,
+            "air_accel_cap" => self.air_accel_cap = value,
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Switches the bunnyhop cap strategy to [`BhopCapStrategy::Custom`] if needed and returns
+    /// its multiplier and max speed scale.
+    fn make_bhop_cap_custom(&mut self) -> (&mut f32, &mut f32) {
+        if !matches!(self.bhop_cap, BhopCapStrategy::Custom { .. }) {
+            self.bhop_cap = BhopCapStrategy::Custom {
+                multiplier: self.bhop_cap_multiplier,
+                max_speed_scale: self.bhop_cap_max_speed_scale,
+            };
+        }
+
+        let BhopCapStrategy::Custom {
+            multiplier,
+            max_speed_scale,
+        } = &mut self.bhop_cap
+        else {
+            unreachable!()
+        };
+        (multiplier, max_speed_scale)
+    }
+}
+
+/// Builder for [`Parameters`].
+///
+/// Starts out with the default Half-Life values for 100 FPS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParametersBuilder {
+    parameters: Parameters,
+}
+
+impl Default for ParametersBuilder {
+    fn default() -> Self {
+        Self {
+            parameters: Parameters {
+                frame_time: 0.010000001,
+                max_velocity: 2000.,
+                max_speed: 320.,
+                stop_speed: 100.,
+                friction: 4.,
+                edge_friction: 2.,
+                ent_friction: 1.,
+                accelerate: 10.,
+                air_accelerate: 10.,
+                gravity: 800.,
+                ent_gravity: 1.,
+                step_size: 18.,
+                bounce: 1.,
+                bhop_cap: BhopCapStrategy::None,
+                bhop_cap_multiplier: 0.65,
+                bhop_cap_max_speed_scale: 1.7,
+                use_slow_down: true,
+                has_stamina: false,
+                duck_animation_slow_down: false,
+                wish_speed_reduction: 0.,
+                air_accel_cap: 30.,
+            },
+        }
+    }
+}
+
+impl ParametersBuilder {
+    /// Sets the frame time, in seconds.
+    pub fn frame_time(mut self, value: f32) -> Self {
+        self.parameters.frame_time = value;
+        self
+    }
+
+    /// Sets `sv_maxvelocity`.
+    pub fn max_velocity(mut self, value: f32) -> Self {
+        self.parameters.max_velocity = value;
+        self
+    }
+
+    /// Sets `sv_maxspeed`.
+    pub fn max_speed(mut self, value: f32) -> Self {
+        self.parameters.max_speed = value;
+        self
+    }
+
+    /// Sets `sv_stopspeed`.
+    pub fn stop_speed(mut self, value: f32) -> Self {
+        self.parameters.stop_speed = value;
+        self
+    }
+
+    /// Sets `sv_friction`.
+    pub fn friction(mut self, value: f32) -> Self {
+        self.parameters.friction = value;
+        self
+    }
+
+    /// Sets `edgefriction`.
+    pub fn edge_friction(mut self, value: f32) -> Self {
+        self.parameters.edge_friction = value;
+        self
+    }
+
+    /// Sets the player entity friction.
+    pub fn ent_friction(mut self, value: f32) -> Self {
+        self.parameters.ent_friction = value;
+        self
+    }
+
+    /// Sets `sv_accelerate`.
+    pub fn accelerate(mut self, value: f32) -> Self {
+        self.parameters.accelerate = value;
+        self
+    }
+
+    /// Sets `sv_airaccelerate`.
+    pub fn air_accelerate(mut self, value: f32) -> Self {
+        self.parameters.air_accelerate = value;
+        self
+    }
+
+    /// Sets `sv_gravity`.
+    pub fn gravity(mut self, value: f32) -> Self {
+        self.parameters.gravity = value;
+        self
+    }
+
+    /// Sets the player entity gravity.
+    pub fn ent_gravity(mut self, value: f32) -> Self {
+        self.parameters.ent_gravity = value;
+        self
+    }
+
+    /// Sets `sv_stepsize`.
+    pub fn step_size(mut self, value: f32) -> Self {
+        self.parameters.step_size = value;
+        self
+    }
+
+    /// Sets `sv_bounce`.
+    pub fn bounce(mut self, value: f32) -> Self {
+        self.parameters.bounce = value;
+        self
+    }
+
+    /// Sets the bunnyhop cap strategy.
+    pub fn bhop_cap(mut self, value: BhopCapStrategy) -> Self {
+        self.parameters.bhop_cap = value;
+        self
+    }
+
+    /// Sets the bunnyhop cap speed multiplier.
+    pub fn bhop_cap_multiplier(mut self, value: f32) -> Self {
+        self.parameters.bhop_cap_multiplier = value;
+        self
+    }
+
+    /// Sets the bunnyhop cap max speed scale.
+    pub fn bhop_cap_max_speed_scale(mut self, value: f32) -> Self {
+        self.parameters.bhop_cap_max_speed_scale = value;
+        self
+    }
+
+    /// Sets whether `+use` slows the player down.
+    pub fn use_slow_down(mut self, value: bool) -> Self {
+        self.parameters.use_slow_down = value;
+        self
+    }
+
+    /// Sets whether the game has stamina (like CS 1.6).
+    pub fn has_stamina(mut self, value: bool) -> Self {
+        self.parameters.has_stamina = value;
+        self
+    }
+
+    /// Sets whether the ducking animation slows the player down.
+    pub fn duck_animation_slow_down(mut self, value: bool) -> Self {
+        self.parameters.duck_animation_slow_down = value;
+        self
+    }
+
+    /// Sets the fraction by which the wish speed is reduced, for example when walking.
+    pub fn wish_speed_reduction(mut self, value: f32) -> Self {
+        self.parameters.wish_speed_reduction = value;
+        self
+    }
+
+    /// Sets the cap on the wish speed used for air acceleration.
+    pub fn air_accel_cap(mut self, value: f32) -> Self {
+        self.parameters.air_accel_cap = value;
+        self
+    }
+
+    /// Returns the built [`Parameters`].
+    pub fn build(self) -> Parameters {
+        self.parameters
+    }
 }
 
 /// The type of player's position in the world.
@@ -130,6 +525,22 @@ pub struct State {
     pub prev_frame_input: Input,
     pub jumped: bool,
     pub move_traces: ArrayVec<TraceResult, 4>,
+    /// Whether the velocity was clamped to [`Parameters::max_velocity`] during movement.
+    pub velocity_clamped: bool,
+    /// Whether the jump on this frame was a jumpbug done by the jumpbug auto action.
+    pub jumpbugged: bool,
+    /// Whether the player was stuck inside solid geometry during movement.
+    ///
+    /// The player position is not advanced by traces that start in solid geometry.
+    pub stuck: bool,
+    /// Normal of the ground plane under the player, `None` if the player is not on the ground.
+    pub ground_normal: Option<Vec3>,
+    /// Movement vector angle, in radians relative to the view yaw, requested by strafing on this
+    /// frame.
+    pub last_strafe_accel_angle: Option<f32>,
+    /// Movement vector angle, in radians relative to the view yaw, of the vectorial compensation
+    /// table entry that was used for strafing on this frame.
+    pub last_vct_entry_angle: Option<f32>,
     // Number of frames for [`StrafeDir::LeftRight`] or [`StrafeDir::RightLeft`] which goes from
     // `0` to `count - 1`.
     pub strafe_cycle_frame_count: u32,
@@ -140,8 +551,11 @@ pub struct State {
     pub prev_max_accel_yaw_offset_target: f32,
     pub prev_max_accel_yaw_offset_accel: f32,
     pub prev_max_accel_yaw_offset_right: bool,
-    // In case of yaw and pitch override, this might be useful.
-    pub rendered_viewangles: Vec3,
+    /// View angles in degrees that were rendered on this frame, which differ from the input view
+    /// angles in case of yaw and pitch override.
+    ///
+    /// Only known for frames played back in the game, `None` for predicted frames.
+    pub rendered_viewangles: Option<Vec3>,
 }
 
 impl State {
@@ -149,17 +563,23 @@ impl State {
         let mut rv = Self {
             player,
             place: Place::Air,
-            wish_speed: parameters.max_speed,
+            wish_speed: parameters.max_speed * (1. - parameters.wish_speed_reduction),
             prev_frame_input: Input::default(),
             jumped: false,
             move_traces: ArrayVec::new(),
+            velocity_clamped: false,
+            jumpbugged: false,
+            stuck: false,
+            ground_normal: None,
+            last_strafe_accel_angle: None,
+            last_vct_entry_angle: None,
             strafe_cycle_frame_count: 0,
             max_accel_yaw_offset_value: 0.,
             prev_max_accel_yaw_offset_start: 0.,
             prev_max_accel_yaw_offset_target: 0.,
             prev_max_accel_yaw_offset_accel: 0.,
             prev_max_accel_yaw_offset_right: false,
-            rendered_viewangles: Vec3::ZERO,
+            rendered_viewangles: None,
         };
 
         rv.update_place(tracer);
@@ -180,29 +600,95 @@ impl State {
         chain.simulate(tracer, parameters, frame_bulk, self, Input::default())
     }
 
-    fn update_place<T: Trace>(&mut self, tracer: &T) {
-        self.place = Place::Air;
+    /// Returns whether the player would be on the ground next frame.
+    ///
+    /// This is a cheap lookahead that only moves the player by their velocity with gravity applied
+    /// and checks for ground at the new position, without any acceleration or collision with walls.
+    /// It is meant for ground contact queries where running [`State::simulate`] is too expensive.
+    pub fn would_be_grounded_next_frame<T: Trace>(
+        &self,
+        tracer: &T,
+        parameters: Parameters,
+    ) -> bool {
+        let ent_gravity = if parameters.ent_gravity == 0. {
+            1.
+        } else {
+            parameters.ent_gravity
+        };
 
-        if self.player.vel.z > 180. {
-            return;
+        // Same as the gravity and position update in the Move step.
+        let mut player = self.player;
+        player.vel.z -= ent_gravity * parameters.gravity * 0.5 * parameters.frame_time;
+        if self.place == Place::Ground {
+            player.vel.z = 0.;
         }
+        player.pos += player.vel * parameters.frame_time;
 
-        let tr = tracer.trace(
-            self.player.pos,
-            self.player.pos - Vec3::new(0., 0., 2.),
-            self.player.hull(),
-        );
-        if tr.entity == -1 || tr.plane_normal.z < 0.7 {
+        ground_trace(tracer, &player).is_some()
+    }
+
+    /// Estimates the velocity that moved the player from `prev` to `curr` over one frame.
+    ///
+    /// This only inverts the position integration: the result is the displacement divided by the
+    /// frame time, minus the base velocity of `curr`. It does not invert friction or acceleration,
+    /// so it corresponds to the velocity during the movement rather than the velocity stored at the
+    /// end of the frame. Notably, in the air the stored vertical velocity additionally has the
+    /// second half of gravity applied, and on the ground it is reset to zero.
+    ///
+    /// The estimate is only accurate when the player moved freely. Collisions with walls, walking
+    /// up steps and snapping to the ground all change the position without a matching velocity.
+    /// Precision is also lost for positions far from the origin and for low frame times.
+    pub fn reconstruct_velocity(prev: &State, curr: &State, parameters: Parameters) -> Vec3 {
+        let displacement = curr.player.pos - prev.player.pos;
+        displacement / parameters.frame_time - curr.player.base_vel
+    }
+
+    /// Returns the error, in radians, between the movement vector angle requested by strafing and
+    /// the one achieved with the vectorial compensation table on this frame.
+    ///
+    /// Returns `None` if there was no strafing on this frame.
+    pub fn strafe_angle_error(&self) -> Option<f32> {
+        let requested = self.last_strafe_accel_angle?;
+        let achieved = self.last_vct_entry_angle?;
+        Some(normalize_rad(achieved - requested))
+    }
+
+    fn update_place<T: Trace>(&mut self, tracer: &T) {
+        self.place = Place::Air;
+        self.ground_normal = None;
+
+        let Some(tr) = ground_trace(tracer, &self.player) else {
             return;
-        }
+        };
 
         self.place = Place::Ground;
-        if !tr.start_solid && !tr.all_solid {
+        self.ground_normal = Some(tr.plane_normal);
+        if tr.start_solid || tr.all_solid {
+            self.stuck = true;
+        } else {
             self.player.pos = tr.end_pos;
         }
     }
 }
 
+/// Returns the trace to the ground under the player, or `None` if the player is not on the ground.
+fn ground_trace<T: Trace>(tracer: &T, player: &Player) -> Option<TraceResult> {
+    if player.vel.z > 180. {
+        return None;
+    }
+
+    let tr = tracer.trace(
+        player.pos,
+        player.pos - Vec3::new(0., 0., 2.),
+        player.hull(),
+    );
+    if tr.entity == -1 || tr.plane_normal.z < 0.7 {
+        return None;
+    }
+
+    Some(tr)
+}
+
 const U_RAD: f32 = PI / 32768.;
 const INV_U_RAD: f32 = 32768. / PI;
 
@@ -238,6 +724,122 @@ impl Trace for DummyTracer {
     }
 }
 
+/// A tracer that splits long traces into multiple shorter ones.
+///
+/// A single long trace can tunnel through thin geometry that the engine would have hit. This
+/// tracer subdivides every trace longer than `max_distance` into equal segments and stops at the
+/// first segment that hits something. The fraction of the returned result is relative to the
+/// whole trace.
+pub struct SubdividingTracer<'a, T> {
+    tracer: &'a T,
+    max_distance: f32,
+}
+
+impl<'a, T> SubdividingTracer<'a, T> {
+    /// Creates a new [`SubdividingTracer`] wrapping `tracer`.
+    ///
+    /// Traces longer than `max_distance` units are subdivided. Non-positive `max_distance` disables
+    /// the subdivision.
+    pub fn new(tracer: &'a T, max_distance: f32) -> Self {
+        Self {
+            tracer,
+            max_distance,
+        }
+    }
+}
+
+impl<T: Trace> Trace for SubdividingTracer<'_, T> {
+    fn trace(&self, start: Vec3, end: Vec3, hull: Hull) -> TraceResult {
+        let distance = start.distance(end);
+        if self.max_distance <= 0. || !distance.is_finite() || distance <= self.max_distance {
+            return self.tracer.trace(start, end, hull);
+        }
+
+        let count = (distance / self.max_distance).ceil() as u32;
+        let delta = (end - start) / count as f32;
+
+        let mut segment_start = start;
+        for i in 0..count {
+            let is_last = i + 1 == count;
+            let segment_end = if is_last {
+                end
+            } else {
+                start + delta * (i + 1) as f32
+            };
+
+            let mut tr = self.tracer.trace(segment_start, segment_end, hull);
+            if is_last || tr.fraction < 1. || tr.start_solid || tr.all_solid {
+                tr.fraction = (i as f32 + tr.fraction) / count as f32;
+                return tr;
+            }
+
+            segment_start = tr.end_pos;
+        }
+
+        unreachable!("the last segment always returns")
+    }
+}
+
+/// A tracer that only collides with an infinite horizontal ground plane.
+///
+/// This is much cheaper than tracing against the game world, but the results are only an
+/// approximation useful for quick previews. It is also handy for testing movement on flat ground.
+#[derive(Debug, Clone, Copy)]
+pub struct GroundPlaneTracer {
+    /// Z coordinate of the ground plane.
+    pub z: f32,
+}
+
+impl GroundPlaneTracer {
+    /// Creates a new [`GroundPlaneTracer`] with the ground plane at the bottom of the player's
+    /// bbox.
+    pub fn under_player(player: &Player) -> Self {
+        Self {
+            z: player.pos.z - player.hull().half_height(),
+        }
+    }
+}
+
+impl Trace for GroundPlaneTracer {
+    fn trace(&self, start: Vec3, end: Vec3, hull: Hull) -> TraceResult {
+        let half_height = hull.half_height();
+        let start_bottom = start.z - half_height;
+        let end_bottom = end.z - half_height;
+
+        if start_bottom < self.z {
+            return TraceResult {
+                all_solid: end_bottom < self.z,
+                start_solid: true,
+                fraction: 0.,
+                end_pos: start,
+                plane_normal: Vec3::Z,
+                entity: 0,
+            };
+        }
+
+        if end_bottom >= self.z {
+            return TraceResult {
+                all_solid: false,
+                start_solid: false,
+                fraction: 1.,
+                end_pos: end,
+                plane_normal: Vec3::ZERO,
+                entity: -1,
+            };
+        }
+
+        let fraction = (start_bottom - self.z) / (start_bottom - end_bottom);
+        TraceResult {
+            all_solid: false,
+            start_solid: false,
+            fraction,
+            end_pos: start + (end - start) * fraction,
+            plane_normal: Vec3::Z,
+            entity: 0,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::num::NonZeroU32;
@@ -277,12 +879,14 @@ mod tests {
             ent_gravity: 1.,
             step_size: 18.,
             bounce: 1.,
-            bhop_cap: false,
+            bhop_cap: BhopCapStrategy::None,
             bhop_cap_multiplier: 0.65,
             bhop_cap_max_speed_scale: 1.7,
             use_slow_down: true,
             has_stamina: false,
             duck_animation_slow_down: false,
+            wish_speed_reduction: 0.,
+            air_accel_cap: 30.,
         }
     }
 
@@ -356,6 +960,177 @@ mod tests {
         }
     }
 
+    #[test]
+    fn subdividing_tracer_short_trace() {
+        let world = World::new();
+        let tracer = SubdividingTracer::new(&world, 100.);
+
+        let start = Vec3::new(0., 0., 50.);
+        let end = Vec3::new(0., 0., -50.);
+        assert_eq!(
+            tracer.trace(start, end, Hull::Standing),
+            world.trace(start, end, Hull::Standing)
+        );
+    }
+
+    #[test]
+    fn subdividing_tracer_long_trace() {
+        let world = World::new();
+        let tracer = SubdividingTracer::new(&world, 10.);
+
+        let start = Vec3::new(0., 0., 50.);
+        let end = Vec3::new(0., 0., -50.);
+        let expected = world.trace(start, end, Hull::Standing);
+        let tr = tracer.trace(start, end, Hull::Standing);
+
+        assert!((tr.fraction - expected.fraction).abs() < 1e-3, "{tr:?}");
+        assert_eq!(tr.plane_normal, expected.plane_normal);
+        assert_eq!(tr.entity, expected.entity);
+    }
+
+    #[test]
+    fn subdividing_tracer_no_hit() {
+        let world = World::new();
+        let tracer = SubdividingTracer::new(&world, 10.);
+
+        let start = Vec3::new(0., 0., 50.);
+        let end = Vec3::new(100., 0., 50.);
+        let tr = tracer.trace(start, end, Hull::Standing);
+
+        assert_eq!(tr.fraction, 1.);
+        assert_eq!(tr.end_pos, end);
+    }
+
+    #[test]
+    fn ground_plane_tracer() {
+        let player = Player {
+            pos: Vec3::new(0., 0., 36.),
+            ..default_player()
+        };
+        let tracer = GroundPlaneTracer::under_player(&player);
+        assert_eq!(tracer.z, 0.);
+
+        // Moving down onto the plane.
+        let tr = tracer.trace(
+            Vec3::new(0., 0., 46.),
+            Vec3::new(10., 0., 26.),
+            Hull::Standing,
+        );
+        assert_eq!(tr.fraction, 0.5);
+        assert_eq!(tr.end_pos, Vec3::new(5., 0., 36.));
+        assert_eq!(tr.plane_normal, Vec3::Z);
+        assert_eq!(tr.entity, 0);
+
+        // Moving along the plane.
+        let tr = tracer.trace(
+            Vec3::new(0., 0., 36.),
+            Vec3::new(100., 0., 36.),
+            Hull::Standing,
+        );
+        assert_eq!(tr.fraction, 1.);
+        assert_eq!(tr.entity, -1);
+
+        // Starting below the plane.
+        let tr = tracer.trace(Vec3::ZERO, Vec3::new(0., 0., 10.), Hull::Ducked);
+        assert!(tr.start_solid);
+        assert!(tr.all_solid);
+    }
+
+    #[test]
+    fn parameters_builder_defaults() {
+        assert_eq!(Parameters::builder().build(), default_parameters());
+    }
+
+    #[test]
+    fn parameters_builder_setters() {
+        let parameters = Parameters::builder()
+            .frame_time(0.001)
+            .max_speed(250.)
+            .bhop_cap(BhopCapStrategy::Standard)
+            .build();
+
+        assert_eq!(
+            parameters,
+            Parameters {
+                frame_time: 0.001,
+                max_speed: 250.,
+                bhop_cap: BhopCapStrategy::Standard,
+                ..default_parameters()
+            }
+        );
+    }
+
+    #[test]
+    fn parameters_diff() {
+        let a = default_parameters();
+        assert!(a.diff(&a).is_empty());
+
+        let b = Parameters {
+            ent_friction: 0.5,
+            bhop_cap: BhopCapStrategy::Standard,
+            ..a
+        };
+        assert_eq!(
+            a.diff(&b),
+            vec![("ent_friction", 1., 0.5), ("bhop_cap", 0., 1.)]
+        );
+    }
+
+    #[test]
+    fn parameters_set_by_name() {
+        let mut parameters = default_parameters();
+        assert!(parameters.set_by_name("friction", 2.));
+        assert!(parameters.set_by_name("bhop_cap", 1.));
+        assert!(!parameters.set_by_name("nonexistent", 1.));
+
+        assert_eq!(parameters.get_by_name("friction"), Some(2.));
+        assert_eq!(parameters.get_by_name("bhop_cap"), Some(1.));
+        assert_eq!(parameters.get_by_name("nonexistent"), None);
+
+        assert_eq!(
+            default_parameters().diff(&parameters),
+            vec![("friction", 4., 2.), ("bhop_cap", 0., 1.)]
+        );
+    }
+
+    #[test]
+    fn parameters_custom_bhop_cap_by_name() {
+        let mut parameters = default_parameters();
+
+        // Switching to the custom cap starts from the standard values.
+        assert!(parameters.set_by_name("bhop_cap", 2.));
+        assert_eq!(
+            parameters.bhop_cap,
+            BhopCapStrategy::Custom {
+                multiplier: 0.65,
+                max_speed_scale: 1.7,
+            }
+        );
+
+        let before = parameters;
+        assert!(parameters.set_by_name("bhop_cap_custom_multiplier", 0.5));
+        assert_eq!(parameters.get_by_name("bhop_cap"), Some(2.));
+        assert_eq!(
+            parameters.get_by_name("bhop_cap_custom_multiplier"),
+            Some(0.5)
+        );
+        assert_eq!(
+            before.diff(&parameters),
+            vec![("bhop_cap_custom_multiplier", 0.65, 0.5)]
+        );
+
+        // Setting a custom value switches to the custom cap.
+        let mut parameters = default_parameters();
+        assert!(parameters.set_by_name("bhop_cap_custom_max_speed_scale", 2.));
+        assert_eq!(
+            parameters.bhop_cap,
+            BhopCapStrategy::Custom {
+                multiplier: 0.65,
+                max_speed_scale: 2.,
+            }
+        );
+    }
+
     #[test]
     fn stand_still_on_ground() {
         let world = World::new();
@@ -420,6 +1195,394 @@ mod tests {
         assert!(state.player.pos.z.abs() >= 1e-5);
     }
 
+    #[test]
+    fn stand_still_on_ground_plane() {
+        let tracer = GroundPlaneTracer { z: 0. };
+        let parameters = default_parameters();
+        let player = Player {
+            pos: Vec3::new(0., 0., 36.),
+            ..default_player()
+        };
+        let state = State::new(&tracer, parameters, player);
+        assert_eq!(state.place, Place::Ground);
+
+        let new_state = state
+            .clone()
+            .simulate(
+                &tracer,
+                parameters,
+                &FrameBulk::with_frame_time("0.010000001".to_owned()),
+            )
+            .0;
+
+        assert_eq!(state.player, new_state.player);
+    }
+
+    #[test]
+    fn snap_to_ground_plane_from_one_unit() {
+        let tracer = GroundPlaneTracer { z: 0. };
+        let parameters = default_parameters();
+
+        for (hull_half_height, ducking) in [(36., false), (18., true)] {
+            let player = Player {
+                pos: Vec3::new(0., 0., hull_half_height + 1.),
+                ducking,
+                ..default_player()
+            };
+            let state = State::new(&tracer, parameters, player);
+
+            // Hold duck to stay ducked.
+            let mut frame_bulk = FrameBulk::with_frame_time("0.010000001".to_owned());
+            frame_bulk.action_keys.duck = ducking;
+            let state = state.simulate(&tracer, parameters, &frame_bulk).0;
+
+            assert_eq!(state.player.ducking, ducking);
+            assert!((state.player.pos.z - hull_half_height).abs() < 1e-5);
+        }
+    }
+
+    /// Step that ends the simulation chain without changing anything.
+    struct End;
+
+    impl Step for End {
+        fn simulate<T: Trace>(
+            &self,
+            _tracer: &T,
+            _parameters: Parameters,
+            _frame_bulk: &FrameBulk,
+            state: State,
+            input: Input,
+        ) -> (State, Input) {
+            (state, input)
+        }
+    }
+
+    /// Jumps on the ground at 1000 ups and returns the resulting horizontal speed.
+    fn speed_after_jump(bhop_cap: BhopCapStrategy) -> f32 {
+        let world = World::new();
+        let parameters = Parameters {
+            bhop_cap,
+            ..default_parameters()
+        };
+        let player = Player {
+            pos: Vec3::new(0., 0., 1.),
+            vel: Vec3::new(1000., 0., 0.),
+            ..default_player()
+        };
+        let state = State::new(&world, parameters, player);
+        assert_eq!(state.place, Place::Ground);
+
+        let input = Input {
+            jump: true,
+            ..Input::default()
+        };
+        let frame_bulk = FrameBulk::with_frame_time("0.010000001".to_owned());
+        let (state, _) = Jump(End).simulate(&world, parameters, &frame_bulk, state, input);
+
+        assert!(state.jumped);
+        state.player.vel.truncate().length()
+    }
+
+    #[test]
+    fn bhop_cap_none() {
+        assert_eq!(speed_after_jump(BhopCapStrategy::None), 1000.);
+    }
+
+    #[test]
+    fn bhop_cap_standard() {
+        // 320 * 1.7 * 0.65
+        let speed = speed_after_jump(BhopCapStrategy::Standard);
+        assert!((speed - 353.6).abs() < 1e-3, "{speed}");
+    }
+
+    #[test]
+    fn bhop_cap_custom() {
+        // 320 * 1.2 * 0.8
+        let speed = speed_after_jump(BhopCapStrategy::Custom {
+            multiplier: 0.8,
+            max_speed_scale: 1.2,
+        });
+        assert!((speed - 307.2).abs() < 1e-3, "{speed}");
+    }
+
+    #[test]
+    fn velocity_clamped() {
+        let world = World::new();
+        let parameters = default_parameters();
+        let frame_bulk = FrameBulk::with_frame_time("0.010000001".to_owned());
+
+        let player = Player {
+            pos: Vec3::new(0., 0., 1000.),
+            vel: Vec3::new(3000., 0., 0.),
+            ..default_player()
+        };
+        let state = State::new(&world, parameters, player);
+        let (state, _) = state.simulate(&world, parameters, &frame_bulk);
+        assert!(state.velocity_clamped);
+        assert_eq!(state.player.vel.x, parameters.max_velocity);
+
+        let player = Player {
+            pos: Vec3::new(0., 0., 1000.),
+            vel: Vec3::new(1000., 0., 0.),
+            ..default_player()
+        };
+        let state = State::new(&world, parameters, player);
+        let (state, _) = state.simulate(&world, parameters, &frame_bulk);
+        assert!(!state.velocity_clamped);
+    }
+
+    #[test]
+    fn jumpbug_sets_jumpbugged() {
+        let world = World::new();
+        let parameters = default_parameters();
+        let player = Player {
+            pos: Vec3::new(0., 0., 1.),
+            ducking: true,
+            ..default_player()
+        };
+
+        // In the game, the ducked hull can be in the air while the standing hull at the same spot
+        // would be on the ground. The test world places both hulls by their bottom, so emulate
+        // this by forcing the place.
+        let mut state = State::new(&world, parameters, player);
+        state.place = Place::Air;
+
+        let frame_bulk = FrameBulk {
+            auto_actions: AutoActions {
+                jump_bug: Some(hltas::types::JumpBug {
+                    times: Times::UnlimitedWithinFrameBulk,
+                }),
+                ..Default::default()
+            },
+            ..FrameBulk::with_frame_time("0.010000001".to_owned())
+        };
+
+        let (new_state, input) = state.clone().simulate(&world, parameters, &frame_bulk);
+        assert!(input.jump);
+        assert!(new_state.jumped);
+        assert!(new_state.jumpbugged);
+
+        // The flag is reset on the next frame.
+        let (new_state, _) = new_state.simulate(&world, parameters, &frame_bulk);
+        assert!(!new_state.jumpbugged);
+
+        // Without the jumpbug auto action there is no jumpbug.
+        let frame_bulk = FrameBulk::with_frame_time("0.010000001".to_owned());
+        let (new_state, _) = state.simulate(&world, parameters, &frame_bulk);
+        assert!(!new_state.jumpbugged);
+    }
+
+    struct SolidTracer;
+
+    impl Trace for SolidTracer {
+        fn trace(&self, start: Vec3, _end: Vec3, _hull: Hull) -> TraceResult {
+            TraceResult {
+                all_solid: true,
+                start_solid: true,
+                fraction: 0.,
+                end_pos: start,
+                plane_normal: Vec3::Z,
+                entity: 0,
+            }
+        }
+    }
+
+    #[test]
+    fn start_solid_marks_stuck() {
+        let parameters = default_parameters();
+        let player = Player {
+            pos: Vec3::new(0., 0., 100.),
+            vel: Vec3::new(100., 0., 0.),
+            ..default_player()
+        };
+        let frame_bulk = FrameBulk::with_frame_time("0.010000001".to_owned());
+
+        let state = State::new(&SolidTracer, parameters, player);
+        let (new_state, _) = state.simulate(&SolidTracer, parameters, &frame_bulk);
+        assert!(new_state.stuck);
+        assert_eq!(new_state.player.pos, player.pos);
+
+        let state = State::new(&DummyTracer, parameters, player);
+        let (new_state, _) = state.simulate(&DummyTracer, parameters, &frame_bulk);
+        assert!(!new_state.stuck);
+        assert_ne!(new_state.player.pos, player.pos);
+    }
+
+    #[test]
+    fn optimal_accel_yaw_low_speed() {
+        // Ground, accel speed 10 * 320 * 0.01 = 32 is below 320 - 100, any angle up to this one
+        // accelerates fully.
+        let theta = optimal_accel_yaw(Vec3::new(100., 0., 0.), 320., 10. * 320., 0.01);
+        assert_eq!(theta, 0.);
+
+        // Standing still.
+        let theta = optimal_accel_yaw(Vec3::ZERO, 320., 10. * 320., 0.01);
+        assert_eq!(theta, 0.);
+
+        // No acceleration at all.
+        let theta = optimal_accel_yaw(Vec3::new(100., 0., 0.), 320., 0., 0.01);
+        assert_eq!(theta, PI);
+    }
+
+    #[test]
+    fn optimal_accel_yaw_high_speed() {
+        // Ground: acos((320 - 32) / 1000).
+        let theta = optimal_accel_yaw(Vec3::new(1000., 0., 0.), 320., 10. * 320., 0.01);
+        assert!((theta - 1.278_659).abs() < 1e-5, "{theta}");
+
+        // Air with a low air accelerate: acos((30 - 3.2) / 1000).
+        let theta = optimal_accel_yaw(Vec3::new(0., 1000., 0.), 30., 320., 0.01);
+        assert!((theta - 1.543_993).abs() < 1e-5, "{theta}");
+
+        // Air with the default air accelerate, accel speed is above the cap.
+        let theta = optimal_accel_yaw(Vec3::new(1000., 0., 0.), 30., 10. * 320., 0.01);
+        assert_eq!(theta, std::f32::consts::FRAC_PI_2);
+    }
+
+    fn ground_speed_after_running(wish_speed_reduction: f32) -> f32 {
+        let world = World::new();
+        let parameters = Parameters {
+            wish_speed_reduction,
+            ..default_parameters()
+        };
+        let player = Player {
+            pos: Vec3::new(0., 0., 1.),
+            ..default_player()
+        };
+        let mut state = State::new(&world, parameters, player);
+        assert_eq!(state.place, Place::Ground);
+
+        let input = Input {
+            forward: 400.,
+            ..Input::default()
+        };
+        let frame_bulk = FrameBulk::with_frame_time("0.010000001".to_owned());
+        for _ in 0..200 {
+            state = ResetFields(Friction(Move))
+                .simulate(&world, parameters, &frame_bulk, state, input)
+                .0;
+        }
+
+        state.player.vel.truncate().length()
+    }
+
+    #[test]
+    fn wish_speed_reduction() {
+        let full = ground_speed_after_running(0.);
+        let half = ground_speed_after_running(0.5);
+        assert!((full - 320.).abs() < 1e-3, "{full}");
+        assert!((half - 160.).abs() < 1e-3, "{half}");
+    }
+
+    fn air_speed_gain(air_accel_cap: f32) -> f32 {
+        let world = World::new();
+        let parameters = Parameters {
+            air_accel_cap,
+            ..default_parameters()
+        };
+        let player = Player {
+            pos: Vec3::new(0., 0., 100.),
+            vel: Vec3::new(300., 0., 0.),
+            ..default_player()
+        };
+        let state = State::new(&world, parameters, player);
+        assert_eq!(state.place, Place::Air);
+
+        // Accelerate perpendicular to the velocity.
+        let input = Input {
+            side: 400.,
+            ..Input::default()
+        };
+        let frame_bulk = FrameBulk::with_frame_time("0.010000001".to_owned());
+        let (state, _) = Move.simulate(&world, parameters, &frame_bulk, state, input);
+
+        state.player.vel.truncate().length() - 300.
+    }
+
+    #[test]
+    fn air_accel_cap() {
+        let standard = air_speed_gain(30.);
+        let raised = air_speed_gain(60.);
+
+        // sqrt(300^2 + 30^2) - 300
+        assert!((standard - 1.496).abs() < 1e-2, "{standard}");
+        // Accel speed is 10 * 320 * 0.01 = 32, which is now below the cap.
+        // sqrt(300^2 + 32^2) - 300
+        assert!((raised - 1.702).abs() < 1e-2, "{raised}");
+        assert!(raised > standard);
+    }
+
+    #[test]
+    fn ground_normal() {
+        let world = World::new();
+        let parameters = default_parameters();
+
+        let player = Player {
+            pos: Vec3::new(0., 0., 1.),
+            ..default_player()
+        };
+        let state = State::new(&world, parameters, player);
+        assert_eq!(state.place, Place::Ground);
+        assert_eq!(state.ground_normal, Some(Vec3::Z));
+
+        let player = Player {
+            pos: Vec3::new(0., 0., 100.),
+            ..default_player()
+        };
+        let state = State::new(&world, parameters, player);
+        assert_eq!(state.ground_normal, None);
+    }
+
+    #[test]
+    fn would_be_grounded_next_frame() {
+        let world = World::new();
+        let parameters = default_parameters();
+
+        // Falling onto the ground.
+        let player = Player {
+            pos: Vec3::new(0., 0., 3.),
+            vel: Vec3::new(0., 0., -100.),
+            ..default_player()
+        };
+        let state = State::new(&world, parameters, player);
+        assert_eq!(state.place, Place::Air);
+        assert!(state.would_be_grounded_next_frame(&world, parameters));
+
+        // Still high up in the air.
+        let player = Player {
+            pos: Vec3::new(0., 0., 100.),
+            vel: Vec3::new(0., 0., -100.),
+            ..default_player()
+        };
+        let state = State::new(&world, parameters, player);
+        assert!(!state.would_be_grounded_next_frame(&world, parameters));
+
+        // Jumping off the ground.
+        let player = Player {
+            vel: Vec3::new(0., 0., 268.),
+            ..default_player()
+        };
+        let state = State::new(&world, parameters, player);
+        assert_eq!(state.place, Place::Air);
+        assert!(!state.would_be_grounded_next_frame(&world, parameters));
+    }
+
+    #[test]
+    fn strafe_angle_error() {
+        let mut state = State::default();
+        assert_eq!(state.strafe_angle_error(), None);
+
+        state.last_strafe_accel_angle = Some(0.5);
+        state.last_vct_entry_angle = Some(0.25);
+        assert_eq!(state.strafe_angle_error(), Some(-0.25));
+
+        // The error wraps around ±PI.
+        state.last_strafe_accel_angle = Some(PI - 0.1);
+        state.last_vct_entry_angle = Some(-PI + 0.1);
+        let error = state.strafe_angle_error().unwrap();
+        assert!((error - 0.2).abs() < 1e-5, "{error}");
+    }
+
     #[test]
     fn autojump_works() {
         let world = World::new();
@@ -492,6 +1655,32 @@ mod tests {
             }
         }
 
+        #[test]
+        fn reconstruct_velocity_on_flat_ground(
+            pos in (-1000f32..1000., -1000f32..1000.).prop_map(|(x, y)| Vec3::new(x, y, 0.)),
+            vel in (-1000f32..1000., -1000f32..1000.).prop_map(|(x, y)| Vec3::new(x, y, 0.)),
+        ) {
+            let world = World::new();
+            let parameters = default_parameters();
+            let player = Player {
+                pos, vel, ..default_player()
+            };
+
+            let frame_bulk = FrameBulk::with_frame_time("0.010000001".to_owned());
+
+            let prev = State::new(&world, parameters, player);
+            prop_assume!(prev.place == Place::Ground);
+            let curr = prev.clone().simulate(&world, parameters, &frame_bulk).0;
+            prop_assume!(curr.place == Place::Ground);
+
+            let reconstructed = State::reconstruct_velocity(&prev, &curr, parameters);
+            prop_assert!(
+                reconstructed.distance(curr.player.vel) < 0.05,
+                "reconstructed {reconstructed}, actual {}",
+                curr.player.vel
+            );
+        }
+
         #[test]
         fn player_eventually_reaches_zero_velocity(
             // Smaller values for faster convergence.
@@ -57,6 +57,8 @@ fn fly_move<T: Trace>(tracer: &T, parameters: Parameters, state: &mut State) {
         total_fraction += tr.fraction;
 
         if tr.all_solid {
+            // The player is stuck inside solid geometry and can't move.
+            state.stuck = true;
             player.vel = Vec3::ZERO;
             break;
         }
@@ -139,6 +141,15 @@ fn clamp_velocity(velocity: Vec3, max: f32) -> Vec3 {
     velocity.clamp(-Vec3::splat(max), Vec3::splat(max))
 }
 
+/// Clamps the player velocity and records whether the clamp was active.
+fn clamp_player_velocity(state: &mut State, max: f32) {
+    let clamped = clamp_velocity(state.player.vel, max);
+    if clamped != state.player.vel {
+        state.velocity_clamped = true;
+    }
+    state.player.vel = clamped;
+}
+
 pub struct Move;
 
 impl Step for Move {
@@ -150,7 +161,8 @@ impl Step for Move {
         mut state: State,
         input: Input,
     ) -> (State, Input) {
-        state.player.vel = clamp_velocity(state.player.vel, parameters.max_velocity);
+        state.velocity_clamped = false;
+        clamp_player_velocity(&mut state, parameters.max_velocity);
 
         // AddCorrectGravity()
         let ent_gravity = parameters
@@ -159,7 +171,7 @@ impl Step for Move {
         state.player.vel.z -= ent_gravity * parameters.gravity * 0.5 * parameters.frame_time;
         state.player.vel.z += state.player.base_vel.z * parameters.frame_time;
         state.player.base_vel.z = 0.;
-        state.player.vel = clamp_velocity(state.player.vel, parameters.max_velocity);
+        clamp_player_velocity(&mut state, parameters.max_velocity);
 
         // Move()
         if state.place == Place::Ground {
@@ -175,7 +187,7 @@ impl Step for Move {
         let wish_speed_capped = if state.place == Place::Ground {
             state.wish_speed
         } else {
-            30.
+            parameters.air_accel_cap
         };
         let tmp = wish_speed_capped - state.player.vel.xy().dot(accel_dir);
         if tmp > 0. {
@@ -243,14 +255,14 @@ impl Step for Move {
 
         state.update_place(tracer);
         state.player.vel -= state.player.base_vel;
-        state.player.vel = clamp_velocity(state.player.vel, parameters.max_velocity);
+        clamp_player_velocity(&mut state, parameters.max_velocity);
 
         match state.place {
             Place::Air => {
                 // FixupGravityVelocity()
                 state.player.vel.z -=
                     ent_gravity * parameters.gravity * 0.5 * parameters.frame_time;
-                state.player.vel = clamp_velocity(state.player.vel, parameters.max_velocity);
+                clamp_player_velocity(&mut state, parameters.max_velocity);
             }
             Place::Ground => {
                 state.player.vel.z = 0.;
@@ -271,27 +283,44 @@ fn max_accel_theta(parameters: Parameters, state: &State) -> f32 {
         parameters.air_accelerate
     };
 
-    let accel_speed = accel * state.wish_speed * parameters.ent_friction * parameters.frame_time;
+    let wish_speed_capped = if state.place == Place::Ground {
+        state.wish_speed
+    } else {
+        parameters.air_accel_cap
+    };
+
+    optimal_accel_yaw(
+        state.player.vel,
+        wish_speed_capped,
+        accel * state.wish_speed * parameters.ent_friction,
+        parameters.frame_time,
+    )
+}
+
+/// Returns the angle, in radians, between the horizontal velocity and the acceleration direction
+/// that gives the maximal horizontal acceleration.
+///
+/// `wish_speed` is the wish speed after capping, which in the air is
+/// [`Parameters::air_accel_cap`]. `max_accel` is the acceleration in units per second squared,
+/// which is the (air) accelerate multiplied by the uncapped wish speed and the entity friction.
+///
+/// The returned angle is non-negative; negate it to strafe to the right.
+pub fn optimal_accel_yaw(vel: Vec3, wish_speed: f32, max_accel: f32, frame_time: f32) -> f32 {
+    let accel_speed = max_accel * frame_time;
     if accel_speed <= 0. {
         return PI;
     }
 
-    if state.player.vel.xy() == Vec2::ZERO {
+    if vel.xy() == Vec2::ZERO {
         return 0.;
     }
 
-    let wish_speed_capped = if state.place == Place::Ground {
-        state.wish_speed
-    } else {
-        30.
-    };
-
-    let tmp = wish_speed_capped - accel_speed;
+    let tmp = wish_speed - accel_speed;
     if tmp <= 0. {
         return FRAC_PI_2;
     }
 
-    let speed = state.player.vel.xy().length();
+    let speed = vel.xy().length();
     if tmp < speed {
         return (tmp / speed).acos();
     }
@@ -419,7 +448,7 @@ impl<S: Step> Step for Strafe<S> {
                     Vct::MAX_SPEED_CAP
                 );
 
-                let (camera_yaw, entry) = if let StrafeType::ConstYawspeed(yawspeed) = type_ {
+                let (camera_yaw, accel_angle) = if let StrafeType::ConstYawspeed(yawspeed) = type_ {
                     let right = matches!(dir, StrafeDir::Right);
                     let yaw_delta = (yawspeed * parameters.frame_time).to_radians();
 
@@ -435,18 +464,20 @@ impl<S: Step> Step for Strafe<S> {
                     };
 
                     let camera_yaw = angle_mod_rad(camera_yaw);
-                    let entry = Vct::get().find_best(accel_angle);
 
-                    (camera_yaw, entry)
+                    (camera_yaw, accel_angle)
                 } else {
                     // TODO: target_yaw velocity_lock
 
                     let camera_yaw = angle_mod_rad(vel_yaw);
-                    let entry = Vct::get().find_best((vel_yaw + theta) - camera_yaw);
 
-                    (camera_yaw, entry)
+                    (camera_yaw, (vel_yaw + theta) - camera_yaw)
                 };
 
+                let entry = Vct::get().find_best(accel_angle);
+                state.last_strafe_accel_angle = Some(accel_angle);
+                state.last_vct_entry_angle = Some(entry.angle_radians());
+
                 let camera_yaw = if matches!(type_, StrafeType::MaxAccelYawOffset { .. }) {
                     // theta < 0. = is right
                     // If is right then we decreases yaw by offset.
@@ -543,8 +574,13 @@ impl<S: Step> Step for ResetFields<S> {
             input.pitch = pitch.to_radians();
         }
 
-        state.wish_speed = parameters.max_speed;
+        state.wish_speed = parameters.max_speed * (1. - parameters.wish_speed_reduction);
         state.jumped = false;
+        state.jumpbugged = false;
+        state.stuck = false;
+        state.last_strafe_accel_angle = None;
+        state.last_vct_entry_angle = None;
+        state.rendered_viewangles = None;
         state.move_traces = ArrayVec::new();
 
         if !matches!(
@@ -622,13 +658,24 @@ impl<S: Step> Step for Jump<S> {
         if input.jump && !state.prev_frame_input.jump && state.place == Place::Ground {
             state.jumped = true;
 
-            if parameters.bhop_cap {
-                let max_scaled_speed = parameters.bhop_cap_max_speed_scale * parameters.max_speed;
+            let bhop_cap = match parameters.bhop_cap {
+                BhopCapStrategy::None => None,
+                BhopCapStrategy::Standard => Some((
+                    parameters.bhop_cap_multiplier,
+                    parameters.bhop_cap_max_speed_scale,
+                )),
+                BhopCapStrategy::Custom {
+                    multiplier,
+                    max_speed_scale,
+                } => Some((multiplier, max_speed_scale)),
+            };
+
+            if let Some((multiplier, max_speed_scale)) = bhop_cap {
+                let max_scaled_speed = max_speed_scale * parameters.max_speed;
                 if max_scaled_speed > 0. {
                     let speed = state.player.vel.length();
                     if speed > max_scaled_speed {
-                        state.player.vel *=
-                            (max_scaled_speed / speed) * parameters.bhop_cap_multiplier;
+                        state.player.vel *= (max_scaled_speed / speed) * multiplier;
                     }
                 }
             }
@@ -1044,10 +1091,11 @@ impl<S: Step> Step for JumpBug<S> {
             }
 
             input.jump = true;
-            let do_action = self
+            let mut do_action = self
                 .0
                 .simulate(tracer, parameters, frame_bulk, state, input);
             if do_action.0.jumped {
+                do_action.0.jumpbugged = true;
                 do_action
             } else {
                 do_nothing
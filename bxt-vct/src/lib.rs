@@ -3,11 +3,12 @@
 //! This is extracted into a separate crate to be able to compile it with optimizations even in
 //! debug builds.
 
-use std::f32::consts::{PI, TAU};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_8, PI, TAU};
 use std::sync::Once;
 
 use arrayvec::ArrayVec;
 use ordered_float::NotNan;
+use rayon::prelude::*;
 
 /// VCT entry.
 #[derive(Debug, Clone, Copy)]
@@ -22,6 +23,37 @@ pub struct Entry {
     pub angle: NotNan<f32>,
 }
 
+impl Entry {
+    /// Returns the movement vector angle, in radians, as a plain float.
+    pub fn angle_radians(&self) -> f32 {
+        self.angle.into_inner()
+    }
+}
+
+/// Angular gap between two consecutive VCT entries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gap {
+    /// Angle of the entry before the gap, in radians.
+    pub start: f32,
+    /// Angle of the entry after the gap, in radians.
+    ///
+    /// For the gap wrapping around from the last entry to the first one, this is above π.
+    pub end: f32,
+}
+
+impl Gap {
+    /// Returns the size of the gap, in radians.
+    pub fn size(&self) -> f32 {
+        self.end - self.start
+    }
+
+    /// Returns whether the middle of the gap is closer to an axis than to a diagonal.
+    pub fn is_near_axis(&self) -> bool {
+        let middle = ((self.start + self.end) / 2.).rem_euclid(FRAC_PI_2);
+        middle.min(FRAC_PI_2 - middle) < FRAC_PI_8
+    }
+}
+
 /// Vectorial compensation table.
 ///
 /// Instances of this type are HUGE (~78 MB), never put them on the stack. They are not
@@ -62,62 +94,26 @@ impl Vct {
         }
     }
 
-    fn add_combinations(&mut self, f: i16, s: i16) {
-        for (f, s) in [
-            (f, s),
-            (f, -s),
-            (-f, s),
-            (-f, -s),
-            (s, f),
-            (s, -f),
-            (-s, f),
-            (-s, -f),
-        ] {
-            self.entries.push(Entry {
-                forward: f,
-                side: s,
-                angle: NotNan::new((-s as f32).atan2(f as f32)).unwrap(),
-            })
-        }
-    }
-
     fn compute(&mut self) {
         let _span = tracing::info_span!("Vct::compute").entered();
 
         eprintln!("Computing the vectorial compensation table.");
 
-        /// Maximal value for forwardmove and sidemove.
-        const MAX_MOVE: i16 = 2047;
-
-        // Compute the Farey sequence in ascending order, starting from 0 / 1 and 1 / MAX_MOVE.
-        // This produces all co-prime F and S in the first octant (angles from -90 to -45 degrees).
-        let mut f = 0;
-        let mut s = 1;
-        let mut p = 1;
-        let mut q = MAX_MOVE;
-
-        while p != 1 || q != 1 {
-            let k = (MAX_MOVE + s) / q;
-            let tmp_f = f;
-            let tmp_s = s;
-            f = p;
-            s = q;
-            p = k * p - tmp_f;
-            q = k * q - tmp_s;
-
-            // Scale f and s to be as large as possible.
-            let fac = MAX_MOVE / s;
-            let scaled_f = f * fac;
-            let scaled_s = s * fac;
-
-            self.add_combinations(scaled_f, scaled_s);
-        }
+        for_each_combination(|forward, side| {
+            self.entries.push(Entry {
+                forward,
+                side,
+                // Computed in parallel below.
+                angle: NotNan::default(),
+            })
+        });
 
-        // Add 0 and PI / 4 angles omitted in the loop above.
-        self.add_combinations(0, 2047);
-        self.add_combinations(2047, 2047);
+        compute_angles_and_sort(&mut self.entries);
+    }
 
-        self.entries.sort_unstable_by_key(|entry| entry.angle);
+    /// Returns all VCT entries, sorted by angle.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
     }
 
     /// Finds and returns the VCT entry giving the closest angle to accel_angle.
@@ -145,6 +141,130 @@ impl Vct {
             }
         }
     }
+
+    /// Returns the largest angular gap between consecutive VCT entries.
+    ///
+    /// Target angles inside this gap have the largest error. The gap wrapping around from the last
+    /// entry to the first one is considered too. Returns `None` if the table is empty.
+    pub fn max_gap(&self) -> Option<Gap> {
+        max_gap(&self.entries)
+    }
+
+    /// Finds and returns up to `n` VCT entries closest to accel_angle, ordered by the angular
+    /// distance.
+    ///
+    /// At most 8 entries are returned. The angles are in radians.
+    pub fn find_best_n(&self, accel_angle: f32, n: usize) -> ArrayVec<Entry, 8> {
+        let accel_angle = normalize_rad(accel_angle);
+        let distance = |entry: &Entry| normalize_rad(entry.angle_radians() - accel_angle).abs();
+
+        let mut rv = ArrayVec::new();
+        let len = self.entries.len();
+        let n = n.min(rv.capacity()).min(len);
+        if n == 0 {
+            return rv;
+        }
+
+        let index = match self
+            .entries
+            .binary_search_by_key(&NotNan::new(accel_angle).unwrap(), |entry| entry.angle)
+        {
+            Ok(index) | Err(index) => index,
+        };
+
+        // Expand outward from the insertion point, wrapping around at ±π.
+        let mut prev = (index + len - 1) % len;
+        let mut next = index % len;
+        while rv.len() < n {
+            let prev_entry = &self.entries[prev];
+            let next_entry = &self.entries[next];
+            if distance(prev_entry) < distance(next_entry) {
+                rv.push(*prev_entry);
+                prev = (prev + len - 1) % len;
+            } else {
+                rv.push(*next_entry);
+                next = (next + 1) % len;
+            }
+        }
+
+        rv
+    }
+}
+
+/// Calls `f` with forward and side input values of every VCT entry, in no particular order.
+fn for_each_combination(mut f: impl FnMut(i16, i16)) {
+    let mut add_combinations = |fwd: i16, side: i16| {
+        for (fwd, side) in [
+            (fwd, side),
+            (fwd, -side),
+            (-fwd, side),
+            (-fwd, -side),
+            (side, fwd),
+            (side, -fwd),
+            (-side, fwd),
+            (-side, -fwd),
+        ] {
+            f(fwd, side);
+        }
+    };
+
+    /// Maximal value for forwardmove and sidemove.
+    const MAX_MOVE: i16 = 2047;
+
+    // Compute the Farey sequence in ascending order, starting from 0 / 1 and 1 / MAX_MOVE.
+    // This produces all co-prime F and S in the first octant (angles from -90 to -45 degrees).
+    let mut f = 0;
+    let mut s = 1;
+    let mut p = 1;
+    let mut q = MAX_MOVE;
+
+    while p != 1 || q != 1 {
+        let k = (MAX_MOVE + s) / q;
+        let tmp_f = f;
+        let tmp_s = s;
+        f = p;
+        s = q;
+        p = k * p - tmp_f;
+        q = k * q - tmp_s;
+
+        // Scale f and s to be as large as possible.
+        let fac = MAX_MOVE / s;
+        let scaled_f = f * fac;
+        let scaled_s = s * fac;
+
+        add_combinations(scaled_f, scaled_s);
+    }
+
+    // Add 0 and PI / 4 angles omitted in the loop above.
+    add_combinations(0, 2047);
+    add_combinations(2047, 2047);
+}
+
+/// Computes the angles of the entries and sorts the entries by angle using all CPU cores.
+fn compute_angles_and_sort(entries: &mut [Entry]) {
+    entries.par_iter_mut().for_each(|entry| {
+        entry.angle = NotNan::new((-entry.side as f32).atan2(entry.forward as f32)).unwrap();
+    });
+
+    entries.par_sort_unstable_by_key(|entry| entry.angle);
+}
+
+/// Returns the largest angular gap between consecutive entries sorted by angle, including the one
+/// wrapping around from the last entry to the first one.
+fn max_gap(entries: &[Entry]) -> Option<Gap> {
+    let (first, last) = (entries.first()?, entries.last()?);
+
+    entries
+        .windows(2)
+        .map(|pair| Gap {
+            start: pair[0].angle_radians(),
+            end: pair[1].angle_radians(),
+        })
+        .chain([Gap {
+            start: last.angle_radians(),
+            end: first.angle_radians() + TAU,
+        }])
+        .max_by(|a, b| a.size().total_cmp(&b.size()))
 }
 
 fn normalize_rad(mut angle: f32) -> f32 {
@@ -158,3 +278,130 @@ fn normalize_rad(mut angle: f32) -> f32 {
         angle
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::iter::zip;
+
+    use super::*;
+
+    /// Checks find_best_n() against sorting all entries by the distance.
+    fn check_find_best_n(vct: &Vct, accel_angle: f32, n: usize) -> ArrayVec<Entry, 8> {
+        let distance = |entry: &Entry| {
+            NotNan::new(normalize_rad(entry.angle_radians() - normalize_rad(accel_angle)).abs())
+                .unwrap()
+        };
+
+        let mut expected: Vec<_> = vct.entries().iter().map(distance).collect();
+        expected.sort_unstable();
+        expected.truncate(n);
+
+        let best = vct.find_best_n(accel_angle, n);
+        let distances: Vec<_> = best.iter().map(distance).collect();
+        assert_eq!(distances, expected);
+
+        best
+    }
+
+    #[test]
+    fn find_best_n() {
+        let vct = Vct::get();
+        let entries = vct.entries();
+
+        // Exactly on an entry.
+        let entry = entries[entries.len() / 3];
+        let best = check_find_best_n(vct, entry.angle_radians(), 4);
+        assert_eq!(best[0].angle, entry.angle);
+
+        // Between two entries.
+        let index = entries
+            .windows(2)
+            .position(|w| w[1].angle.into_inner() - w[0].angle.into_inner() > 1e-4)
+            .unwrap();
+        let (a, b) = (entries[index], entries[index + 1]);
+        let best = check_find_best_n(vct, (a.angle_radians() + b.angle_radians()) / 2., 8);
+        assert!(best.iter().any(|entry| entry.angle == a.angle));
+        assert!(best.iter().any(|entry| entry.angle == b.angle));
+
+        // Near the ±π boundary, the closest entry is across it.
+        let last = entries[entries.len() - 1];
+        let best = check_find_best_n(vct, -PI + 1e-6, 8);
+        assert_eq!(best[0].angle, last.angle);
+        check_find_best_n(vct, PI - 1e-6, 8);
+
+        // The count is capped.
+        assert_eq!(vct.find_best_n(0., 0).len(), 0);
+        assert_eq!(vct.find_best_n(0., 100).len(), 8);
+    }
+
+    fn entry(angle: f32) -> Entry {
+        Entry {
+            forward: 0,
+            side: 0,
+            angle: NotNan::new(angle).unwrap(),
+        }
+    }
+
+    #[test]
+    fn max_gap() {
+        assert_eq!(super::max_gap(&[]), None);
+
+        // The largest gap is between two entries, near the diagonal.
+        let entries = [-3., -2., -1., 0., 1.6, 2.2, 3.].map(entry);
+        let gap = super::max_gap(&entries).unwrap();
+        assert_eq!(
+            gap,
+            Gap {
+                start: 0.,
+                end: 1.6
+            }
+        );
+        assert!(!gap.is_near_axis());
+
+        // The largest gap wraps around ±π, near the axis.
+        let entries = [-2., -1., 0., 1., 2.].map(entry);
+        let gap = super::max_gap(&entries).unwrap();
+        assert_eq!(gap.start, 2.);
+        assert!((gap.size() - (TAU - 4.)).abs() < 1e-6);
+        assert!(gap.is_near_axis());
+
+        // A single entry has a full turn gap to itself.
+        let gap = super::max_gap(&[entry(0.)]).unwrap();
+        assert_eq!(gap.size(), TAU);
+    }
+
+    #[test]
+    fn parallel_compute_matches_single_threaded() {
+        let mut single_threaded = Vec::new();
+        for_each_combination(|forward, side| {
+            single_threaded.push(Entry {
+                forward,
+                side,
+                angle: NotNan::new((-side as f32).atan2(forward as f32)).unwrap(),
+            })
+        });
+        single_threaded.sort_unstable_by_key(|entry| entry.angle);
+
+        let mut parallel = Vec::new();
+        for_each_combination(|forward, side| {
+            parallel.push(Entry {
+                forward,
+                side,
+                angle: NotNan::default(),
+            })
+        });
+        compute_angles_and_sort(&mut parallel);
+
+        // The angles are sorted the same way. Entries with equal angles may end up in a different
+        // order, so compare them as sets.
+        let key = |entry: &Entry| (entry.angle, entry.forward, entry.side);
+        assert_eq!(parallel.len(), single_threaded.len());
+        assert!(zip(&parallel, &single_threaded).all(|(a, b)| a.angle == b.angle));
+
+        let mut parallel: Vec<_> = parallel.iter().map(key).collect();
+        let mut single_threaded: Vec<_> = single_threaded.iter().map(key).collect();
+        parallel.sort_unstable();
+        single_threaded.sort_unstable();
+        assert_eq!(parallel, single_threaded);
+    }
+}
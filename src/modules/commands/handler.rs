@@ -119,6 +119,46 @@ impl<A1: FromStr, A2: FromStr, A3: FromStr> CommandHandler for fn(MainThreadMark
     }
 }
 
+impl<A1: FromStr, A2: FromStr, A3: FromStr, A4: FromStr> CommandHandler
+    for fn(MainThreadMarker, A1, A2, A3, A4)
+{
+    unsafe fn handle(self, marker: MainThreadMarker) -> bool {
+        let mut args = Args::new(marker).skip(1);
+        if args.len() != 4 {
+            return false;
+        }
+
+        let a1 = if let Some(a1) = args.next().and_then(parse_arg) {
+            a1
+        } else {
+            return false;
+        };
+
+        let a2 = if let Some(a2) = args.next().and_then(parse_arg) {
+            a2
+        } else {
+            return false;
+        };
+
+        let a3 = if let Some(a3) = args.next().and_then(parse_arg) {
+            a3
+        } else {
+            return false;
+        };
+
+        let a4 = if let Some(a4) = args.next().and_then(parse_arg) {
+            a4
+        } else {
+            return false;
+        };
+
+        drop(args);
+        self(marker, a1, a2, a3, a4);
+
+        true
+    }
+}
+
 /// Wraps a function accepting `FromStr` arguments as a console command handler.
 ///
 /// The arguments are safely extracted and parsed into their respective types, and if the parsing
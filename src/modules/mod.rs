@@ -50,6 +50,7 @@ pub mod tas_recording;
 pub mod tas_server_time_fix;
 pub mod tas_studio;
 pub mod triangle_drawing;
+pub mod vct_query;
 pub mod viewmodel_remove;
 pub mod viewmodel_sway;
 pub mod wallhack;
@@ -118,6 +119,7 @@ pub static MODULES: &[&dyn Module] = &[
     &tas_server_time_fix::TasServerTimeFix,
     &tas_studio::TasStudio,
     &triangle_drawing::TriangleDrawing,
+    &vct_query::VctQuery,
     &viewmodel_remove::ViewmodelRemove,
     &viewmodel_sway::ViewmodelSway,
     &wallhack::Wallhack,
@@ -6,7 +6,7 @@ use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 use bxt_ipc_types::Frame;
-use bxt_strafe::{Parameters, Player, State};
+use bxt_strafe::{BhopCapStrategy, Parameters, Player, State};
 use glam::Vec3;
 use hltas::HLTAS;
 
@@ -68,6 +68,7 @@ impl Module for TasOptimizer {
             &BXT_TAS_OPTIM_CHANGE_SINGLE_FRAMES,
             &BXT_TAS_OPTIM_CHANGE_PITCH,
             &BXT_TAS_OPTIM_FRAMES,
+            &BXT_TAS_OPTIM_PINNED_FRAME_BULKS,
             &BXT_TAS_OPTIM_SIMULATION_ACCURACY,
             &BXT_TAS_OPTIM_MULTIPLE_GAMES,
             &BXT_TAS_OPTIM_CONSTRAINT_VALUE,
@@ -76,6 +77,8 @@ impl Module for TasOptimizer {
             &BXT_TAS_OPTIM_DIRECTION,
             &BXT_TAS_OPTIM_VARIABLE,
             &BXT_TAS_OPTIM_RHAI_FILE,
+            &BXT_TAS_OPTIM_WISH_SPEED_REDUCTION,
+            &BXT_TAS_OPTIM_AIR_ACCEL_CAP,
         ];
         CVARS
     }
@@ -110,6 +113,15 @@ How much of the script, in number of frames, can be mutated in the single-frame
 Use when you want the tail of the script to remain unchanged, but still included in the \
 optimization objective.",
 );
+static BXT_TAS_OPTIM_PINNED_FRAME_BULKS: CVar = CVar::new(
+    b"bxt_tas_optim_pinned_frame_bulks\0",
+    b"\0",
+    "\
+Space-separated indices of frame bulks that the optimizer must not change, counting from 0 at the \
+frame bulk where the optimization starts.
+
+Use when you want to keep a critical part of the script fixed and optimize around it.",
+);
 static BXT_TAS_OPTIM_RANDOM_FRAMES_TO_CHANGE: CVar = CVar::new(
     b"bxt_tas_optim_random_frames_to_change\0",
     b"6\0",
@@ -155,6 +167,25 @@ much slower compared even to accurate prediction.
 You need to start one or more game instances in addition to the one running the optimizer.",
 );
 
+static BXT_TAS_OPTIM_WISH_SPEED_REDUCTION: CVar = CVar::new(
+    b"bxt_tas_optim_wish_speed_reduction\0",
+    b"0\0",
+    "\
+Fraction by which the player's wish speed is reduced in the simulation, for example when walking.
+
+`0` means moving at full speed and `0.5` means moving at half speed. Also used for prediction in \
+the TAS editor.",
+);
+static BXT_TAS_OPTIM_AIR_ACCEL_CAP: CVar = CVar::new(
+    b"bxt_tas_optim_air_accel_cap\0",
+    b"30\0",
+    "\
+Cap on the wish speed used for air acceleration in the simulation.
+
+This is `30` in Half-Life, but some mods change or remove it. Also used for prediction in the TAS \
+editor.",
+);
+
 static BXT_TAS_OPTIM_VARIABLE: CVar = CVar::new(
     b"bxt_tas_optim_variable\0",
     b"pos.x\0",
@@ -331,7 +362,11 @@ pub unsafe fn parameters(marker: MainThreadMarker) -> Parameters {
             .unwrap_or(1.),
         step_size: get_cvar_f32(marker, "sv_stepsize").unwrap_or(18.),
         bounce: get_cvar_f32(marker, "sv_bounce").unwrap_or(1.),
-        bhop_cap: get_cvar_f32(marker, "bxt_bhopcap").unwrap_or(0.) != 0.,
+        bhop_cap: if get_cvar_f32(marker, "bxt_bhopcap").unwrap_or(0.) != 0. {
+            BhopCapStrategy::Standard
+        } else {
+            BhopCapStrategy::None
+        },
         max_speed: {
             if is_paranoia {
                 max_speed * client_max_speed / 100.
@@ -361,6 +396,9 @@ pub unsafe fn parameters(marker: MainThreadMarker) -> Parameters {
                 .map(|x| x != 1.)
                 .unwrap_or(true),
         duck_animation_slow_down: is_cstrike || is_czero,
+        wish_speed_reduction: get_cvar_f32(marker, "bxt_tas_optim_wish_speed_reduction")
+            .unwrap_or(0.),
+        air_accel_cap: get_cvar_f32(marker, "bxt_tas_optim_air_accel_cap").unwrap_or(30.),
     }
 }
 
@@ -833,6 +871,14 @@ pub unsafe fn player_data(marker: MainThreadMarker) -> Option<Player> {
 
 pub fn draw(marker: MainThreadMarker, tri: &TriangleApi) {
     if let Some(optimizer) = &mut *OPTIMIZER.borrow_mut(marker) {
+        optimizer.set_pinned_bulks(
+            BXT_TAS_OPTIM_PINNED_FRAME_BULKS
+                .to_string(marker)
+                .split_whitespace()
+                .filter_map(|index| index.parse().ok())
+                .collect(),
+        );
+
         if BXT_TAS_OPTIM_MULTIPLE_GAMES.as_bool(marker) {
             if OPTIMIZE.get(marker) {
                 optimizer.optimize_with_remote_clients(
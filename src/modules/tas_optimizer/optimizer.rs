@@ -1,6 +1,7 @@
 use std::error::Error;
 use std::io::Write;
 use std::num::NonZeroU32;
+use std::ops::Range;
 use std::result::Result;
 use std::{iter, mem};
 
@@ -8,8 +9,6 @@ use bxt_ipc_types::Frame;
 use bxt_strafe::Trace;
 use hltas::types::*;
 use hltas::HLTAS;
-use rand::distributions::Uniform;
-use rand::prelude::Distribution;
 use rand::seq::SliceRandom;
 use rand::Rng;
 use tap::{Conv, Pipe, Tap, TryConv};
@@ -42,6 +41,16 @@ pub struct Optimizer {
 
     /// Console command from the first frame of the optimized script, that we erased.
     erased_console_command: Option<String>,
+
+    /// Indices of frame bulks that the optimizer must not mutate.
+    ///
+    /// In the frame bulk mode these are indices into the current script. In the single-frame mode
+    /// they are converted into frame ranges of the original script, since that mode splits frame
+    /// bulks but never moves frames around.
+    pinned_bulks: Vec<usize>,
+
+    /// Frame ranges of the pinned frame bulks in the original script.
+    pinned_frames: Vec<Range<usize>>,
 }
 
 impl Optimizer {
@@ -76,7 +85,45 @@ impl Optimizer {
             last_mutation_frames: None,
             generation,
             erased_console_command,
+            pinned_bulks: Vec::new(),
+            pinned_frames: Vec::new(),
+        }
+    }
+
+    /// Sets the indices of frame bulks that the optimizer must not mutate.
+    ///
+    /// The indices are counted from the first optimized frame bulk.
+    pub fn set_pinned_bulks(&mut self, pinned_bulks: Vec<usize>) {
+        if self.pinned_bulks == pinned_bulks {
+            return;
         }
+
+        let mut first_frame = 0;
+        self.pinned_frames = self
+            .original_hltas
+            .frame_bulks()
+            .enumerate()
+            .filter_map(|(bulk_idx, bulk)| {
+                let frames = first_frame..first_frame + bulk.frame_count.get() as usize;
+                first_frame = frames.end;
+                pinned_bulks.contains(&bulk_idx).then_some(frames)
+            })
+            .collect();
+        self.pinned_bulks = pinned_bulks;
+    }
+
+    /// Returns the frames that can be mutated in the single-frame mode, out of the first `high`.
+    fn mutable_frames(&self, high: usize) -> Vec<usize> {
+        (0..high)
+            .filter(|frame| !self.pinned_frames.iter().any(|range| range.contains(frame)))
+            .collect()
+    }
+
+    /// Returns the frame bulks that can be mutated in the frame bulk mode.
+    fn mutable_bulks(&self) -> Vec<usize> {
+        (0..self.hltas.frame_bulks().count())
+            .filter(|bulk_idx| !self.pinned_bulks.contains(bulk_idx))
+            .collect()
     }
 
     /// Resets the optimizer to the original non-optimized script.
@@ -175,7 +222,15 @@ impl Optimizer {
             high = high.min(frames);
         }
 
-        let between = Uniform::from(0..high);
+        let mutable_frames = self.mutable_frames(high);
+        let mutable_bulks = self.mutable_bulks();
+        if (change_single_frames && mutable_frames.is_empty())
+            || (!change_single_frames && mutable_bulks.is_empty())
+        {
+            // Everything is pinned.
+            return None;
+        }
+
         let mut rng = rand::thread_rng();
 
         Some(iter::from_fn(move || {
@@ -186,11 +241,11 @@ impl Optimizer {
             for _ in 0..random_frames_to_change {
                 let frame = if change_single_frames {
                     // Pick a random frame and mutate it.
-                    let frame = between.sample(&mut rng);
+                    let frame = *mutable_frames.choose(&mut rng).unwrap();
                     mutate_frame(change_pitch, &mut rng, &mut hltas, frame);
                     frame
                 } else {
-                    mutate_single_frame_bulk(change_pitch, &mut hltas, &mut rng)
+                    mutate_single_frame_bulk(change_pitch, &mut hltas, &mutable_bulks, &mut rng)
                 };
 
                 stale_frame = stale_frame.min(frame);
@@ -330,7 +385,15 @@ impl Optimizer {
             high = high.min(frames);
         }
 
-        let between = Uniform::from(0..high);
+        let mutable_frames = self.mutable_frames(high);
+        let mutable_bulks = self.mutable_bulks();
+        if (change_single_frames && mutable_frames.is_empty())
+            || (!change_single_frames && mutable_bulks.is_empty())
+        {
+            // Everything is pinned.
+            return;
+        }
+
         let mut rng = rand::thread_rng();
 
         remote::simulate_in_available_clients(|| {
@@ -339,11 +402,16 @@ impl Optimizer {
             // Change several frames.
             for _ in 0..random_frames_to_change {
                 if change_single_frames {
-                    let frame = between.sample(&mut rng);
+                    let frame = *mutable_frames.choose(&mut rng).unwrap();
                     let frame_bulk = self.hltas.split_single_at_frame(frame).unwrap();
                     mutate_frame_bulk(change_pitch, &mut rng, frame_bulk);
                 } else {
-                    mutate_single_frame_bulk(change_pitch, &mut self.hltas, &mut rng);
+                    mutate_single_frame_bulk(
+                        change_pitch,
+                        &mut self.hltas,
+                        &mutable_bulks,
+                        &mut rng,
+                    );
                 }
             }
 
@@ -529,9 +597,17 @@ fn mutate_frame_bulk<R: Rng>(change_pitch: bool, rng: &mut R, frame_bulk: &mut F
     }
 }
 
-fn mutate_single_frame_bulk<R: Rng>(change_pitch: bool, hltas: &mut HLTAS, rng: &mut R) -> usize {
-    let count = hltas.frame_bulks().count();
-    let index = rng.gen_range(0..count);
+/// Mutates one of the `mutable_bulks` and returns the first frame that changed.
+///
+/// Only the frame bulks in `mutable_bulks` are changed, including the one that exchanges frame
+/// count with the mutated frame bulk.
+fn mutate_single_frame_bulk<R: Rng>(
+    change_pitch: bool,
+    hltas: &mut HLTAS,
+    mutable_bulks: &[usize],
+    rng: &mut R,
+) -> usize {
+    let index = *mutable_bulks.choose(rng).unwrap();
     let frame_bulk = hltas.frame_bulks_mut().nth(index).unwrap();
     let mut mutated_index = index;
 
@@ -628,7 +704,9 @@ fn mutate_single_frame_bulk<R: Rng>(change_pitch: bool, hltas: &mut HLTAS, rng:
     let same_frame_time_bulks: Vec<usize> = hltas
         .frame_bulks()
         .enumerate()
-        .filter(|&(i, bulk)| i != index && bulk.frame_time == frame_time)
+        .filter(|&(i, bulk)| {
+            i != index && bulk.frame_time == frame_time && mutable_bulks.contains(&i)
+        })
         .map(|(i, _)| i)
         .collect();
 
@@ -746,3 +824,34 @@ fn mutate_pitch<R: Rng>(rng: &mut R, frame_bulk: &mut FrameBulk) {
 }
 
 // proptest: after simulating, self.frames.len() = frame count + 1
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn mutate_single_frame_bulk_skips_pinned() {
+        let original = HLTAS::from_str(
+            "version 1\nframes\n\
+                s03-------|------|------|0.004|10|-|10\n\
+                s03-------|------|------|0.004|20|-|10\n\
+                s03-------|------|------|0.004|30|-|10",
+        )
+        .unwrap();
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut hltas = original.clone();
+        for _ in 0..100 {
+            let frame = mutate_single_frame_bulk(false, &mut hltas, &[1], &mut rng);
+            assert_eq!(frame, 10);
+        }
+
+        let bulks: Vec<_> = hltas.frame_bulks().collect();
+        let original_bulks: Vec<_> = original.frame_bulks().collect();
+        assert_eq!(bulks[0], original_bulks[0]);
+        assert_eq!(bulks[2], original_bulks[2]);
+    }
+}
@@ -7,7 +7,7 @@ use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use std::{mem, thread};
 
-use bxt_ipc_types::Frame;
+use bxt_ipc_types::{Frame, Handshake, VersionMismatch};
 use color_eyre::eyre::{self, eyre, Context};
 use hltas::HLTAS;
 use ipc_channel::ipc::{IpcOneShotServer, IpcReceiver, IpcSender};
@@ -240,7 +240,7 @@ fn server_thread(listener: TcpListener) {
         }
         drop(stream);
 
-        let (_, (hltas_sender, workaround_sender)): (_, (_, IpcSender<_>)) = match server.accept() {
+        let (_, handshake): (_, Handshake<(_, IpcSender<_>)>) = match server.accept() {
             Ok(x) => x,
             Err(err) => {
                 error!("Error accepting remote client IPC connection: {err:?}");
@@ -248,6 +248,13 @@ fn server_thread(listener: TcpListener) {
             }
         };
 
+        let (hltas_sender, workaround_sender) = handshake.channels;
+        if let Err(err) = handshake.check_version() {
+            error!("Rejecting remote client: {err}");
+            let _ = workaround_sender.send(Err(err));
+            continue;
+        }
+
         let (frames_sender, frames_receiver) = match ipc_channel::ipc::channel() {
             Ok(x) => x,
             Err(err) => {
@@ -256,7 +263,7 @@ fn server_thread(listener: TcpListener) {
             }
         };
 
-        if let Err(err) = workaround_sender.send(frames_sender) {
+        if let Err(err) = workaround_sender.send(Ok(frames_sender)) {
             error!("Error sending the frames sender to the remote client: {err:?}");
             return;
         };
@@ -367,12 +374,13 @@ fn connect_to_server(mut stream: TcpStream) -> eyre::Result<RemoteServer> {
     let (workaround_sender, workaround_receiver) =
         ipc_channel::ipc::channel().context("error creating a workaround IPC channel")?;
 
-    tx.send((hltas_sender, workaround_sender))
+    tx.send(Handshake::new((hltas_sender, workaround_sender)))
         .context("error sending the IPC channels to the remote server")?;
 
-    let frames_sender = workaround_receiver
+    let response: Result<_, VersionMismatch> = workaround_receiver
         .recv()
         .context("error receiving the frames sender from the remote server")?;
+    let frames_sender = response.context("the remote server rejected the connection")?;
 
     Ok(RemoteServer {
         receiver: hltas_receiver,
@@ -1,8 +1,17 @@
+use std::collections::BTreeMap;
+
 use bxt_ipc_types::Frame;
 /// Frame simulator.
-use bxt_strafe::Trace;
+use bxt_strafe::{SubdividingTracer, Trace};
 use hltas::types::Line;
 
+/// Movement parameter overrides used during prediction.
+///
+/// Maps a frame index to parameter names and values, with names as reported by
+/// [`Parameters::diff`](bxt_strafe::Parameters::diff). An override takes effect starting from its
+/// frame and persists for all subsequent frames.
+pub type ParameterOverrides = BTreeMap<usize, Vec<(String, f32)>>;
+
 /// Frame simulator.
 ///
 /// This is an [`Iterator`] that outputs simulated frames.
@@ -15,6 +24,12 @@ pub struct Simulator<'a, T> {
     repeat: u32,
     /// Frame to simulate from.
     last_frame: Frame,
+    /// Index of the next frame to simulate.
+    frame_idx: usize,
+    /// Parameter overrides to apply to simulated frames.
+    parameter_overrides: Option<&'a ParameterOverrides>,
+    /// Maximum distance of a single trace, longer traces are subdivided.
+    max_trace_distance: Option<f32>,
 }
 
 impl<'a, T> Simulator<'a, T> {
@@ -46,6 +61,9 @@ impl<'a, T> Simulator<'a, T> {
                     lines: &lines[l..],
                     repeat: 0,
                     last_frame: existing_frames.last().unwrap().clone(),
+                    frame_idx: existing_frames.len(),
+                    parameter_overrides: None,
+                    max_trace_distance: None,
                 };
             }
 
@@ -57,6 +75,9 @@ impl<'a, T> Simulator<'a, T> {
                             lines: &lines[l..],
                             repeat,
                             last_frame: existing_frames.last().unwrap().clone(),
+                            frame_idx: existing_frames.len(),
+                            parameter_overrides: None,
+                            max_trace_distance: None,
                         };
                     }
 
@@ -77,8 +98,28 @@ impl<'a, T> Simulator<'a, T> {
             lines: &[],
             repeat: 0,
             last_frame: existing_frames.last().unwrap().clone(),
+            frame_idx: existing_frames.len(),
+            parameter_overrides: None,
+            max_trace_distance: None,
         }
     }
+
+    /// Applies the given parameter overrides to the simulated frames.
+    ///
+    /// Only predicted frames are affected: the overrides have no effect on `existing_frames`.
+    pub fn with_parameter_overrides(mut self, parameter_overrides: &'a ParameterOverrides) -> Self {
+        self.parameter_overrides = Some(parameter_overrides);
+        self
+    }
+
+    /// Subdivides traces longer than `max_distance` units into multiple shorter traces.
+    ///
+    /// This prevents tunneling through thin geometry at very high speeds, at the cost of more
+    /// traces. By default every movement is traced at once.
+    pub fn with_trace_subdivision(mut self, max_distance: f32) -> Self {
+        self.max_trace_distance = Some(max_distance);
+        self
+    }
 }
 
 impl<'a, T: Trace> Iterator for Simulator<'a, T> {
@@ -101,11 +142,25 @@ impl<'a, T: Trace> Iterator for Simulator<'a, T> {
                                 / 1000.;
                     }
 
-                    let (new_state, _input) =
-                        state.clone().simulate(self.tracer, *parameters, frame_bulk);
+                    if let Some(overrides) = self.parameter_overrides {
+                        for (name, value) in overrides.range(..=self.frame_idx).flat_map(|(_, v)| v)
+                        {
+                            parameters.set_by_name(name, *value);
+                        }
+                    }
+
+                    let (new_state, _input) = match self.max_trace_distance {
+                        Some(max_distance) => state.clone().simulate(
+                            &SubdividingTracer::new(self.tracer, max_distance),
+                            *parameters,
+                            frame_bulk,
+                        ),
+                        None => state.clone().simulate(self.tracer, *parameters, frame_bulk),
+                    };
 
                     *state = new_state;
 
+                    self.frame_idx += 1;
                     self.repeat += 1;
                     if self.repeat == frame_bulk.frame_count.get() {
                         self.lines = &self.lines[1..];
@@ -141,7 +196,7 @@ impl<'a, T: Trace> Iterator for Simulator<'a, T> {
 mod tests {
     use std::num::NonZeroU32;
 
-    use bxt_strafe::{DummyTracer, Parameters, Player, State};
+    use bxt_strafe::{BhopCapStrategy, DummyTracer, Parameters, Player, State};
     use glam::Vec3;
     use hltas::types::{
         ActionKeys, AutoActions, AutoMovement, FrameBulk, MovementKeys, StrafeDir, StrafeSettings,
@@ -165,12 +220,14 @@ mod tests {
             ent_gravity: 1.,
             step_size: 18.,
             bounce: 1.,
-            bhop_cap: false,
+            bhop_cap: BhopCapStrategy::None,
             bhop_cap_multiplier: 0.65,
             bhop_cap_max_speed_scale: 1.7,
             use_slow_down: true,
             has_stamina: false,
             duck_animation_slow_down: false,
+            wish_speed_reduction: 0.,
+            air_accel_cap: 30.,
         }
     }
 
@@ -289,6 +346,40 @@ mod tests {
         assert_eq!(simulator.count(), 2);
     }
 
+    #[test]
+    fn simulator_parameter_overrides() {
+        let lines = [Line::FrameBulk(FrameBulk {
+            frame_count: NonZeroU32::new(3).unwrap(),
+            ..FrameBulk::with_frame_time("0.001".to_string())
+        })];
+        let overrides = ParameterOverrides::from([(2, vec![("friction".to_string(), 2.)])]);
+        let frames: Vec<Frame> = Simulator::new(&DummyTracer, &[default_frame()], &lines)
+            .with_parameter_overrides(&overrides)
+            .collect();
+
+        assert_eq!(frames[0].parameters.friction, 4.);
+        assert_eq!(frames[1].parameters.friction, 2.);
+        assert_eq!(frames[2].parameters.friction, 2.);
+    }
+
+    #[test]
+    fn simulator_trace_subdivision() {
+        let lines = [Line::FrameBulk(FrameBulk {
+            frame_count: NonZeroU32::new(3).unwrap(),
+            ..FrameBulk::with_frame_time("0.01".to_string())
+        })];
+        let mut initial_frame = default_frame();
+        initial_frame.state.player.vel = Vec3::new(2000., 0., 0.);
+
+        let expected: Vec<Frame> =
+            Simulator::new(&DummyTracer, &[initial_frame.clone()], &lines).collect();
+        let frames: Vec<Frame> = Simulator::new(&DummyTracer, &[initial_frame], &lines)
+            .with_trace_subdivision(1.)
+            .collect();
+
+        assert_eq!(frames, expected);
+    }
+
     #[test]
     fn simulator_accel_yawspeed_increment() {
         let mut frames: Vec<Frame> = vec![default_frame()];
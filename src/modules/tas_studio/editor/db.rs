@@ -1,4 +1,5 @@
 use std::fmt;
+use std::iter::zip;
 use std::path::Path;
 
 use bincode::Options;
@@ -22,6 +23,8 @@ pub struct Branch {
 
     pub script: HLTAS,
     pub stop_frame: u32,
+    /// Color for drawing this branch when it's not focused, `None` for the default color.
+    pub color: Option<[u8; 3]>,
 }
 
 impl fmt::Debug for Branch {
@@ -31,6 +34,7 @@ impl fmt::Debug for Branch {
             .field("name", &self.name)
             .field("is_hidden", &self.is_hidden)
             .field("stop_frame", &self.stop_frame)
+            .field("color", &self.color)
             .finish()
     }
 }
@@ -40,6 +44,16 @@ pub struct GlobalSettings {
     pub current_branch_id: i64,
 }
 
+/// Editor UI state saved for restoring after reopening the project.
+///
+/// This is only a hint: every field is validated against the loaded script before use.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UiState {
+    pub selected_bulk_idx: Option<usize>,
+    pub first_shown_frame_idx: usize,
+    pub in_camera_editor: bool,
+}
+
 /// An action that applies to a branch and can be undone and redone.
 #[derive(Debug, Clone)]
 pub struct Action {
@@ -62,6 +76,8 @@ pub enum ActionKind {
     Hide,
     /// Show a hidden branch.
     Show,
+    /// Apply several operations to a script as a single action.
+    Group(Vec<Operation>),
 }
 
 impl Db {
@@ -97,7 +113,8 @@ impl Db {
                 name TEXT NOT NULL DEFAULT \"Default Branch\",
                 is_hidden INTEGER NOT NULL DEFAULT 0, 
                 script TEXT NOT NULL,
-                stop_frame INTEGER NOT NULL DEFAULT 0
+                stop_frame INTEGER NOT NULL DEFAULT 0,
+                color INTEGER
             ) STRICT",
             (),
         )?;
@@ -128,6 +145,8 @@ impl Db {
             (),
         )?;
 
+        create_ui_state_table(&conn)?;
+
         // Add the default rows.
         let mut buffer = Vec::new();
         script
@@ -156,6 +175,16 @@ impl Db {
             OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
         )?;
 
+        // Projects created before branch colors were added lack the color column.
+        if conn.prepare("SELECT color FROM branches LIMIT 0").is_err() {
+            conn.execute("ALTER TABLE branches ADD COLUMN color INTEGER", ())?;
+        }
+
+        // Projects created before UI state saving was added lack the ui_state table.
+        if conn.prepare("SELECT 1 FROM ui_state LIMIT 0").is_err() {
+            create_ui_state_table(&conn)?;
+        }
+
         Ok(Self { conn })
     }
 
@@ -172,10 +201,44 @@ impl Db {
         Ok(rv)
     }
 
+    #[instrument]
+    pub fn ui_state(&self) -> eyre::Result<UiState> {
+        let rv = self.conn.query_row(
+            "SELECT selected_bulk_idx, first_shown_frame_idx, in_camera_editor FROM ui_state",
+            [],
+            |row| {
+                Ok(UiState {
+                    selected_bulk_idx: row.get(0)?,
+                    first_shown_frame_idx: row.get(1)?,
+                    in_camera_editor: row.get(2)?,
+                })
+            },
+        )?;
+
+        Ok(rv)
+    }
+
+    #[instrument]
+    pub fn update_ui_state(&self, ui_state: &UiState) -> eyre::Result<()> {
+        self.conn.execute(
+            "UPDATE ui_state SET
+                selected_bulk_idx = ?1,
+                first_shown_frame_idx = ?2,
+                in_camera_editor = ?3",
+            params![
+                ui_state.selected_bulk_idx,
+                ui_state.first_shown_frame_idx,
+                ui_state.in_camera_editor
+            ],
+        )?;
+
+        Ok(())
+    }
+
     #[instrument]
     pub fn branch(&self, branch_id: i64) -> eyre::Result<Branch> {
-        let (buffer, name, is_hidden, stop_frame) = self.conn.query_row(
-            "SELECT script, name, is_hidden, stop_frame FROM branches WHERE branch_id = ?1",
+        let (buffer, name, is_hidden, stop_frame, color) = self.conn.query_row(
+            "SELECT script, name, is_hidden, stop_frame, color FROM branches WHERE branch_id = ?1",
             [branch_id],
             |row| {
                 Ok((
@@ -183,6 +246,7 @@ impl Db {
                     row.get(1)?,
                     row.get(2)?,
                     row.get(3)?,
+                    row.get::<_, Option<u32>>(4)?,
                 ))
             },
         )?;
@@ -196,6 +260,7 @@ impl Db {
             is_hidden,
             script,
             stop_frame,
+            color: color.map(unpack_color),
         })
     }
 
@@ -203,9 +268,9 @@ impl Db {
     pub fn branches(&self) -> eyre::Result<Vec<Branch>> {
         let mut branches = vec![];
 
-        let mut stmt = self
-            .conn
-            .prepare("SELECT branch_id, script, name, is_hidden, stop_frame FROM branches")?;
+        let mut stmt = self.conn.prepare(
+            "SELECT branch_id, script, name, is_hidden, stop_frame, color FROM branches",
+        )?;
         for value in stmt.query_map([], |row| {
             Ok((
                 row.get(0)?,
@@ -213,9 +278,10 @@ impl Db {
                 row.get(2)?,
                 row.get(3)?,
                 row.get(4)?,
+                row.get::<_, Option<u32>>(5)?,
             ))
         })? {
-            let (branch_id, buffer, name, is_hidden, stop_frame) = value?;
+            let (branch_id, buffer, name, is_hidden, stop_frame, color) = value?;
 
             let script = HLTAS::from_str(&buffer)
                 .map_err(|err| eyre!("invalid script value, cannot parse: {err:?}"))?;
@@ -226,6 +292,7 @@ impl Db {
                 is_hidden,
                 script,
                 stop_frame,
+                color: color.map(unpack_color),
             })
         }
         stmt.finalize()?;
@@ -246,9 +313,7 @@ impl Db {
 
         match value {
             Some((branch_id, buffer)) => {
-                let kind = bincode::options()
-                    .deserialize(&buffer)
-                    .map_err(|err| eyre!("invalid action, cannot deserialize: {err:?}"))?;
+                let kind = deserialize_action_kind(&buffer)?;
 
                 Ok(Some(Action { branch_id, kind }))
             }
@@ -269,9 +334,7 @@ impl Db {
 
         match value {
             Some((branch_id, buffer)) => {
-                let kind = bincode::options()
-                    .deserialize(&buffer)
-                    .map_err(|err| eyre!("invalid action, cannot deserialize: {err:?}"))?;
+                let kind = deserialize_action_kind(&buffer)?;
 
                 Ok(Some(Action { branch_id, kind }))
             }
@@ -279,50 +342,87 @@ impl Db {
         }
     }
 
+    /// Returns the undo and redo logs.
+    ///
+    /// Entries that cannot be deserialized are skipped with a warning, along with the older entries
+    /// that depend on them. Use [`Db::repair_action_logs`] to remove them from the database.
     #[instrument]
     pub fn undo_redo(&mut self) -> eyre::Result<(Vec<Action>, Vec<Action>)> {
         let tx = self.conn.transaction()?;
 
-        let mut undo_log = Vec::new();
-        let mut stmt = tx.prepare("SELECT branch_id, action FROM undo_log")?;
-        for value in stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, Vec<u8>>(1)?)))? {
-            let (branch_id, buffer) = value?;
-            let kind = bincode::options()
-                .deserialize(&buffer)
-                .map_err(|err| eyre!("invalid action, cannot deserialize: {err:?}"))?;
-            undo_log.push(Action { branch_id, kind });
+        let undo_log = load_action_log(&tx, "undo_log")?;
+        let redo_log = load_action_log(&tx, "redo_log")?;
+
+        tx.commit()?;
+
+        Ok((undo_log, redo_log))
+    }
+
+    /// Returns the number of undo and redo log entries that [`Db::repair_action_logs`] would
+    /// remove.
+    #[instrument]
+    pub fn check_action_logs(&self) -> eyre::Result<(usize, usize)> {
+        let mut counts = [0; 2];
+        for (count, table) in zip(&mut counts, ["undo_log", "redo_log"]) {
+            if let Some(rowid) = last_invalid_action_rowid(&self.conn, table)? {
+                *count = self.conn.query_row(
+                    &format!("SELECT count(*) FROM {table} WHERE _rowid_ <= ?1"),
+                    [rowid],
+                    |row| row.get(0),
+                )?;
+            }
         }
-        stmt.finalize()?;
 
-        let mut redo_log = Vec::new();
-        let mut stmt = tx.prepare("SELECT branch_id, action FROM redo_log")?;
-        for value in stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, Vec<u8>>(1)?)))? {
-            let (branch_id, buffer) = value?;
-            let kind = bincode::options()
-                .deserialize(&buffer)
-                .map_err(|err| eyre!("invalid action, cannot deserialize: {err:?}"))?;
-            redo_log.push(Action { branch_id, kind });
+        Ok((counts[0], counts[1]))
+    }
+
+    /// Removes undo and redo log entries that cannot be deserialized or refer to a missing branch.
+    ///
+    /// The entries before an invalid one cannot be undone or redone without it, so they are
+    /// removed too. Returns the number of removed undo and redo log entries.
+    #[instrument]
+    pub fn repair_action_logs(&mut self) -> eyre::Result<(usize, usize)> {
+        let tx = self.conn.transaction()?;
+
+        let mut counts = [0; 2];
+        for (count, table) in zip(&mut counts, ["undo_log", "redo_log"]) {
+            if let Some(rowid) = last_invalid_action_rowid(&tx, table)? {
+                *count =
+                    tx.execute(&format!("DELETE FROM {table} WHERE _rowid_ <= ?1"), [rowid])?;
+            }
         }
-        stmt.finalize()?;
 
         tx.commit()?;
-
-        Ok((undo_log, redo_log))
+        Ok((counts[0], counts[1]))
     }
 
     #[instrument]
     pub fn update_with_action(&mut self, branch: &Branch, kind: &ActionKind) -> eyre::Result<()> {
+        self.update_with_actions(branch, std::slice::from_ref(kind))
+    }
+
+    /// Stores the branch and appends all actions to the undo log in a single transaction.
+    ///
+    /// Only the row of `branch` is written to, other branches are left untouched.
+    #[instrument]
+    pub fn update_with_actions(
+        &mut self,
+        branch: &Branch,
+        kinds: &[ActionKind],
+    ) -> eyre::Result<()> {
         let tx = self.conn.transaction()?;
 
         update_branch(&tx, branch)?;
 
-        let buffer = bincode::options()
-            .serialize(kind)
-            .expect("serializing action should never fail");
-        tx.execute(
-            "INSERT INTO undo_log (branch_id, action) VALUES (?1, ?2)",
-            params![branch.branch_id, buffer],
-        )?;
+        for kind in kinds {
+            let buffer = bincode::options()
+                .serialize(kind)
+                .expect("serializing action should never fail");
+            tx.execute(
+                "INSERT INTO undo_log (branch_id, action) VALUES (?1, ?2)",
+                params![branch.branch_id, buffer],
+            )?;
+        }
 
         tx.execute("DELETE FROM redo_log", [])?;
 
@@ -335,22 +435,7 @@ impl Db {
         let tx = self.conn.transaction()?;
 
         update_branch(&tx, branch)?;
-
-        let deleted = tx.execute(
-            "DELETE FROM undo_log WHERE _rowid_ = (
-                SELECT max(_rowid_) FROM undo_log WHERE branch_id = ?1
-            )",
-            [branch.branch_id],
-        )?;
-        ensure!(deleted == 1, "undo log should have had an entry");
-
-        let buffer = bincode::options()
-            .serialize(kind)
-            .expect("serializing action should never fail");
-        tx.execute(
-            "INSERT INTO redo_log (branch_id, action) VALUES (?1, ?2)",
-            params![branch.branch_id, buffer],
-        )?;
+        move_last_action(&tx, "undo_log", "redo_log", branch.branch_id, kind)?;
 
         tx.commit()?;
         Ok(())
@@ -361,22 +446,51 @@ impl Db {
         let tx = self.conn.transaction()?;
 
         update_branch(&tx, branch)?;
+        move_last_action(&tx, "redo_log", "undo_log", branch.branch_id, kind)?;
 
-        let deleted = tx.execute(
-            "DELETE FROM redo_log WHERE _rowid_ = (
-                SELECT max(_rowid_) FROM redo_log WHERE branch_id = ?1
-            )",
-            [branch.branch_id],
-        )?;
-        ensure!(deleted == 1, "redo log should have had an entry");
+        tx.commit()?;
+        Ok(())
+    }
 
-        let buffer = bincode::options()
-            .serialize(kind)
-            .expect("serializing action should never fail");
-        tx.execute(
-            "INSERT INTO undo_log (branch_id, action) VALUES (?1, ?2)",
-            params![branch.branch_id, buffer],
-        )?;
+    /// Stores the branches and moves the undone actions to the redo log in a single transaction.
+    ///
+    /// `actions` must be in the order they were undone in.
+    #[instrument]
+    pub fn update_after_undo_all(
+        &mut self,
+        branches: &[&Branch],
+        actions: &[Action],
+    ) -> eyre::Result<()> {
+        let tx = self.conn.transaction()?;
+
+        for branch in branches {
+            update_branch(&tx, branch)?;
+        }
+        for action in actions {
+            move_last_action(&tx, "undo_log", "redo_log", action.branch_id, &action.kind)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Stores the branches and moves the redone actions to the undo log in a single transaction.
+    ///
+    /// `actions` must be in the order they were redone in.
+    #[instrument]
+    pub fn update_after_redo_all(
+        &mut self,
+        branches: &[&Branch],
+        actions: &[Action],
+    ) -> eyre::Result<()> {
+        let tx = self.conn.transaction()?;
+
+        for branch in branches {
+            update_branch(&tx, branch)?;
+        }
+        for action in actions {
+            move_last_action(&tx, "redo_log", "undo_log", action.branch_id, &action.kind)?;
+        }
 
         tx.commit()?;
         Ok(())
@@ -400,8 +514,15 @@ impl Db {
             .expect("HLTAS serialization should never produce invalid UTF-8");
 
         tx.execute(
-            "INSERT INTO branches (name, is_hidden, script, stop_frame) VALUES (?1, ?2, ?3, ?4)",
-            params![&branch.name, branch.is_hidden, buffer, branch.stop_frame],
+            "INSERT INTO branches (name, is_hidden, script, stop_frame, color)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                &branch.name,
+                branch.is_hidden,
+                buffer,
+                branch.stop_frame,
+                branch.color.map(pack_color)
+            ],
         )?;
         branch.branch_id = tx.last_insert_rowid();
 
@@ -479,6 +600,109 @@ impl Db {
     }
 }
 
+fn deserialize_action_kind(buffer: &[u8]) -> eyre::Result<ActionKind> {
+    bincode::options()
+        .deserialize(buffer)
+        .map_err(|err| eyre!("invalid action, cannot deserialize: {err:?}"))
+}
+
+/// Loads the actions in `table`, skipping the ones up to the last action that cannot be
+/// deserialized.
+fn load_action_log(conn: &Connection, table: &str) -> eyre::Result<Vec<Action>> {
+    let mut actions = Vec::new();
+    let mut skipped = 0;
+
+    let mut stmt = conn.prepare(&format!("SELECT branch_id, action FROM {table}"))?;
+    for value in stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, Vec<u8>>(1)?)))? {
+        let (branch_id, buffer) = value?;
+        match deserialize_action_kind(&buffer) {
+            Ok(kind) => actions.push(Action { branch_id, kind }),
+            Err(err) => {
+                warn!("skipping {table} entry: {err:?}");
+
+                // The older entries cannot be applied without this one.
+                skipped += actions.len() + 1;
+                actions.clear();
+            }
+        }
+    }
+    stmt.finalize()?;
+
+    if skipped > 0 {
+        warn!("skipped {skipped} {table} entries, use bxt_tas_studio_check_db to remove them");
+    }
+
+    Ok(actions)
+}
+
+/// Returns the rowid of the last action in `table` that cannot be deserialized or refers to a
+/// missing branch.
+fn last_invalid_action_rowid(conn: &Connection, table: &str) -> eyre::Result<Option<i64>> {
+    let mut last_invalid_rowid = None;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT _rowid_, action, branch_id IN (SELECT branch_id FROM branches) FROM {table}"
+    ))?;
+    for value in stmt.query_map([], |row| {
+        Ok((
+            row.get(0)?,
+            row.get::<_, Vec<u8>>(1)?,
+            row.get::<_, bool>(2)?,
+        ))
+    })? {
+        let (rowid, buffer, branch_exists) = value?;
+        if !branch_exists || deserialize_action_kind(&buffer).is_err() {
+            last_invalid_rowid = Some(rowid);
+        }
+    }
+    stmt.finalize()?;
+
+    Ok(last_invalid_rowid)
+}
+
+fn create_ui_state_table(conn: &Connection) -> eyre::Result<()> {
+    conn.execute(
+        "CREATE TABLE ui_state (
+            selected_bulk_idx INTEGER,
+            first_shown_frame_idx INTEGER NOT NULL DEFAULT 0,
+            in_camera_editor INTEGER NOT NULL DEFAULT 0
+        ) STRICT",
+        (),
+    )?;
+    conn.execute("INSERT INTO ui_state DEFAULT VALUES", ())?;
+
+    Ok(())
+}
+
+/// Moves the last action of the branch from the `from` table to the `to` table as `kind`.
+fn move_last_action(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+    branch_id: i64,
+    kind: &ActionKind,
+) -> eyre::Result<()> {
+    let deleted = conn.execute(
+        &format!(
+            "DELETE FROM {from} WHERE _rowid_ = (
+                SELECT max(_rowid_) FROM {from} WHERE branch_id = ?1
+            )"
+        ),
+        [branch_id],
+    )?;
+    ensure!(deleted == 1, "{from} should have had an entry");
+
+    let buffer = bincode::options()
+        .serialize(kind)
+        .expect("serializing action should never fail");
+    conn.execute(
+        &format!("INSERT INTO {to} (branch_id, action) VALUES (?1, ?2)"),
+        params![branch_id, buffer],
+    )?;
+
+    Ok(())
+}
+
 fn update_branch(conn: &Connection, branch: &Branch) -> eyre::Result<()> {
     let mut buffer = Vec::new();
     branch
@@ -493,16 +717,146 @@ fn update_branch(conn: &Connection, branch: &Branch) -> eyre::Result<()> {
             name = ?1,
             is_hidden = ?2,
             script = ?3,
-            stop_frame = ?4
-        WHERE branch_id = ?5",
+            stop_frame = ?4,
+            color = ?5
+        WHERE branch_id = ?6",
         params![
             &branch.name,
             branch.is_hidden,
             buffer,
             branch.stop_frame,
+            branch.color.map(pack_color),
             branch.branch_id
         ],
     )?;
 
     Ok(())
 }
+
+/// Packs an RGB color into a `0xRRGGBB` integer for storing in the database.
+fn pack_color([r, g, b]: [u8; 3]) -> u32 {
+    u32::from_be_bytes([0, r, g, b])
+}
+
+/// Unpacks an RGB color from a `0xRRGGBB` integer stored in the database.
+fn unpack_color(color: u32) -> [u8; 3] {
+    let [_, r, g, b] = color.to_be_bytes();
+    [r, g, b]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repair_action_logs() {
+        let script = HLTAS::from_str("version 1\nframes\n").unwrap();
+        let mut db = Db::create_in_memory(&script).unwrap();
+        let branch_id = db.global_settings().unwrap().current_branch_id;
+
+        let valid = bincode::options().serialize(&ActionKind::Hide).unwrap();
+        for (branch_id, action) in [
+            (branch_id, valid.clone()),
+            (branch_id, vec![0xff; 4]),
+            (branch_id, valid.clone()),
+        ] {
+            db.conn
+                .execute(
+                    "INSERT INTO undo_log (branch_id, action) VALUES (?1, ?2)",
+                    params![branch_id, action],
+                )
+                .unwrap();
+        }
+        // Loading skips the invalid entry and the ones before it.
+        let (undo_log, redo_log) = db.undo_redo().unwrap();
+        assert_eq!(undo_log.len(), 1);
+        assert!(redo_log.is_empty());
+
+        assert_eq!(db.check_action_logs().unwrap(), (2, 0));
+        assert_eq!(db.repair_action_logs().unwrap(), (2, 0));
+        assert_eq!(db.check_action_logs().unwrap(), (0, 0));
+
+        let (undo_log, redo_log) = db.undo_redo().unwrap();
+        assert_eq!(undo_log.len(), 1);
+        assert!(redo_log.is_empty());
+    }
+
+    #[test]
+    fn branch_name_persists() {
+        let path = std::env::temp_dir().join(format!(
+            "bxt-rs-branch-name-persists-{}.hltasproj",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let script = HLTAS::from_str("version 1\nframes\n").unwrap();
+        let mut db = Db::create(&path, &script).unwrap();
+        let branch_id = db.global_settings().unwrap().current_branch_id;
+
+        let mut branch = db.branch(branch_id).unwrap();
+        branch.name = "Renamed".to_owned();
+        db.insert_branch(&mut branch).unwrap();
+        branch.name = "Renamed Again".to_owned();
+        db.update_branch(&branch).unwrap();
+        drop(db);
+
+        let db = Db::open(&path).unwrap();
+        let names: Vec<_> = db
+            .branches()
+            .unwrap()
+            .into_iter()
+            .map(|branch| branch.name)
+            .collect();
+        assert_eq!(names, ["Default Branch", "Renamed Again"]);
+
+        drop(db);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn update_with_actions_only_touches_target_branch() {
+        let script = HLTAS::from_str("version 1\nframes\n").unwrap();
+        let mut db = Db::create_in_memory(&script).unwrap();
+        let branch_id = db.global_settings().unwrap().current_branch_id;
+
+        let mut other = db.branch(branch_id).unwrap();
+        other.name = "Other".to_owned();
+        db.insert_branch(&mut other).unwrap();
+
+        let changes = |db: &Db| -> i64 {
+            db.conn
+                .query_row("SELECT total_changes()", [], |row| row.get(0))
+                .unwrap()
+        };
+        let other_row = |db: &Db| -> (String, String) {
+            db.conn
+                .query_row(
+                    "SELECT name, script FROM branches WHERE branch_id = ?1",
+                    [other.branch_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap()
+        };
+        let other_before = other_row(&db);
+        let changes_before = changes(&db);
+
+        let mut branch = db.branch(branch_id).unwrap();
+        branch.script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|-|-|10\n").unwrap();
+        db.update_with_actions(&branch, &[ActionKind::Hide, ActionKind::Show])
+            .unwrap();
+
+        // One branch row update and two undo log inserts.
+        assert_eq!(changes(&db) - changes_before, 3);
+        assert_eq!(other_row(&db), other_before);
+        assert_eq!(db.branch(branch_id).unwrap().script, branch.script);
+
+        let (undo_log, redo_log) = db.undo_redo().unwrap();
+        // Insert of the other branch, then the two new actions.
+        assert_eq!(undo_log.len(), 3);
+        assert!(undo_log[1..]
+            .iter()
+            .all(|action| action.branch_id == branch_id));
+        assert!(redo_log.is_empty());
+    }
+}
@@ -1,40 +1,45 @@
 use std::cmp::{max, min};
 use std::fmt::Write;
+use std::fs::File;
 use std::iter::{self, zip};
+use std::mem;
 use std::num::NonZeroU32;
-use std::ops::ControlFlow;
+use std::ops::Range;
 use std::path::Path;
 use std::time::Instant;
 
 use bxt_ipc_types::Frame;
-use bxt_strafe::{Hull, Trace};
-use color_eyre::eyre::{self, ensure};
+use bxt_strafe::{GroundPlaneTracer, Hull, Input, Parameters, Player, Trace};
+use bxt_vct::Vct;
+use color_eyre::eyre::{self, bail, ensure, Context};
 use glam::{IVec2, Vec2, Vec3};
 use hltas::types::{
-    AutoMovement, Change, ChangeTarget, Line, StrafeDir, StrafeSettings, StrafeType,
-    VectorialStrafingConstraints,
+    AutoMovement, Change, ChangeTarget, LeaveGroundAction, LeaveGroundActionType, Line, StrafeDir,
+    StrafeSettings, StrafeType, VectorialStrafingConstraints,
 };
 use hltas::HLTAS;
 use itertools::Itertools;
 use thiserror::Error;
 
-use self::db::{Action, ActionKind, Branch, Db};
+use self::db::{Action, ActionKind, Branch, Db, UiState};
 use self::operation::{Key, Operation};
+use self::smoothing::{unwrap_angles, SmoothingKind};
 use self::toggle_auto_action::ToggleAutoActionTarget;
 use self::utils::{
     bulk_and_first_frame_idx, bulk_and_first_frame_idx_mut, bulk_idx_and_is_last,
     bulk_idx_and_repeat_at_frame, join_lines, line_first_frame_idx, line_idx_and_repeat_at_frame,
-    FrameBulkExt, MaxAccelOffsetValuesMut,
+    lines_frame_count, normalize_yaw, split_line_at_frame, FrameBulkExt, MaxAccelOffsetValuesMut,
 };
 use super::remote::{AccurateFrame, PlayRequest};
 use crate::hooks::sdl::MouseState;
-use crate::modules::tas_optimizer::simulator::Simulator;
+use crate::modules::tas_optimizer::simulator::{ParameterOverrides, Simulator};
 use crate::modules::tas_studio::editor::utils::MaxAccelOffsetValues;
-use crate::modules::triangle_drawing::triangle_api::{Primitive, RenderMode};
+use crate::modules::triangle_drawing::triangle_api::{CullStyle, Primitive, RenderMode};
 use crate::modules::triangle_drawing::TriangleApi;
 
 mod db;
 pub mod operation;
+pub mod smoothing;
 pub mod toggle_auto_action;
 pub mod utils;
 
@@ -53,6 +58,11 @@ pub struct Editor {
     undo_log: Vec<Action>,
     /// Log of actions for redo.
     redo_log: Vec<Action>,
+    /// Operations stored since the current undo group was started, if any.
+    ///
+    /// While an undo group is active, stored operations are collected here instead of going
+    /// directly into the undo log, and are committed as a single action when the group ends.
+    undo_group: Option<Vec<Operation>>,
 
     /// Current project generation.
     ///
@@ -76,17 +86,47 @@ pub struct Editor {
     auto_smoothing: bool,
     /// Whether to show the player bbox for the frame under cursor.
     show_player_bbox: bool,
+    /// Whether to show the player velocity vectors along the path.
+    show_velocity_vectors: bool,
+    /// Whether to color the path by the strafing angle error.
+    color_by_strafe_angle_error: bool,
+    /// Whether to highlight the frames where the player is on a steep slope.
+    highlight_steep_slopes: bool,
+    /// Which paths to draw when auto-smoothing is enabled.
+    smoothed_view_mode: SmoothedViewMode,
     /// Index of the first frame that should be fully shown and able to be interacted with.
     ///
     /// Frames before this cannot be interacted with and can be hidden from display.
     first_shown_frame_idx: usize,
+    /// Maximum number of frames to draw around the hovered frame, `None` for no limit.
+    max_drawn_frames: Option<usize>,
+    /// Indices of the frames that are drawn and can be hovered.
+    ///
+    /// Updated every tick according to `max_drawn_frames`.
+    drawn_frames: Range<usize>,
+    /// Maximum total number of frames in the script that operations are allowed to produce.
+    max_frame_count: usize,
+    /// Whether to predict frames with a fast approximate tracer during adjustments.
+    fast_preview: bool,
+    /// Index of the first frame predicted with the fast approximate tracer.
+    ///
+    /// These frames are predicted again with the accurate tracer once the adjustment is done.
+    first_preview_frame_idx: Option<usize>,
+    /// Maximum distance of a single trace during prediction, longer traces are subdivided.
+    max_trace_distance: Option<f32>,
 
     /// Whether the editor is in the camera editor mode.
     in_camera_editor: bool,
 
+    /// Whether to select the next frame bulk after deleting the selected one.
+    select_after_delete: bool,
+
     /// Frame index calculated from bxt_tas_studio_norefresh_until_stop_frame.
     norefresh_until_stop_frame_frame_idx: usize,
 
+    /// UI state last written to the DB, used to only write it when it changes.
+    saved_ui_state: UiState,
+
     // ==============================================
     // Movement-editor-specific state.
     /// Index of the hovered frame bulk.
@@ -95,6 +135,11 @@ pub struct Editor {
     ///
     /// When drag-editing a frame bulk, it remains the selected one.
     selected_bulk_idx: Option<usize>,
+    /// Index of the frame bulk where the range selection started.
+    ///
+    /// Together with `selected_bulk_idx` this forms the selected range of frame bulks. When
+    /// `None`, only the selected frame bulk is in the range.
+    selection_anchor_bulk_idx: Option<usize>,
 
     // Adjustments MUST BE applied or cancelled, never simply dropped. Dropping without applying or
     // cancelling will result in database corruption!
@@ -113,6 +158,11 @@ pub struct Editor {
     /// This adjustment requires a following frame bulk to exist (since it keeps the total frame
     /// count the same).
     adjacent_frame_count_adjustment: Option<MouseAdjustment<u32>>,
+    /// Adjacent frame bulk frame time adjustment.
+    ///
+    /// Preserves the total duration of the two adjacent frame bulks by changing the frame count of
+    /// the following frame bulk.
+    adjacent_frame_time_adjustment: Option<AdjacentFrameTimeAdjustment>,
     /// Adjacent frame bulk yaw adjustment.
     ///
     /// Adjusts the yaw in the same way for all adjacent frame bulks with equal yaw.
@@ -139,6 +189,11 @@ pub struct Editor {
     /// Index of the hovered frame bulk.
     hovered_line_idx: Option<usize>,
 
+    /// Range of frames (inclusive) that camera lines can be hovered in.
+    ///
+    /// When `None`, camera lines can be hovered anywhere.
+    camera_frame_range: Option<(usize, usize)>,
+
     /// Adjustment to insert a camera line.
     insert_camera_line_adjustment: Option<InsertCameraLineAdjustment>,
 
@@ -155,8 +210,22 @@ pub struct Editor {
     smooth_small_window_s: f32,
     /// Smoothing small window impact multiplier.
     smooth_small_window_multiplier: f32,
+    /// Kind of smoothing to apply.
+    smooth_kind: SmoothingKind,
 }
 
+/// Colors assigned round-robin to new branches, so that other branches are distinguishable.
+const BRANCH_COLORS: [[u8; 3]; 8] = [
+    [230, 159, 0],
+    [86, 180, 233],
+    [0, 158, 115],
+    [240, 228, 66],
+    [0, 114, 178],
+    [213, 94, 0],
+    [204, 121, 167],
+    [255, 255, 255],
+];
+
 #[derive(Debug, Clone)]
 pub struct BranchData {
     /// Edited branch.
@@ -177,6 +246,14 @@ pub struct BranchData {
     extra_cam: Vec<ExtraCameraEditorFrameData>,
     /// Data for auto-smoothing.
     auto_smoothing: AutoSmoothing,
+    /// Movement parameter overrides applied to predicted frames.
+    ///
+    /// These are not saved in the project.
+    parameter_overrides: ParameterOverrides,
+    /// Frame count of the script when `parameter_overrides` were last updated.
+    ///
+    /// Used to move the overrides along with the frames when the frame count changes.
+    parameter_overrides_frame_count: usize,
 }
 
 impl BranchData {
@@ -189,9 +266,33 @@ impl BranchData {
             auto_smoothing: AutoSmoothing {
                 script: None,
                 frames: vec![],
+                scratch: None,
             },
+            parameter_overrides: ParameterOverrides::new(),
+            parameter_overrides_frame_count: 0,
         }
     }
+
+    /// Invalidates predicted frames starting from given.
+    ///
+    /// Accurate frames are left intact.
+    fn invalidate_predicted(&mut self, frame_idx: usize) {
+        self.frames
+            .truncate(max(frame_idx, self.first_predicted_frame).max(1));
+        self.extra_cam.clear();
+    }
+
+    /// Returns the name of this branch.
+    pub fn name(&self) -> &str {
+        &self.branch.name
+    }
+
+    /// Returns the color for drawing this branch when it's not focused.
+    pub fn color(&self) -> Vec3 {
+        self.branch.color.map_or(Vec3::ONE * 0.5, |[r, g, b]| {
+            Vec3::new(r.into(), g.into(), b.into()) / 255.
+        })
+    }
 }
 
 /// Extra camera editor data for every frame.
@@ -259,6 +360,22 @@ impl<T> MouseAdjustment<T> {
     }
 }
 
+/// Data for handling the adjacent frame time adjustment.
+///
+/// We need to store the original values of both frame bulks so that the total duration is computed
+/// from them rather than from the values changed by the adjustment itself.
+#[derive(Debug, Clone)]
+struct AdjacentFrameTimeAdjustment {
+    /// The mouse adjustment itself.
+    mouse_adjustment: MouseAdjustment<f32>,
+    /// Original frame time of the frame bulk, as written in the script.
+    original_frame_time: String,
+    /// Original frame count of the next frame bulk.
+    original_next_frame_count: u32,
+    /// Frame time of the next frame bulk.
+    next_frame_time: f32,
+}
+
 /// Data for handling the adjacent yaw adjustment.
 ///
 /// We need to store which frame bulks are affected so that as we drag the mouse we don't "pick up"
@@ -376,7 +493,7 @@ struct InsertCameraLineAdjustment {
 }
 
 /// `CameraViewAdjustmentMode::Alt` adjusts different things depending on the line type. For
-/// target_yaw lines, it adjusts the velocity_lock angle.
+/// target_yaw lines, it cycles between a fixed yaw, velocity_lock and look_at the hovered frame.
 #[derive(Debug, Clone, Copy)]
 pub enum CameraViewAdjustmentMode {
     Yaw,
@@ -391,6 +508,40 @@ pub struct CameraViewAdjustment {
     camera_line_idx: usize,
 }
 
+/// Information about a branch for displaying in a branch list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchInfo {
+    /// Database ID of the branch.
+    pub branch_id: i64,
+    /// Name of the branch.
+    pub name: String,
+    /// Whether the branch is hidden.
+    pub is_hidden: bool,
+    /// Number of frames in the branch script.
+    pub frame_count: usize,
+    /// Whether this is the currently focused branch.
+    pub is_focused: bool,
+}
+
+/// Which current branch paths to draw when auto-smoothing is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmoothedViewMode {
+    Both,
+    RawOnly,
+    SmoothedOnly,
+}
+
+impl SmoothedViewMode {
+    /// Returns the next mode in the cycle.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Both => Self::RawOnly,
+            Self::RawOnly => Self::SmoothedOnly,
+            Self::SmoothedOnly => Self::Both,
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct KeyboardState {
     /// Whether the "faster" key is pressed.
@@ -399,6 +550,10 @@ pub struct KeyboardState {
     pub adjust_slower: bool,
     /// Whether the "insert camera line" key is pressed.
     pub insert_camera_line: bool,
+    /// Whether the "extend selection" key is pressed.
+    pub extend_selection: bool,
+    /// Whether the "adjust frame time" key is pressed.
+    pub adjust_frame_time: bool,
 }
 
 impl KeyboardState {
@@ -432,6 +587,22 @@ struct AutoSmoothing {
     ///
     /// The result of playing back the smoothed `script`.
     frames: Vec<Frame>,
+    /// Experimental edits of the smoothed script.
+    ///
+    /// Discarded whenever the smoothed script changes.
+    scratch: Option<SmoothingScratch>,
+}
+
+/// Experimental edits of the smoothed script that are never saved.
+#[derive(Debug, Clone)]
+struct SmoothingScratch {
+    /// Edited smoothed script.
+    script: HLTAS,
+    /// Smoothed accurate frames up to the first edited frame, followed by predicted frames.
+    ///
+    /// The prediction does not model the view override lines of the smoothed script, so only the
+    /// effect of frame bulk edits is shown.
+    frames: Vec<Frame>,
 }
 
 /// Error of a manually-triggered operation.
@@ -489,9 +660,26 @@ impl Editor {
             .find(|(_, branch)| branch.branch_id == global_settings.current_branch_id)
             .map(|(idx, _)| idx)
             .unwrap_or(0);
-        let branches = branches.into_iter().map(BranchData::new).collect();
+        let branches: Vec<_> = branches.into_iter().map(BranchData::new).collect();
         let (undo_log, redo_log) = db.undo_redo()?;
 
+        // Restore the UI state from the last session, dropping anything that doesn't match the
+        // script anymore. Adjustments are never saved, so any adjustment that was in progress when
+        // the game crashed is effectively cancelled.
+        let saved_ui_state = db.ui_state()?;
+        let script = &branches[branch_idx].branch.script;
+        let selected_bulk_idx = saved_ui_state
+            .selected_bulk_idx
+            .filter(|&idx| idx < script.frame_bulks().count());
+        let mut first_shown_frame_idx = saved_ui_state.first_shown_frame_idx;
+        let last_frame_idx = bulk_and_first_frame_idx(script)
+            .last()
+            .map(|(bulk, frame_idx)| frame_idx.saturating_add(bulk.frame_count.get() as usize))
+            .unwrap_or(0);
+        if first_shown_frame_idx + 1 >= last_frame_idx {
+            first_shown_frame_idx = 0;
+        }
+
         Ok(Self {
             db,
             branches,
@@ -499,8 +687,10 @@ impl Editor {
             generation: 0,
             undo_log,
             redo_log,
+            undo_group: None,
             hovered_bulk_idx: None,
-            selected_bulk_idx: None,
+            selected_bulk_idx,
+            selection_anchor_bulk_idx: None,
             hovered_frame_idx: None,
             prev_mouse_state: MouseState::default(),
             prev_keyboard_state: KeyboardState::default(),
@@ -508,22 +698,37 @@ impl Editor {
             yaw_adjustment: None,
             left_right_count_adjustment: None,
             adjacent_frame_count_adjustment: None,
+            adjacent_frame_time_adjustment: None,
             adjacent_yaw_adjustment: None,
             adjacent_left_right_count_adjustment: None,
             side_strafe_yawspeed_adjustment: None,
             adjacent_side_strafe_yawspeed_adjustment: None,
             max_accel_yaw_offset_adjustment: None,
-            in_camera_editor: false,
+            in_camera_editor: saved_ui_state.in_camera_editor,
             auto_smoothing: false,
             show_player_bbox: false,
-            first_shown_frame_idx: 0,
+            show_velocity_vectors: false,
+            color_by_strafe_angle_error: false,
+            highlight_steep_slopes: false,
+            smoothed_view_mode: SmoothedViewMode::Both,
+            select_after_delete: true,
+            first_shown_frame_idx,
+            max_drawn_frames: None,
+            drawn_frames: 0..usize::MAX,
+            fast_preview: false,
+            max_frame_count: DEFAULT_MAX_FRAME_COUNT,
+            first_preview_frame_idx: None,
+            max_trace_distance: None,
             hovered_line_idx: None,
+            camera_frame_range: None,
             insert_camera_line_adjustment: None,
             camera_view_adjustment: None,
             smooth_window_s: 0.15,
             smooth_small_window_s: 0.03,
             smooth_small_window_multiplier: 3.,
+            smooth_kind: SmoothingKind::Windowed,
             norefresh_until_stop_frame_frame_idx: 0,
+            saved_ui_state,
         })
     }
 
@@ -532,6 +737,21 @@ impl Editor {
         Self::open_db(db)
     }
 
+    /// Returns the number of undo and redo log entries that [`Editor::repair_db`] would remove
+    /// from the project at `path`.
+    pub fn check_db(path: &Path) -> eyre::Result<(usize, usize)> {
+        let db = Db::open(path)?;
+        db.check_action_logs()
+    }
+
+    /// Removes undo and redo log entries that cannot be loaded from the project at `path`.
+    ///
+    /// Returns the number of removed undo and redo log entries.
+    pub fn repair_db(path: &Path) -> eyre::Result<(usize, usize)> {
+        let mut db = Db::open(path)?;
+        db.repair_action_logs()
+    }
+
     pub fn create(path: &Path, script: &HLTAS) -> eyre::Result<Self> {
         let db = Db::create(path, script)?;
         Self::open_db(db)
@@ -575,6 +795,25 @@ impl Editor {
         self.selected_bulk_idx
     }
 
+    /// Returns the first and the last frame bulk index of the selected range, inclusive.
+    pub fn selected_bulk_range(&self) -> Option<(usize, usize)> {
+        let selected_bulk_idx = self.selected_bulk_idx?;
+
+        let anchor = self
+            .selection_anchor_bulk_idx
+            .filter(|&idx| idx < self.script().frame_bulks().count())
+            .unwrap_or(selected_bulk_idx);
+
+        Some((
+            min(anchor, selected_bulk_idx),
+            max(anchor, selected_bulk_idx),
+        ))
+    }
+
+    pub fn in_camera_editor(&self) -> bool {
+        self.in_camera_editor
+    }
+
     pub fn hovered_frame_idx(&self) -> Option<usize> {
         self.hovered_frame_idx
     }
@@ -583,18 +822,62 @@ impl Editor {
         self.hovered_frame_idx.map(|idx| &self.branch().frames[idx])
     }
 
+    /// Returns the yaw and pitch of the given frame in degrees, as used in .hltas scripts.
+    ///
+    /// These are the view angles of the input that led to this frame.
+    pub fn view_angles_deg(&self, frame_idx: usize) -> Option<(f32, f32)> {
+        let input = &self.branch().frames.get(frame_idx)?.state.prev_frame_input;
+        Some((input.yaw.to_degrees(), input.pitch.to_degrees()))
+    }
+
     pub fn has_all_accurate_frames(&self) -> bool {
-        let frame_count = self
-            .branch()
-            .branch
-            .script
-            .frame_bulks()
-            .map(|bulk| bulk.frame_count.get() as usize)
-            .sum::<usize>();
+        let frame_count = lines_frame_count(&self.script().lines);
 
         self.branch().first_predicted_frame == frame_count + 1
     }
 
+    /// Returns the index of the frame with the time closest to `seconds`.
+    ///
+    /// The time of a frame is the sum of the frame times of all frames up to and including it, so
+    /// the initial frame is at 0 seconds. Returns `None` if the branch has no frames.
+    pub fn frame_at_time(&self, seconds: f32) -> Option<usize> {
+        let frames = &self.branch().frames;
+        if frames.is_empty() {
+            return None;
+        }
+
+        // Skip the initial frame since it's not simulated.
+        let frame_times = frames
+            .iter()
+            .skip(1)
+            .map(|frame| frame.parameters.frame_time);
+        iter::once(0.)
+            .chain(frame_times)
+            .scan(0., |time, frame_time| {
+                *time += frame_time;
+                Some(*time)
+            })
+            .enumerate()
+            // Find closest to the requested time.
+            .min_by(|(_, time_a), (_, time_b)| {
+                let dist_a = (time_a - seconds).abs();
+                let dist_b = (time_b - seconds).abs();
+                dist_a.total_cmp(&dist_b)
+            })
+            // Extract frame index.
+            .map(|(frame_idx, _)| frame_idx)
+    }
+
+    /// Returns the number of simulated frames and the total number of frames in the script.
+    ///
+    /// Simulated frames include both accurate and predicted frames. The initial frame, which is
+    /// not simulated by any frame bulk, is not counted.
+    pub fn prediction_progress(&self) -> (usize, usize) {
+        let total = lines_frame_count(&self.script().lines);
+        let simulated = self.branch().frames.len().saturating_sub(1).min(total);
+        (simulated, total)
+    }
+
     pub fn undo_log_len(&self) -> usize {
         self.undo_log.len()
     }
@@ -644,6 +927,10 @@ impl Editor {
         self.smooth_small_window_multiplier = value;
     }
 
+    pub fn set_smooth_kind(&mut self, value: SmoothingKind) {
+        self.smooth_kind = value;
+    }
+
     pub fn set_auto_smoothing(&mut self, value: bool) {
         self.auto_smoothing = value;
     }
@@ -652,28 +939,141 @@ impl Editor {
         self.show_player_bbox = value;
     }
 
+    pub fn set_max_drawn_frames(&mut self, value: Option<usize>) {
+        self.max_drawn_frames = value;
+    }
+
+    pub fn set_max_frame_count(&mut self, value: usize) {
+        self.max_frame_count = value;
+    }
+
+    pub fn set_fast_preview(&mut self, value: bool) {
+        self.fast_preview = value;
+    }
+
+    pub fn set_max_trace_distance(&mut self, value: Option<f32>) {
+        if self.max_trace_distance == value {
+            return;
+        }
+
+        self.max_trace_distance = value;
+
+        // The predicted frames were traced differently, so predict them again.
+        self.invalidate_predicted(0);
+    }
+
+    pub fn set_show_velocity_vectors(&mut self, value: bool) {
+        self.show_velocity_vectors = value;
+    }
+
+    pub fn set_color_by_strafe_angle_error(&mut self, value: bool) {
+        self.color_by_strafe_angle_error = value;
+    }
+
+    pub fn set_highlight_steep_slopes(&mut self, value: bool) {
+        self.highlight_steep_slopes = value;
+    }
+
+    pub fn set_smoothed_view_mode(&mut self, value: SmoothedViewMode) {
+        self.smoothed_view_mode = value;
+    }
+
+    pub fn set_select_after_delete(&mut self, value: bool) {
+        self.select_after_delete = value;
+    }
+
     pub fn set_norefresh_until_stop_frame(&mut self, value: usize) {
         self.norefresh_until_stop_frame_frame_idx = value;
     }
 
+    /// Restricts camera line hovering to the given range of frames (inclusive).
+    ///
+    /// Passing `None` allows hovering camera lines anywhere.
+    pub fn set_camera_frame_range(&mut self, range: Option<(usize, usize)>) -> ManualOpResult<()> {
+        // Don't do this during active adjustments because they store the hovered line index.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if let Some((start, end)) = range {
+            if start > end {
+                return Err(ManualOpError::UserError(
+                    "the start frame must not be after the end frame".to_owned(),
+                ));
+            }
+        }
+
+        self.camera_frame_range = range;
+        Ok(())
+    }
+
     /// Invalidates frames starting from given.
     ///
     /// Erases cached frame data and adjusts the first predicted frame index if needed.
     fn invalidate(&mut self, frame_idx: usize) {
         let branch = &mut self.branch_mut();
+        let frames_discarded = branch.frames.len().saturating_sub(frame_idx);
+        let _span =
+            info_span!("invalidate", invalidated_from = frame_idx, frames_discarded).entered();
+
         branch.frames.truncate(frame_idx);
         branch.first_predicted_frame = min(branch.first_predicted_frame, frame_idx);
 
         // TODO: probably possible to do a finer-grained invalidation.
         branch.auto_smoothing.script = None;
         branch.auto_smoothing.frames.clear();
+        branch.auto_smoothing.scratch = None;
 
         branch.extra_cam.clear();
+
+        if !branch.parameter_overrides.is_empty() {
+            // Frames starting from frame_idx might have moved, so move their overrides by the
+            // change in the frame count.
+            let frame_count = lines_frame_count(&branch.branch.script.lines);
+            let delta = frame_count as isize - branch.parameter_overrides_frame_count as isize;
+            branch.parameter_overrides_frame_count = frame_count;
+
+            if delta != 0 {
+                let moved = branch.parameter_overrides.split_off(&frame_idx.max(1));
+                for (override_frame_idx, overrides) in moved {
+                    // Overrides of removed frames end up at the first changed frame.
+                    let new_frame_idx = override_frame_idx
+                        .saturating_add_signed(delta)
+                        .max(frame_idx)
+                        .max(1);
+                    branch
+                        .parameter_overrides
+                        .entry(new_frame_idx)
+                        .or_default()
+                        .extend(overrides);
+                }
+            }
+        }
+
         self.recompute_extra_camera_frame_data_if_needed();
 
         self.generation = self.generation.wrapping_add(1);
     }
 
+    /// Invalidates predicted frames starting from given in all branches.
+    ///
+    /// Accurate frames are left intact.
+    fn invalidate_predicted(&mut self, frame_idx: usize) {
+        for branch in &mut self.branches {
+            branch.invalidate_predicted(frame_idx);
+        }
+
+        self.recompute_extra_camera_frame_data_if_needed();
+    }
+
+    /// Invalidates predicted frames starting from given in the current branch.
+    ///
+    /// Accurate frames are left intact.
+    fn invalidate_predicted_in_branch(&mut self, frame_idx: usize) {
+        self.branch_mut().invalidate_predicted(frame_idx);
+        self.recompute_extra_camera_frame_data_if_needed();
+    }
+
     pub fn recompute_extra_camera_frame_data_if_needed(&mut self) {
         if !self.in_camera_editor {
             return;
@@ -851,6 +1251,7 @@ impl Editor {
             || self.yaw_adjustment.is_some()
             || self.left_right_count_adjustment.is_some()
             || self.adjacent_frame_count_adjustment.is_some()
+            || self.adjacent_frame_time_adjustment.is_some()
             || self.adjacent_yaw_adjustment.is_some()
             || self.adjacent_left_right_count_adjustment.is_some()
             || self.side_strafe_yawspeed_adjustment.is_some()
@@ -877,6 +1278,7 @@ impl Editor {
         self.tick_yaw_adjustment(mouse, keyboard)?;
         self.tick_left_right_count_adjustment(mouse, keyboard)?;
         self.tick_adjacent_frame_count_adjustment(mouse, keyboard)?;
+        self.tick_adjacent_frame_time_adjustment(mouse, keyboard)?;
         self.tick_adjacent_yaw_adjustment(mouse, keyboard)?;
         self.tick_adjacent_left_right_count_adjustment(mouse, keyboard)?;
         self.tick_side_strafe_yawspeed_adjustment(mouse, keyboard)?;
@@ -894,17 +1296,66 @@ impl Editor {
         // we're receiving accurate frames, we don't run prediction every frame, which will be
         // invalidated next frame due to receiving the next accurate frame.
         {
-            let _span = info_span!("predict").entered();
+            let use_preview_tracer = self.fast_preview && self.is_any_adjustment_active();
 
-            let branch = self.branch_mut();
-            let simulator = Simulator::new(tracer, &branch.frames, &branch.branch.script.lines);
-            for frame in simulator {
-                // Always simulate at least one frame.
-                branch.frames.push(frame);
+            // Once the adjustment is done, predict the approximate frames again accurately.
+            if !use_preview_tracer {
+                if let Some(frame_idx) = self.first_preview_frame_idx.take() {
+                    let branch = self.branch_mut();
+                    branch
+                        .frames
+                        .truncate(max(frame_idx, branch.first_predicted_frame).max(1));
+                    branch.extra_cam.clear();
+                }
+            }
 
-                // Break if the deadline has passed.
-                if Instant::now() >= deadline {
-                    break;
+            let branch = &mut self.branches[self.branch_idx];
+            let first_frame_idx = branch.frames.len();
+            let span = info_span!(
+                "predict",
+                first_frame_idx,
+                use_preview_tracer,
+                frames_resimulated = tracing::field::Empty
+            )
+            .entered();
+
+            if use_preview_tracer {
+                // The preview path is approximate: it only collides with a ground plane under the
+                // player on the last predicted frame.
+                let preview_tracer = branch
+                    .frames
+                    .last()
+                    .map(|frame| GroundPlaneTracer::under_player(&frame.state.player))
+                    .unwrap_or(GroundPlaneTracer { z: 0. });
+                predict(&preview_tracer, branch, None, deadline);
+
+                if branch.frames.len() > first_frame_idx {
+                    self.first_preview_frame_idx = Some(
+                        self.first_preview_frame_idx
+                            .map_or(first_frame_idx, |idx| min(idx, first_frame_idx)),
+                    );
+                }
+            } else {
+                predict(tracer, branch, self.max_trace_distance, deadline);
+            }
+
+            span.record("frames_resimulated", branch.frames.len() - first_frame_idx);
+        }
+
+        // Predict the smoothed scratch frames with the remaining time.
+        if let Some(scratch) = &mut self.branches[self.branch_idx].auto_smoothing.scratch {
+            if Instant::now() < deadline {
+                let mut simulator = Simulator::new(tracer, &scratch.frames, &scratch.script.lines);
+                if let Some(max_distance) = self.max_trace_distance {
+                    simulator = simulator.with_trace_subdivision(max_distance);
+                }
+
+                for frame in simulator {
+                    scratch.frames.push(frame);
+
+                    if Instant::now() >= deadline {
+                        break;
+                    }
                 }
             }
         }
@@ -912,6 +1363,9 @@ impl Editor {
         // Recompute extra data in case the prediction above added frames.
         self.recompute_extra_camera_frame_data_if_needed();
 
+        // Hovering should only consider the drawn frames.
+        self.update_drawn_frames();
+
         let mouse_pos = mouse.pos.as_vec2();
 
         let any_mouse_was_down_before = self.prev_mouse_state.buttons.is_left_down()
@@ -926,6 +1380,9 @@ impl Editor {
 
         let mouse_became_down = !any_mouse_was_down_before && any_mouse_is_down;
 
+        // Screen positions of the frames, computed on first use and shared by all hover passes.
+        let mut screen_positions = None;
+
         // Only update the hovered and active bulk index if we are not holding, or just pressed a
         // mouse button.
         if !any_mouse_is_down || mouse_became_down {
@@ -934,6 +1391,8 @@ impl Editor {
             if self.in_camera_editor {
                 self.hovered_bulk_idx = None;
 
+                let screen_positions = screen_positions
+                    .get_or_insert_with(|| self.project_shown_frames(&world_to_screen));
                 self.hovered_line_idx = iter::zip(
                     self.branches[self.branch_idx].frames.iter(),
                     // We take the next frame's extra_cam here because we're mostly concerned with
@@ -943,24 +1402,31 @@ impl Editor {
                 )
                 // Add frame indices.
                 .enumerate()
-                // Skip past hidden frames.
+                // Skip past hidden and not drawn frames.
                 .filter_map(|(frame_idx, rest)| {
-                    if frame_idx < self.first_shown_frame_idx {
+                    if frame_idx < self.first_shown_frame_idx
+                        || !self.drawn_frames.contains(&frame_idx)
+                    {
                         None
                     } else {
-                        Some(rest)
+                        Some((frame_idx, rest))
                     }
                 })
+                // Skip frames outside of the camera frame range.
+                .filter(|(frame_idx, _)| match self.camera_frame_range {
+                    Some((start, end)) => *frame_idx >= start && *frame_idx <= end,
+                    None => true,
+                })
                 // Take the last of the change lines.
-                .filter_map(|(frame, next_extra_cam)| {
+                .filter_map(|(frame_idx, (_, next_extra_cam))| {
                     next_extra_cam
                         .camera_line_that_starts_or_ends_here
                         .last()
-                        .map(|line_idx| (frame, *line_idx))
+                        .map(|line_idx| (frame_idx, *line_idx))
                 })
-                // Convert to screen and take only successfully converted coordinates.
-                .filter_map(|(frame, line_idx)| {
-                    world_to_screen(frame.state.player.pos).map(|screen| (screen, line_idx))
+                // Take only successfully converted screen coordinates.
+                .filter_map(|(frame_idx, line_idx)| {
+                    screen_positions[frame_idx].map(|screen| (screen, line_idx))
                 })
                 // Compute distance to cursor.
                 .map(|(screen, line_idx)| (screen.distance_squared(mouse_pos), line_idx))
@@ -971,27 +1437,27 @@ impl Editor {
                 // Extract line index.
                 .map(|(_, line_idx)| line_idx);
             } else {
+                let screen_positions = screen_positions
+                    .get_or_insert_with(|| self.project_shown_frames(&world_to_screen));
                 self.hovered_bulk_idx = iter::zip(
                     self.branches[self.branch_idx].frames.iter().skip(1),
                     bulk_idx_and_is_last(&self.branches[self.branch_idx].branch.script.lines),
                 )
                 // Add frame indices.
                 .enumerate()
-                // Skip past hidden frames.
-                .filter_map(|(frame_idx, rest)| {
-                    if frame_idx < self.first_shown_frame_idx {
-                        None
-                    } else {
-                        Some(rest)
-                    }
+                // Skip past hidden and not drawn frames.
+                .filter(|(frame_idx, _)| {
+                    *frame_idx >= self.first_shown_frame_idx
+                        && self.drawn_frames.contains(&(frame_idx + 1))
                 })
                 // Take only last frame in each bulk.
-                .filter_map(|(frame, (bulk_idx, _, is_last_in_bulk))| {
-                    is_last_in_bulk.then_some((frame, bulk_idx))
+                .filter_map(|(frame_idx, (_, (bulk_idx, _, is_last_in_bulk)))| {
+                    is_last_in_bulk.then_some((frame_idx, bulk_idx))
                 })
-                // Convert to screen and take only successfully converted coordinates.
-                .filter_map(|(frame, bulk_idx)| {
-                    world_to_screen(frame.state.player.pos).map(|screen| (screen, bulk_idx))
+                // Take only successfully converted screen coordinates. The frame indices here
+                // don't count the initial frame.
+                .filter_map(|(frame_idx, bulk_idx)| {
+                    screen_positions[frame_idx + 1].map(|screen| (screen, bulk_idx))
                 })
                 // Compute distance to cursor.
                 .map(|(screen, bulk_idx)| (screen.distance_squared(mouse_pos), bulk_idx))
@@ -1016,6 +1482,7 @@ impl Editor {
                 // Make the hovered bulk the selected bulk (or clear the selected bulk if not
                 // hovering anything).
                 self.selected_bulk_idx = self.hovered_bulk_idx;
+                self.selection_anchor_bulk_idx = None;
 
                 // Now that we have up-to-date active bulk index, start any adjustments if needed.
                 if let Some(active_bulk_idx) = self.selected_bulk_idx {
@@ -1033,8 +1500,8 @@ impl Editor {
                         .branch
                         .script
                         .frame_bulks()
-                        .scan(0, |frame_idx, bulk| {
-                            *frame_idx += bulk.frame_count.get() as usize;
+                        .scan(0usize, |frame_idx, bulk| {
+                            *frame_idx = frame_idx.saturating_add(bulk.frame_count.get() as usize);
                             Some((bulk, *frame_idx))
                         })
                         .skip(active_bulk_idx);
@@ -1182,7 +1649,7 @@ impl Editor {
                     } else if mouse.buttons.is_middle_down() {
                         let (bulk, last_frame_idx) = bulk_and_last_frame_idx.next().unwrap();
 
-                        if bulk_and_last_frame_idx.next().is_some() {
+                        if let Some((next_bulk, _)) = bulk_and_last_frame_idx.next() {
                             let frame = &branch.frames[last_frame_idx];
                             let prev = &branch.frames[last_frame_idx - 1];
 
@@ -1195,8 +1662,34 @@ impl Editor {
                                 _ => Vec2::X,
                             };
 
-                            self.adjacent_frame_count_adjustment =
-                                Some(MouseAdjustment::new(bulk.frame_count.get(), mouse_pos, dir));
+                            if keyboard.adjust_frame_time {
+                                // Keeping the duration requires both frame times to be valid.
+                                let frame_time = bulk.frame_time.parse::<f32>();
+                                let next_frame_time = next_bulk.frame_time.parse::<f32>();
+                                if let (Ok(frame_time), Ok(next_frame_time)) =
+                                    (frame_time, next_frame_time)
+                                {
+                                    if frame_time > 0. && next_frame_time > 0. {
+                                        self.adjacent_frame_time_adjustment =
+                                            Some(AdjacentFrameTimeAdjustment {
+                                                mouse_adjustment: MouseAdjustment::new(
+                                                    frame_time, mouse_pos, dir,
+                                                ),
+                                                original_frame_time: bulk.frame_time.clone(),
+                                                original_next_frame_count: next_bulk
+                                                    .frame_count
+                                                    .get(),
+                                                next_frame_time,
+                                            });
+                                    }
+                                }
+                            } else {
+                                self.adjacent_frame_count_adjustment = Some(MouseAdjustment::new(
+                                    bulk.frame_count.get(),
+                                    mouse_pos,
+                                    dir,
+                                ));
+                            }
                         }
                     } else if mouse.buttons.is_mouse4_down() {
                         let (bulk, last_frame_idx) = bulk_and_last_frame_idx.next().unwrap();
@@ -1339,20 +1832,18 @@ impl Editor {
                 // prediction going during adjustment. Ergo index out of bound.
                 // Min is needed to make sure it never happens.
                 Some(
-                    (frame_idx + (bulk.frame_count.get() as usize) - 1)
+                    frame_idx
+                        .saturating_add(bulk.frame_count.get() as usize - 1)
                         .min(self.branch().frames.len() - 1),
                 )
             } else {
-                self.branch()
-                    .frames
+                // Hidden and not drawn frames are None, so they are skipped here too.
+                screen_positions
+                    .get_or_insert_with(|| self.project_shown_frames(&world_to_screen))
                     .iter()
                     .enumerate()
-                    // Skip past hidden frames.
-                    .filter(|(frame_idx, _)| *frame_idx >= self.first_shown_frame_idx)
-                    // Convert to screen and take only successfully converted coordinates.
-                    .filter_map(|(frame_idx, frame)| {
-                        world_to_screen(frame.state.player.pos).map(|screen| (frame_idx, screen))
-                    })
+                    // Take only successfully converted screen coordinates.
+                    .filter_map(|(frame_idx, screen)| screen.map(|screen| (frame_idx, screen)))
                     // Find closest to cursor.
                     .min_by(|(_, screen_a), (_, screen_b)| {
                         let dist_a = screen_a.distance_squared(mouse_pos);
@@ -1371,6 +1862,8 @@ impl Editor {
                     // Keyboard was released last frame so the adjustment cannot be active.
                     assert!(self.insert_camera_line_adjustment.is_none());
 
+                    let (initial_yaw, _) = self.view_angles_deg(hovered_frame_idx).unwrap();
+
                     // There are no other adjustments in the camera editor at the moment; and anyhow
                     // when more are added, this condition should still be upheld.
                     assert!(!self.is_any_adjustment_active());
@@ -1395,33 +1888,20 @@ impl Editor {
                         VectorialStrafingConstraints::VelocityYawLocking { tolerance: 0. },
                     );
 
-                    let (line_idx, repeat) =
-                        line_idx_and_repeat_at_frame(lines, hovered_frame_idx).unwrap();
-
-                    let camera_line_idx = if repeat == 0 {
-                        lines.insert(line_idx, line);
-                        line_idx
-                    } else {
-                        let Line::FrameBulk(bulk) = &mut lines[line_idx] else {
-                            unreachable!()
-                        };
-
-                        let mut new_bulk = bulk.clone();
-                        bulk.frame_count = NonZeroU32::new(repeat).unwrap();
-                        new_bulk.frame_count =
-                            NonZeroU32::new(new_bulk.frame_count.get() - repeat).unwrap();
+                    let (line_idx, split) = split_line_at_frame(lines, hovered_frame_idx).unwrap();
+                    let did_split = split.is_some();
 
-                        lines.insert(line_idx + 1, line);
-                        lines.insert(line_idx + 2, Line::FrameBulk(new_bulk));
-                        line_idx + 1
+                    let camera_line_idx = match split {
+                        None => {
+                            lines.insert(line_idx, line);
+                            line_idx
+                        }
+                        Some((first, second)) => {
+                            lines.splice(line_idx..=line_idx, [first, line, second]);
+                            line_idx + 1
+                        }
                     };
 
-                    let initial_yaw = branch.frames[hovered_frame_idx]
-                        .state
-                        .prev_frame_input
-                        .yaw
-                        .to_degrees();
-
                     // This unfortunately means we won't have any predicted frames past this
                     // until the next tick, but oh well.
                     branch.extra_cam.clear();
@@ -1437,7 +1917,7 @@ impl Editor {
                         starting_frame_idx: hovered_frame_idx,
                         camera_line_idx,
                         initial_yaw,
-                        did_split: repeat != 0,
+                        did_split,
                     });
                 }
             }
@@ -1512,6 +1992,32 @@ impl Editor {
         self.prev_mouse_state = mouse;
         self.prev_keyboard_state = keyboard;
 
+        self.save_ui_state()?;
+
+        Ok(())
+    }
+
+    /// Writes the UI state to the DB if it changed since the last write.
+    ///
+    /// Nothing is written while an adjustment is active, so that reopening the project after a
+    /// crash lands in the state from before the adjustment.
+    fn save_ui_state(&mut self) -> eyre::Result<()> {
+        if self.is_any_adjustment_active() {
+            return Ok(());
+        }
+
+        let ui_state = UiState {
+            selected_bulk_idx: self.selected_bulk_idx,
+            first_shown_frame_idx: self.first_shown_frame_idx,
+            in_camera_editor: self.in_camera_editor,
+        };
+        if ui_state == self.saved_ui_state {
+            return Ok(());
+        }
+
+        self.db.update_ui_state(&ui_state)?;
+        self.saved_ui_state = ui_state;
+
         Ok(())
     }
 
@@ -1520,11 +2026,14 @@ impl Editor {
         mouse: MouseState,
         keyboard: KeyboardState,
     ) -> eyre::Result<()> {
-        let Some(adjustment) = &mut self.frame_count_adjustment else {
+        if self.frame_count_adjustment.is_none() {
             return Ok(());
-        };
+        }
 
         let bulk_idx = self.selected_bulk_idx.unwrap();
+        let max_new_frame_count = self.max_bulk_frame_count(bulk_idx);
+
+        let adjustment = self.frame_count_adjustment.as_mut().unwrap();
         let (bulk, first_frame_idx) =
             bulk_and_first_frame_idx_mut(&mut self.branches[self.branch_idx].branch.script)
                 .nth(bulk_idx)
@@ -1550,6 +2059,8 @@ impl Editor {
         let new_frame_count = adjustment
             .original_value
             .saturating_add_signed(delta)
+            // Don't cut the frame bulk if the script is already over the limit.
+            .min(max_new_frame_count.max(adjustment.original_value))
             .max(1);
 
         let frame_count = bulk.frame_count.get();
@@ -1655,6 +2166,81 @@ impl Editor {
         Ok(())
     }
 
+    fn tick_adjacent_frame_time_adjustment(
+        &mut self,
+        mouse: MouseState,
+        keyboard: KeyboardState,
+    ) -> eyre::Result<()> {
+        let Some(adjustment) = &mut self.adjacent_frame_time_adjustment else {
+            return Ok(());
+        };
+
+        let bulk_idx = self.selected_bulk_idx.unwrap();
+        let mut bulks =
+            bulk_and_first_frame_idx_mut(&mut self.branches[self.branch_idx].branch.script)
+                .skip(bulk_idx);
+        let (bulk, first_frame_idx) = bulks.next().unwrap();
+        let next_bulk = bulks.next().unwrap().0;
+        drop(bulks);
+
+        if !mouse.buttons.is_middle_down() {
+            if !adjustment.mouse_adjustment.changed_once {
+                self.adjacent_frame_time_adjustment = None;
+                return Ok(());
+            }
+
+            let op = Operation::SetAdjacentFrameTime {
+                bulk_idx,
+                from: (
+                    adjustment.original_frame_time.clone(),
+                    adjustment.original_next_frame_count,
+                ),
+                to: (bulk.frame_time.clone(), next_bulk.frame_count.get()),
+            };
+            self.adjacent_frame_time_adjustment = None;
+            return self.store_operation(op);
+        }
+
+        let original_value = adjustment.mouse_adjustment.original_value;
+
+        // Adjust in whole microseconds, 10 per pixel at normal speed.
+        let speed = keyboard.adjustment_speed();
+        let delta = (adjustment.mouse_adjustment.delta(mouse.pos.as_vec2()) * 10. * speed).round();
+        let original_us = (original_value * 1_000_000.).round();
+        let new_us = (original_us + delta).max(1.);
+
+        let (new_frame_time, new_next_frame_count) = if new_us == original_us {
+            // Keep the original spelling of the frame time.
+            (
+                adjustment.original_frame_time.clone(),
+                adjustment.original_next_frame_count,
+            )
+        } else {
+            let new_frame_time = new_us / 1_000_000.;
+
+            let frame_count = bulk.frame_count.get() as f32;
+            let duration = frame_count * original_value
+                + adjustment.original_next_frame_count as f32 * adjustment.next_frame_time;
+            let next_frame_count = ((duration - frame_count * new_frame_time)
+                / adjustment.next_frame_time)
+                .round()
+                .max(1.) as u32;
+
+            (new_frame_time.to_string(), next_frame_count)
+        };
+
+        if bulk.frame_time != new_frame_time || next_bulk.frame_count.get() != new_next_frame_count
+        {
+            adjustment.mouse_adjustment.changed_once = true;
+            bulk.frame_time = new_frame_time;
+            next_bulk.frame_count = NonZeroU32::new(new_next_frame_count).unwrap();
+
+            self.invalidate(first_frame_idx);
+        }
+
+        Ok(())
+    }
+
     fn tick_adjacent_frame_count_adjustment(
         &mut self,
         mouse: MouseState,
@@ -2293,7 +2879,7 @@ impl Editor {
             lines.remove(*camera_line_idx);
         }
 
-        let (line_idx, repeat) = line_idx_and_repeat_at_frame(&*lines, new_frame_idx).unwrap();
+        let (line_idx, split) = split_line_at_frame(lines, new_frame_idx).unwrap();
 
         if let Line::Change(Change { over, .. }) = &mut line {
             *over = 0.;
@@ -2306,23 +2892,17 @@ impl Editor {
             *over -= 1e-6;
         };
 
-        if repeat == 0 {
-            lines.insert(line_idx, line);
-            *camera_line_idx = line_idx;
-            *did_split = false;
-        } else {
-            let Line::FrameBulk(bulk) = &mut lines[line_idx] else {
-                unreachable!()
-            };
-
-            let mut new_bulk = bulk.clone();
-            bulk.frame_count = NonZeroU32::new(repeat).unwrap();
-            new_bulk.frame_count = NonZeroU32::new(new_bulk.frame_count.get() - repeat).unwrap();
-
-            lines.insert(line_idx + 1, line);
-            lines.insert(line_idx + 2, Line::FrameBulk(new_bulk));
-            *camera_line_idx = line_idx + 1;
-            *did_split = true;
+        match split {
+            None => {
+                lines.insert(line_idx, line);
+                *camera_line_idx = line_idx;
+                *did_split = false;
+            }
+            Some((first, second)) => {
+                lines.splice(line_idx..=line_idx, [first, line, second]);
+                *camera_line_idx = line_idx + 1;
+                *did_split = true;
+            }
         }
 
         self.invalidate(new_frame_idx);
@@ -2365,6 +2945,10 @@ impl Editor {
         let branch = &mut self.branches[self.branch_idx];
 
         let viewangles = (callbacks.get_viewangles)();
+        let look_at_target = self
+            .hovered_frame_idx
+            .and_then(|frame_idx| branch.frames.get(frame_idx))
+            .map(|frame| frame.state.player.pos);
         let line = &mut branch.branch.script.lines[*camera_line_idx];
 
         // Disable as soon as possible in case of failure or lock.
@@ -2388,7 +2972,27 @@ impl Editor {
                         };
                     }
                     CameraViewAdjustmentMode::Pitch => (),
-                    CameraViewAdjustmentMode::Alt => (),
+                    // Alt mode will change this to `target_yaw look_at` the hovered frame.
+                    CameraViewAdjustmentMode::Alt => {
+                        if let Some(target) = look_at_target {
+                            *constraints = VectorialStrafingConstraints::LookAt {
+                                entity: None,
+                                x: target.x,
+                                y: target.y,
+                                z: target.z,
+                            };
+                        }
+                    }
+                },
+                // Both Yaw and Alt mode will change this back to `target_yaw x`.
+                VectorialStrafingConstraints::LookAt { .. } => match mode {
+                    CameraViewAdjustmentMode::Yaw | CameraViewAdjustmentMode::Alt => {
+                        *constraints = VectorialStrafingConstraints::Yaw {
+                            yaw: viewangles[1],
+                            tolerance: 0.,
+                        };
+                    }
+                    CameraViewAdjustmentMode::Pitch => (),
                 },
                 VectorialStrafingConstraints::Yaw { yaw, .. } => {
                     match mode {
@@ -2525,14 +3129,37 @@ impl Editor {
             }
         }
 
-        if let Some(AdjacentYawAdjustment {
-            mouse_adjustment,
-            first_bulk_idx,
-            bulk_count,
-        }) = self.adjacent_yaw_adjustment.take()
+        if let Some(AdjacentFrameTimeAdjustment {
+            original_frame_time,
+            original_next_frame_count,
+            ..
+        }) = self.adjacent_frame_time_adjustment.take()
         {
-            let original_value = mouse_adjustment.original_value;
-
+            let bulk_idx = self.selected_bulk_idx.unwrap();
+            let mut bulks =
+                bulk_and_first_frame_idx_mut(&mut self.branch_mut().branch.script).skip(bulk_idx);
+            let (bulk, first_frame_idx) = bulks.next().unwrap();
+            let next_bulk = bulks.next().unwrap().0;
+            drop(bulks);
+
+            if bulk.frame_time != original_frame_time
+                || next_bulk.frame_count.get() != original_next_frame_count
+            {
+                bulk.frame_time = original_frame_time;
+                next_bulk.frame_count = NonZeroU32::new(original_next_frame_count).unwrap();
+
+                self.invalidate(first_frame_idx);
+            }
+        }
+
+        if let Some(AdjacentYawAdjustment {
+            mouse_adjustment,
+            first_bulk_idx,
+            bulk_count,
+        }) = self.adjacent_yaw_adjustment.take()
+        {
+            let original_value = mouse_adjustment.original_value;
+
             let mut bulks = bulk_and_first_frame_idx_mut(&mut self.branch_mut().branch.script)
                 .skip(first_bulk_idx);
             let (bulk, first_frame_idx) = bulks.next().unwrap();
@@ -2715,19 +3342,92 @@ impl Editor {
 
     /// Stores already-applied operation.
     fn store_operation(&mut self, op: Operation) -> eyre::Result<()> {
-        let action = Action {
-            branch_id: self.branch().branch.branch_id,
-            kind: ActionKind::ApplyOperation(op),
-        };
-        self.undo_log.push(action.clone());
+        self.store_operations(vec![op])
+    }
+
+    /// Stores already-applied operations in a single database transaction.
+    fn store_operations(&mut self, ops: Vec<Operation>) -> eyre::Result<()> {
+        if ops.is_empty() {
+            return Ok(());
+        }
+
+        // The branch and the grouped action are written together in end_undo_group().
+        if let Some(group) = &mut self.undo_group {
+            group.extend(ops);
+            return Ok(());
+        }
+
+        let branch_id = self.branch().branch.branch_id;
+        let kinds: Vec<_> = ops.into_iter().map(ActionKind::ApplyOperation).collect();
+        self.undo_log
+            .extend(kinds.iter().cloned().map(|kind| Action { branch_id, kind }));
         self.redo_log.clear();
         self.db
-            .update_with_action(&self.branches[self.branch_idx].branch, &action.kind)?;
+            .update_with_actions(&self.branches[self.branch_idx].branch, &kinds)?;
         Ok(())
     }
 
+    /// Starts an undo group.
+    ///
+    /// All operations stored until the matching [`Editor::end_undo_group()`] call are undone and
+    /// redone together as a single action.
+    fn begin_undo_group(&mut self) {
+        assert!(self.undo_group.is_none(), "undo groups cannot be nested");
+        self.undo_group = Some(Vec::new());
+    }
+
+    /// Ends the current undo group, storing its operations as a single action.
+    ///
+    /// The branch and the action are written to the database in a single transaction.
+    fn end_undo_group(&mut self) -> eyre::Result<()> {
+        let ops = self
+            .undo_group
+            .take()
+            .expect("end_undo_group() called without begin_undo_group()");
+
+        match ops.len() {
+            0 => Ok(()),
+            1 => self.store_operations(ops),
+            _ => {
+                let branch_id = self.branch().branch.branch_id;
+                let kind = ActionKind::Group(ops);
+                self.undo_log.push(Action {
+                    branch_id,
+                    kind: kind.clone(),
+                });
+                self.redo_log.clear();
+                self.db
+                    .update_with_action(&self.branches[self.branch_idx].branch, &kind)?;
+                Ok(())
+            }
+        }
+    }
+
     /// Applies operation to editor.
     fn apply_operation(&mut self, op: Operation) -> eyre::Result<()> {
+        self.apply_operations(vec![op])
+    }
+
+    /// Applies operations to editor in order, storing them in a single database transaction.
+    fn apply_operations(&mut self, ops: Vec<Operation>) -> eyre::Result<()> {
+        let _span = info_span!("apply_operations").entered();
+
+        let mut applied = Vec::with_capacity(ops.len());
+        for op in ops {
+            // Don't create undo entries for operations that do nothing.
+            if op.is_no_op() {
+                continue;
+            }
+
+            self.apply_operation_to_script(&op);
+            applied.push(op);
+        }
+
+        self.store_operations(applied)
+    }
+
+    /// Applies operation to the script, invalidating frames and adjusting the selection.
+    fn apply_operation_to_script(&mut self, op: &Operation) {
         let selected_line_idx = self.selected_bulk_idx.map(|idx| {
             self.branch()
                 .branch
@@ -2745,10 +3445,13 @@ impl Editor {
             self.invalidate(frame_idx);
         }
 
+        // The range selection doesn't survive edits.
+        self.selection_anchor_bulk_idx = None;
+
         // Adjust the selection if needed.
         let script = &self.branch().branch.script;
         if let Some(selected_line_idx) = selected_line_idx {
-            match op {
+            match *op {
                 Operation::Delete { line_idx, .. } => {
                     // Selected bulk index isn't None because selected_line_idx is computed from it.
                     let selected_bulk_idx = self.selected_bulk_idx.unwrap();
@@ -2758,8 +3461,10 @@ impl Editor {
                         // The selected bulk was deleted. In this case, the selected bulk index
                         // should remain unchanged (the bulk right after the deleted one should
                         // be selected), or, if it was the last bulk, the previous one should
-                        // be selected.
-                        if script.frame_bulks().nth(selected_bulk_idx).is_none() {
+                        // be selected. Unless the user asked to clear the selection instead.
+                        if !self.select_after_delete {
+                            self.selected_bulk_idx = None;
+                        } else if script.frame_bulks().nth(selected_bulk_idx).is_none() {
                             // This was the last bulk in the script.
                             if selected_bulk_idx == 0 {
                                 // There are no bulks left.
@@ -2809,8 +3514,6 @@ impl Editor {
             self.branch_mut().extra_cam.clear();
             self.recompute_extra_camera_frame_data_if_needed();
         }
-
-        self.store_operation(op)
     }
 
     /// Undoes the last action if any.
@@ -2822,7 +3525,19 @@ impl Editor {
             return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        let Some(action) = self.undo_log.pop() else {
+        let branch_idx = self.undo_in_memory()?;
+
+        let action = self.redo_log.last().unwrap();
+        self.db
+            .update_after_undo(&self.branches[branch_idx].branch, &action.kind)?;
+        Ok(())
+    }
+
+    /// Undoes the last action if any without storing the change in the database.
+    ///
+    /// Returns the index of the branch that the action applied to.
+    fn undo_in_memory(&mut self) -> ManualOpResult<usize> {
+        let Some(action) = self.undo_log.last().cloned() else {
             return Err(ManualOpError::UserError(
                 "there are no actions to undo".to_owned(),
             ));
@@ -2843,6 +3558,7 @@ impl Editor {
 
                 // TODO: smarter handling
                 self.selected_bulk_idx = None;
+                self.selection_anchor_bulk_idx = None;
 
                 if let Some(frame_idx) = op.undo(&mut self.branch_mut().branch.script) {
                     self.invalidate(frame_idx);
@@ -2851,6 +3567,24 @@ impl Editor {
                 self.branch_mut().extra_cam.clear();
                 self.recompute_extra_camera_frame_data_if_needed();
             }
+            ActionKind::Group(ops) => {
+                if action.branch_id != self.branch().branch.branch_id {
+                    self.branch_focus(branch_idx)?;
+                }
+
+                // TODO: smarter handling
+                self.selected_bulk_idx = None;
+                self.selection_anchor_bulk_idx = None;
+
+                for op in ops.iter().rev() {
+                    if let Some(frame_idx) = op.undo(&mut self.branch_mut().branch.script) {
+                        self.invalidate(frame_idx);
+                    }
+                }
+
+                self.branch_mut().extra_cam.clear();
+                self.recompute_extra_camera_frame_data_if_needed();
+            }
             ActionKind::Hide => {
                 self.branches[branch_idx].branch.is_hidden = false;
             }
@@ -2859,11 +3593,10 @@ impl Editor {
             }
         }
 
-        self.redo_log.push(action.clone());
+        self.undo_log.pop();
+        self.redo_log.push(action);
 
-        self.db
-            .update_after_undo(&self.branches[branch_idx].branch, &action.kind)?;
-        Ok(())
+        Ok(branch_idx)
     }
 
     /// Redoes the last action if any.
@@ -2875,7 +3608,19 @@ impl Editor {
             return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        let Some(action) = self.redo_log.pop() else {
+        let branch_idx = self.redo_in_memory()?;
+
+        let action = self.undo_log.last().unwrap();
+        self.db
+            .update_after_redo(&self.branches[branch_idx].branch, &action.kind)?;
+        Ok(())
+    }
+
+    /// Redoes the last action if any without storing the change in the database.
+    ///
+    /// Returns the index of the branch that the action applied to.
+    fn redo_in_memory(&mut self) -> ManualOpResult<usize> {
+        let Some(action) = self.redo_log.last().cloned() else {
             return Err(ManualOpError::UserError(
                 "there are no actions to redo".to_owned(),
             ));
@@ -2896,6 +3641,7 @@ impl Editor {
 
                 // TODO: smarter handling
                 self.selected_bulk_idx = None;
+                self.selection_anchor_bulk_idx = None;
 
                 if let Some(frame_idx) = op.apply(&mut self.branch_mut().branch.script) {
                     self.invalidate(frame_idx);
@@ -2904,6 +3650,24 @@ impl Editor {
                 self.branch_mut().extra_cam.clear();
                 self.recompute_extra_camera_frame_data_if_needed();
             }
+            ActionKind::Group(ops) => {
+                if action.branch_id != self.branch().branch.branch_id {
+                    self.branch_focus(branch_idx)?;
+                }
+
+                // TODO: smarter handling
+                self.selected_bulk_idx = None;
+                self.selection_anchor_bulk_idx = None;
+
+                for op in ops {
+                    if let Some(frame_idx) = op.apply(&mut self.branch_mut().branch.script) {
+                        self.invalidate(frame_idx);
+                    }
+                }
+
+                self.branch_mut().extra_cam.clear();
+                self.recompute_extra_camera_frame_data_if_needed();
+            }
             ActionKind::Hide => {
                 self.branches[branch_idx].branch.is_hidden = true;
             }
@@ -2912,11 +3676,96 @@ impl Editor {
             }
         }
 
-        self.undo_log.push(action.clone());
+        self.redo_log.pop();
+        self.undo_log.push(action);
+
+        Ok(branch_idx)
+    }
+
+    /// Undoes all actions in the undo log.
+    ///
+    /// The changes are stored in the database in a single transaction.
+    pub fn undo_all(&mut self) -> ManualOpResult<()> {
+        // Check this upfront to avoid undoing only some of the actions.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if self.undo_log.is_empty() {
+            return Err(ManualOpError::UserError(
+                "there are no actions to undo".to_owned(),
+            ));
+        }
+
+        let first_action_idx = self.redo_log.len();
+        let mut branch_idxs = Vec::new();
+        let mut result = Ok(());
+        while !self.undo_log.is_empty() {
+            match self.undo_in_memory() {
+                Ok(branch_idx) => {
+                    if !branch_idxs.contains(&branch_idx) {
+                        branch_idxs.push(branch_idx);
+                    }
+                }
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
 
+        // Store the steps done so far even on error, so that the database matches the editor.
+        let branches: Vec<_> = branch_idxs
+            .into_iter()
+            .map(|branch_idx| &self.branches[branch_idx].branch)
+            .collect();
         self.db
-            .update_after_redo(&self.branches[branch_idx].branch, &action.kind)?;
-        Ok(())
+            .update_after_undo_all(&branches, &self.redo_log[first_action_idx..])?;
+
+        result
+    }
+
+    /// Redoes all actions in the redo log.
+    ///
+    /// The changes are stored in the database in a single transaction.
+    pub fn redo_all(&mut self) -> ManualOpResult<()> {
+        // Check this upfront to avoid redoing only some of the actions.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if self.redo_log.is_empty() {
+            return Err(ManualOpError::UserError(
+                "there are no actions to redo".to_owned(),
+            ));
+        }
+
+        let first_action_idx = self.undo_log.len();
+        let mut branch_idxs = Vec::new();
+        let mut result = Ok(());
+        while !self.redo_log.is_empty() {
+            match self.redo_in_memory() {
+                Ok(branch_idx) => {
+                    if !branch_idxs.contains(&branch_idx) {
+                        branch_idxs.push(branch_idx);
+                    }
+                }
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+
+        // Store the steps done so far even on error, so that the database matches the editor.
+        let branches: Vec<_> = branch_idxs
+            .into_iter()
+            .map(|branch_idx| &self.branches[branch_idx].branch)
+            .collect();
+        self.db
+            .update_after_redo_all(&branches, &self.undo_log[first_action_idx..])?;
+
+        result
     }
 
     /// Selects the given frame bulk.
@@ -2938,9 +3787,64 @@ impl Editor {
         }
 
         self.selected_bulk_idx = Some(bulk_idx);
+        self.selection_anchor_bulk_idx = None;
+        Ok(())
+    }
+
+    /// Selects the given frame bulk, extending the range selection if the "extend selection" key
+    /// is pressed.
+    fn select_bulk_or_extend(&mut self, bulk_idx: usize) -> ManualOpResult<()> {
+        let anchor = if self.prev_keyboard_state.extend_selection {
+            self.selected_bulk_idx
+                .map(|idx| self.selection_anchor_bulk_idx.unwrap_or(idx))
+        } else {
+            None
+        };
+
+        self.select_bulk(bulk_idx)?;
+        self.selection_anchor_bulk_idx = anchor;
         Ok(())
     }
 
+    /// Selects the frame bulk with the visible collision closest to the given screen point.
+    pub fn select_collided(
+        &mut self,
+        world_to_screen: impl Fn(Vec3) -> Option<Vec2>,
+        screen_point: Vec2,
+    ) -> ManualOpResult<()> {
+        let branch = self.branch();
+        let bulk_idx = iter::zip(
+            branch.frames.iter().skip(1),
+            bulk_idx_and_is_last(&branch.branch.script.lines),
+        )
+        // Add frame indices.
+        .enumerate()
+        // Skip past hidden frames.
+        .filter(|(frame_idx, _)| *frame_idx >= self.first_shown_frame_idx)
+        // Take only frames with a collision.
+        .filter(|(_, (frame, _))| wall_collision_normal(frame).is_some())
+        // Convert to screen and take only successfully converted coordinates.
+        .filter_map(|(_, (frame, (bulk_idx, _, _)))| {
+            world_to_screen(frame.state.player.pos).map(|screen| (screen, bulk_idx))
+        })
+        // Find closest to the screen point.
+        .min_by(|(screen_a, _), (screen_b, _)| {
+            let dist_a = screen_a.distance_squared(screen_point);
+            let dist_b = screen_b.distance_squared(screen_point);
+            dist_a.total_cmp(&dist_b)
+        })
+        // Extract bulk index.
+        .map(|(_, bulk_idx)| bulk_idx);
+
+        let Some(bulk_idx) = bulk_idx else {
+            return Err(ManualOpError::UserError(
+                "there are no visible collisions".to_owned(),
+            ));
+        };
+
+        self.select_bulk(bulk_idx)
+    }
+
     /// Selects the next frame bulk.
     pub fn select_next(&mut self) -> ManualOpResult<()> {
         let bulk_idx = if let Some(bulk_idx) = self.selected_bulk_idx {
@@ -2948,7 +3852,7 @@ impl Editor {
         } else {
             0
         };
-        self.select_bulk(bulk_idx)
+        self.select_bulk_or_extend(bulk_idx)
     }
 
     /// Selects the previous frame bulk.
@@ -2958,7 +3862,7 @@ impl Editor {
         } else {
             self.script().frame_bulks().count().saturating_sub(1)
         };
-        self.select_bulk(bulk_idx)
+        self.select_bulk_or_extend(bulk_idx)
     }
 
     /// Deletes the selected line, if any.
@@ -2989,9 +3893,12 @@ impl Editor {
             return Ok(());
         }
 
-        let Some(bulk_idx) = self.selected_bulk_idx else {
+        let Some((first_bulk_idx, last_bulk_idx)) = self.selected_bulk_range() else {
             return Err(ManualOpError::NoSelectedBulk);
         };
+        if first_bulk_idx != last_bulk_idx {
+            return self.delete_selected_range();
+        }
 
         let (line_idx, line) = self
             .branch()
@@ -3001,7 +3908,7 @@ impl Editor {
             .iter()
             .enumerate()
             .filter(|(_, line)| matches!(line, Line::FrameBulk(_)))
-            .nth(bulk_idx)
+            .nth(first_bulk_idx)
             .unwrap();
 
         let mut buffer = Vec::new();
@@ -3019,6 +3926,63 @@ impl Editor {
         Ok(())
     }
 
+    /// Deletes all frame bulks in the selected range, and any lines between them.
+    ///
+    /// This is done as a single operation, so it can be undone in one step.
+    pub fn delete_selected_range(&mut self) -> ManualOpResult<()> {
+        // Don't delete during active adjustments because they store the frame bulk index.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
+
+        let Some((first_bulk_idx, last_bulk_idx)) = self.selected_bulk_range() else {
+            return Err(ManualOpError::NoSelectedBulk);
+        };
+
+        let lines = &self.branch().branch.script.lines;
+        let mut bulk_line_idxs = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| matches!(line, Line::FrameBulk(_)))
+            .map(|(line_idx, _)| line_idx)
+            .skip(first_bulk_idx);
+        let first_line_idx = bulk_line_idxs.next().unwrap();
+        let last_line_idx = if last_bulk_idx == first_bulk_idx {
+            first_line_idx
+        } else {
+            bulk_line_idxs
+                .nth(last_bulk_idx - first_bulk_idx - 1)
+                .unwrap()
+        };
+
+        let mut buffer = Vec::new();
+        hltas::write::gen_lines(&mut buffer, &lines[first_line_idx..=last_line_idx])
+            .expect("writing to an in-memory buffer should never fail");
+        let from = String::from_utf8(buffer)
+            .expect("Line serialization should never produce invalid UTF-8");
+
+        let op = Operation::ReplaceMultiple {
+            first_line_idx,
+            from,
+            to: String::new(),
+        };
+        self.apply_operation(op)?;
+
+        // Mirror the selection behavior of deleting a single frame bulk.
+        let bulk_count = self.script().frame_bulks().count();
+        self.selected_bulk_idx = if !self.select_after_delete || bulk_count == 0 {
+            None
+        } else {
+            Some(first_bulk_idx.min(bulk_count - 1))
+        };
+
+        Ok(())
+    }
+
     /// Deletes the last frame bulk, if any.
     pub fn delete_last(&mut self) -> ManualOpResult<()> {
         // Don't delete during active adjustments because they store the frame bulk index.
@@ -3060,6 +4024,53 @@ impl Editor {
         Ok(())
     }
 
+    /// Inserts a copy of the selected frame bulk right after it.
+    ///
+    /// The selection stays on the original frame bulk.
+    pub fn duplicate_selected_bulk(&mut self) -> ManualOpResult<()> {
+        // Don't insert during active adjustments because they store the frame bulk index.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
+
+        let Some(bulk_idx) = self.selected_bulk_idx else {
+            return Err(ManualOpError::NoSelectedBulk);
+        };
+
+        let (line_idx, line) = self
+            .branch()
+            .branch
+            .script
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| matches!(line, Line::FrameBulk(_)))
+            .nth(bulk_idx)
+            .unwrap();
+
+        let bulk_frame_count = line.frame_bulk().unwrap().frame_count.get() as usize;
+        let frame_count = lines_frame_count(&self.script().lines).saturating_add(bulk_frame_count);
+        self.check_frame_count(frame_count)?;
+
+        let mut buffer = Vec::new();
+        hltas::write::gen_line(&mut buffer, line)
+            .expect("writing to an in-memory buffer should never fail");
+        let buffer = String::from_utf8(buffer)
+            .expect("Line serialization should never produce invalid UTF-8");
+
+        let op = Operation::Insert {
+            line_idx: line_idx + 1,
+            line: buffer,
+        };
+        self.apply_operation(op)?;
+
+        Ok(())
+    }
+
     /// Splits frame bulk at hovered frame.
     pub fn split(&mut self) -> ManualOpResult<()> {
         // Don't split during active adjustments because they store the frame bulk index.
@@ -3172,12 +4183,90 @@ impl Editor {
         Ok(())
     }
 
-    /// Sets pitch of the selected frame bulk.
-    pub fn set_pitch(&mut self, new_pitch: Option<f32>) -> ManualOpResult<()> {
-        // Don't toggle during active adjustments for consistency with other operations.
-        if self.is_any_adjustment_active() {
-            return Err(ManualOpError::CannotDoDuringAdjustment);
-        }
+    /// Returns the selected frame bulk as HLTAS text.
+    pub fn selected_bulk_text(&self) -> ManualOpResult<String> {
+        let Some(bulk_idx) = self.selected_bulk_idx else {
+            return Err(ManualOpError::NoSelectedBulk);
+        };
+        let bulk = self.script().frame_bulks().nth(bulk_idx).unwrap();
+
+        let mut buffer = Vec::new();
+        hltas::write::gen_frame_bulk(&mut buffer, bulk)
+            .expect("writing to an in-memory buffer should never fail");
+        let text = String::from_utf8(buffer)
+            .expect("FrameBulk serialization should never produce invalid UTF-8");
+
+        Ok(text)
+    }
+
+    /// Replaces the settings of the selected frame bulk with the ones from the HLTAS text.
+    ///
+    /// The frame count of the selected frame bulk is kept.
+    pub fn paste_bulk(&mut self, text: &str) -> ManualOpResult<()> {
+        // Don't toggle during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
+
+        let Some(bulk_idx) = self.selected_bulk_idx else {
+            return Err(ManualOpError::NoSelectedBulk);
+        };
+
+        let Ok((rest, Line::FrameBulk(mut new_bulk))) = hltas::read::line(text.trim()) else {
+            return Err(ManualOpError::UserError(
+                "the text should be a frame bulk".to_owned(),
+            ));
+        };
+        if !rest.trim().is_empty() {
+            return Err(ManualOpError::UserError(
+                "the text should contain exactly one frame bulk".to_owned(),
+            ));
+        }
+
+        let (line_idx, bulk) = self
+            .branch()
+            .branch
+            .script
+            .lines
+            .iter()
+            .enumerate()
+            .filter_map(|(line_idx, line)| line.frame_bulk().map(|bulk| (line_idx, bulk)))
+            .nth(bulk_idx)
+            .unwrap();
+
+        new_bulk.frame_count = bulk.frame_count;
+        if new_bulk == *bulk {
+            return Ok(());
+        }
+
+        let mut buffer = Vec::new();
+        hltas::write::gen_frame_bulk(&mut buffer, bulk)
+            .expect("writing to an in-memory buffer should never fail");
+        let from = String::from_utf8(buffer)
+            .expect("FrameBulk serialization should never produce invalid UTF-8");
+
+        let mut buffer = Vec::new();
+        hltas::write::gen_frame_bulk(&mut buffer, &new_bulk)
+            .expect("writing to an in-memory buffer should never fail");
+        let to = String::from_utf8(buffer)
+            .expect("FrameBulk serialization should never produce invalid UTF-8");
+
+        let op = Operation::Replace { line_idx, from, to };
+        self.apply_operation(op)?;
+
+        Ok(())
+    }
+
+    /// Sets pitch of the selected frame bulk.
+    pub fn set_pitch(&mut self, new_pitch: Option<f32>) -> ManualOpResult<()> {
+        // Don't toggle during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
 
         if self.in_camera_editor {
             return Err(ManualOpError::CannotDoInCameraEditor);
@@ -3286,6 +4375,48 @@ impl Editor {
         Ok(())
     }
 
+    /// Snaps yaw of the selected frame bulk to the closest vectorial compensation table angle.
+    ///
+    /// The table is computed on first use, which takes a few seconds.
+    pub fn snap_selected_yaw_to_vct(&mut self) -> ManualOpResult<()> {
+        // Don't toggle during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
+
+        let Some(bulk_idx) = self.selected_bulk_idx else {
+            return Err(ManualOpError::NoSelectedBulk);
+        };
+        let bulk = self.script().frame_bulks().nth(bulk_idx).unwrap();
+        let Some(&yaw) = bulk.yaw() else {
+            return Err(ManualOpError::UserError(
+                "the selected frame bulk does not have a yaw".to_owned(),
+            ));
+        };
+
+        let entry = Vct::get().find_best(yaw.to_radians());
+        let mut snapped = entry.angle_radians().to_degrees();
+        // The table angles are within ±180 degrees; keep the yaw on the same turn as before.
+        snapped += ((yaw - snapped) / 360.).round() * 360.;
+
+        if snapped == yaw {
+            return Ok(());
+        }
+
+        let op = Operation::SetYaw {
+            bulk_idx,
+            from: yaw,
+            to: snapped,
+        };
+        self.apply_operation(op)?;
+
+        Ok(())
+    }
+
     fn replace_multiple(
         &mut self,
         first_line_idx: usize,
@@ -3373,6 +4504,71 @@ impl Editor {
         Ok(())
     }
 
+    /// Returns the largest frame count for the frame bulk that keeps the script within
+    /// `max_frame_count`.
+    fn max_bulk_frame_count(&self, bulk_idx: usize) -> u32 {
+        let bulk_frame_count = self
+            .script()
+            .frame_bulks()
+            .nth(bulk_idx)
+            .map_or(0, |bulk| bulk.frame_count.get() as usize);
+        let other_frame_count = lines_frame_count(&self.script().lines) - bulk_frame_count;
+
+        self.max_frame_count
+            .saturating_sub(other_frame_count)
+            .try_into()
+            .unwrap_or(u32::MAX)
+    }
+
+    /// Returns an error if a script with this many frames would exceed `max_frame_count`.
+    fn check_frame_count(&self, frame_count: usize) -> ManualOpResult<()> {
+        if frame_count > self.max_frame_count {
+            return Err(ManualOpError::UserError(format!(
+                "the script would have {frame_count} frames, which is more than the maximum of {}",
+                self.max_frame_count
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Sets frame count of the selected bulk.
+    pub fn set_frame_count(&mut self, new_frame_count: u32) -> ManualOpResult<()> {
+        // Don't toggle during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
+
+        let Some(bulk_idx) = self.selected_bulk_idx else {
+            return Err(ManualOpError::NoSelectedBulk);
+        };
+
+        if new_frame_count == 0 {
+            return Err(ManualOpError::UserError(
+                "the frame count must be positive".to_owned(),
+            ));
+        }
+
+        let bulk = self.script().frame_bulks().nth(bulk_idx).unwrap();
+        let frame_count = (lines_frame_count(&self.script().lines)
+            - bulk.frame_count.get() as usize)
+            .saturating_add(new_frame_count as usize);
+        self.check_frame_count(frame_count)?;
+
+        let op = Operation::SetFrameCount {
+            bulk_idx,
+            from: bulk.frame_count.get(),
+            to: new_frame_count,
+        };
+        self.apply_operation(op)?;
+
+        Ok(())
+    }
+
     /// Sets frame time of the selected bulk.
     pub fn set_frame_time(&mut self, new_frame_time: String) -> ManualOpResult<()> {
         // Don't toggle during active adjustments for consistency with other operations.
@@ -3413,6 +4609,74 @@ impl Editor {
         Ok(())
     }
 
+    /// Sets frame time of the selected bulk, rescaling its frame count to preserve the duration.
+    ///
+    /// If either frame time cannot be parsed, only the frame time is set.
+    pub fn set_frame_time_preserving_duration(
+        &mut self,
+        new_frame_time: String,
+    ) -> ManualOpResult<()> {
+        // Don't toggle during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
+
+        let Some(bulk_idx) = self.selected_bulk_idx else {
+            return Err(ManualOpError::NoSelectedBulk);
+        };
+
+        let bulk = self.script().frame_bulks().nth(bulk_idx).unwrap();
+
+        if bulk.frame_time == new_frame_time {
+            return Ok(());
+        }
+
+        let new_frame_count = match (
+            bulk.frame_time.parse::<f32>(),
+            new_frame_time.parse::<f32>(),
+        ) {
+            (Ok(old), Ok(new)) if old > 0. && new > 0. => {
+                let frame_count = bulk.frame_count.get() as f32 * old / new;
+                Some((frame_count.round() as u32).max(1))
+            }
+            _ => None,
+        };
+
+        let set_frame_time = Operation::SetFrameTime {
+            bulk_idx,
+            from: bulk.frame_time.clone(),
+            to: new_frame_time,
+        };
+
+        let Some(new_frame_count) = new_frame_count else {
+            self.apply_operation(set_frame_time)?;
+            return Ok(());
+        };
+
+        let frame_count = (lines_frame_count(&self.script().lines)
+            - bulk.frame_count.get() as usize)
+            .saturating_add(new_frame_count as usize);
+        self.check_frame_count(frame_count)?;
+
+        let set_frame_count = Operation::SetFrameCount {
+            bulk_idx,
+            from: bulk.frame_count.get(),
+            to: new_frame_count,
+        };
+
+        self.begin_undo_group();
+        let result = self.apply_operations(vec![set_frame_time, set_frame_count]);
+        let end_result = self.end_undo_group();
+        result?;
+        end_result?;
+
+        Ok(())
+    }
+
     /// Sets commands of the selected bulk.
     pub fn set_commands(&mut self, new_command: Option<String>) -> ManualOpResult<()> {
         // Don't toggle during active adjustments for consistency with other operations.
@@ -3453,6 +4717,59 @@ impl Editor {
         Ok(())
     }
 
+    /// Inserts a console command to run at the hovered frame.
+    ///
+    /// HLTAS has no standalone console command lines, so the command is added to the frame bulk
+    /// starting at the hovered frame. If the hovered frame is in the middle of a frame bulk, the
+    /// frame bulk is split there first. Existing commands of the frame bulk run before the new one.
+    pub fn insert_command(&mut self, command: String) -> ManualOpResult<()> {
+        // Don't insert during active adjustments because they store the frame bulk index.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
+
+        let Some(frame_idx) = self.hovered_frame_idx else {
+            return Err(ManualOpError::NoHoveredFrame);
+        };
+
+        if command.is_empty() {
+            return Err(ManualOpError::UserError(
+                "the console command must not be empty".to_owned(),
+            ));
+        }
+
+        let Some((line_idx, split)) = split_line_at_frame(&self.script().lines, frame_idx) else {
+            return Err(ManualOpError::UserError(
+                "there is no frame bulk at the hovered frame".to_owned(),
+            ));
+        };
+
+        // Past the last frame bulk there's nothing to add the command to.
+        let Some(line) = self.script().lines.get(line_idx) else {
+            return Err(ManualOpError::UserError(
+                "there is no frame bulk at the hovered frame".to_owned(),
+            ));
+        };
+        let mut to = match split {
+            None => vec![line.clone()],
+            Some((first, second)) => vec![first, second],
+        };
+
+        let bulk = to.last_mut().unwrap().frame_bulk_mut().unwrap();
+        bulk.console_command = Some(match bulk.console_command.take() {
+            Some(existing) => format!("{existing};{command}"),
+            None => command,
+        });
+
+        self.replace_multiple(line_idx, 1, &to)?;
+
+        Ok(())
+    }
+
     /// Rewrites the script with a completely new version.
     pub fn rewrite(&mut self, new_script: HLTAS) -> ManualOpResult<()> {
         // Don't toggle during active adjustments for consistency with other operations.
@@ -3491,893 +4808,2140 @@ impl Editor {
         Ok(())
     }
 
-    /// Applies global smoothing to the entire script.
-    pub fn apply_global_smoothing(&mut self) -> ManualOpResult<()> {
-        // Don't apply during active adjustments for consistency with other operations.
+    /// Mirrors the yaws of the frame bulks from `start_bulk` to `end_bulk` (inclusive) about the
+    /// `axis_yaw`.
+    ///
+    /// Every yaw becomes `2 * axis_yaw - yaw`. Only constant yaws and strafing towards a yaw or
+    /// along a line are changed; other strafing directions and yawspeeds are kept as is.
+    pub fn mirror_yaws_in_range(
+        &mut self,
+        start_bulk: usize,
+        end_bulk: usize,
+        axis_yaw: f32,
+    ) -> ManualOpResult<()> {
+        // Don't toggle during active adjustments for consistency with other operations.
         if self.is_any_adjustment_active() {
             return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        // Only smooth when we have all accurate frames.
-        if !self.has_all_accurate_frames() {
-            return Err(ManualOpError::UserError(
-                "all frames must be accurate (simulated by the \
-                 second game) to apply global smoothing"
-                    .to_owned(),
-            ));
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
         }
 
-        let frames = &self.branch().frames;
-
-        let yaws = frames.iter().map(|f| f.state.prev_frame_input.yaw);
-        let pitches = frames.iter().map(|f| f.state.prev_frame_input.pitch);
-
-        let smoothed_yaws = smoothed_views(
-            self.smooth_window_s,
-            self.smooth_small_window_s,
-            self.smooth_small_window_multiplier,
-            &frames[..frames.len()],
-            yaws,
-        );
+        let (first_line_idx, last_line_idx) = self.bulk_range_line_idxs(start_bulk, end_bulk)?;
 
-        let smoothed_pitches = smoothed_views(
-            self.smooth_window_s,
-            self.smooth_small_window_s,
-            self.smooth_small_window_multiplier,
-            &frames[..frames.len()],
-            pitches,
-        );
+        let from = &self.script().lines[first_line_idx..=last_line_idx];
+        let mut to = from.to_vec();
+        for line in &mut to {
+            let Line::FrameBulk(bulk) = line else {
+                continue;
+            };
 
-        let mut line = "target_yaw_override".to_string();
-        // Skip the first frame because it is the initial frame before the start of the TAS.
-        for yaw in &smoothed_yaws[1..] {
-            let yaw = yaw.to_degrees();
-            write!(&mut line, " {yaw}").unwrap();
+            match &mut bulk.auto_actions.movement {
+                Some(AutoMovement::SetYaw(yaw))
+                | Some(AutoMovement::Strafe(StrafeSettings {
+                    dir: StrafeDir::Yaw(yaw) | StrafeDir::Line { yaw },
+                    ..
+                })) => *yaw = 2. * axis_yaw - *yaw,
+                _ => (),
+            }
         }
 
-        let op = Operation::Insert { line_idx: 0, line };
-        self.apply_operation(op)?;
-
-        let mut line = "pitch_override".to_string();
-        for pitch in &smoothed_pitches[1..] {
-            let pitch = pitch.to_degrees();
-            write!(&mut line, " {pitch}").unwrap();
+        if to == from {
+            return Ok(());
         }
 
-        let op = Operation::Insert { line_idx: 1, line };
-        self.apply_operation(op)?;
+        self.replace_multiple(first_line_idx, to.len(), &to)?;
 
         Ok(())
     }
 
-    /// Applies smoothing to the segment under cursor.
-    pub fn apply_smoothing_to_hovered_segment(&mut self) -> ManualOpResult<()> {
-        // Don't apply during active adjustments for consistency with other operations.
+    /// Mirrors the movement of the selected frame bulks, turning left into right and vice versa.
+    ///
+    /// Yaws are negated, strafing directions are swapped, and so are the left and right movement
+    /// keys. Strafing towards a point is kept as is. The range selection is kept.
+    pub fn mirror_selected(&mut self) -> ManualOpResult<()> {
+        // Don't mirror during active adjustments because they store the frame bulk index.
         if self.is_any_adjustment_active() {
             return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        if !self.in_camera_editor {
-            return Err(ManualOpError::CannotDoInMovementEditor);
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
         }
 
-        let Some(hovered_frame_idx) = self.hovered_frame_idx else {
-            return Err(ManualOpError::NoHoveredFrame);
-        };
-
-        // Find the input region the user is pointing at.
-        let frames = &self.branch().frames;
-        let Some((start, end)) = self.branch().extra_cam[hovered_frame_idx].smoothing_input_region
-        else {
-            return Err(ManualOpError::UserError(
-                "you need to point the cursor at an orange / blue segment to do this".to_owned(),
-            ));
+        let Some((first_bulk_idx, last_bulk_idx)) = self.selected_bulk_range() else {
+            return Err(ManualOpError::NoSelectedBulk);
         };
 
-        // Only smooth when we have all accurate frames.
-        if self.branch().first_predicted_frame <= end {
-            return Err(ManualOpError::UserError(
-                "all frames in the segment must be accurate (simulated by the \
-                 second game) to apply smoothing"
-                    .to_owned(),
-            ));
-        }
+        let anchor = self.selection_anchor_bulk_idx;
+        self.apply_operation(Operation::MirrorYaws {
+            first_bulk_idx,
+            bulk_count: last_bulk_idx - first_bulk_idx + 1,
+        })?;
+        // Mirroring doesn't change the frame bulk count, so the range is still valid.
+        self.selection_anchor_bulk_idx = anchor;
 
-        let yaws = frames.iter().map(|f| f.state.prev_frame_input.yaw);
-        let mut smoothed_yaws = smoothed_views(
-            self.smooth_window_s,
-            self.smooth_small_window_s,
-            self.smooth_small_window_multiplier,
-            &frames[start..=end],
-            yaws,
-        );
+        Ok(())
+    }
 
-        // Skip the first frame because it is the initial frame before the start of the TAS.
-        let first = start.max(1);
-        if start == 0 {
-            smoothed_yaws.remove(0);
+    /// Reverses the order of the selected frame bulks.
+    ///
+    /// Lines other than frame bulks stay in place. The range selection is kept.
+    pub fn reverse_selected(&mut self) -> ManualOpResult<()> {
+        // Don't reverse during active adjustments because they store the frame bulk index.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        // Convert to degrees for .hltas.
-        for yaw in &mut smoothed_yaws {
-            *yaw = yaw.to_degrees();
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
         }
 
-        // Figure out where to insert the line.
-        let (line_idx, repeat) =
-            line_idx_and_repeat_at_frame(&self.branch().branch.script.lines, first - 1).unwrap();
+        let Some((first_bulk_idx, last_bulk_idx)) = self.selected_bulk_range() else {
+            return Err(ManualOpError::NoSelectedBulk);
+        };
 
-        if repeat == 0 {
-            let mut line = "target_yaw_override".to_string();
-            for yaw in smoothed_yaws {
-                write!(&mut line, " {yaw}").unwrap();
-            }
+        if first_bulk_idx == last_bulk_idx {
+            return Ok(());
+        }
 
-            // There's already a frame bulk edge here, no need to split.
-            let op = Operation::Insert { line_idx, line };
-            self.apply_operation(op)?;
-            Ok(())
-        } else {
-            let target_yaw_override = Line::TargetYawOverride(smoothed_yaws);
+        let anchor = self.selection_anchor_bulk_idx;
+        self.apply_operation(Operation::ReverseBulkOrder {
+            first_bulk_idx,
+            bulk_count: last_bulk_idx - first_bulk_idx + 1,
+        })?;
+        // Reversing doesn't change the frame bulk count, so the range is still valid.
+        self.selection_anchor_bulk_idx = anchor;
 
-            // We need to insert the line in the middle of a frame bulk, so split it.
-            let mut line = self.branch().branch.script.lines[line_idx].clone();
+        Ok(())
+    }
 
-            let mut buffer = Vec::new();
-            hltas::write::gen_lines(&mut buffer, [&line])
-                .expect("writing to an in-memory buffer should never fail");
-            let from = String::from_utf8(buffer)
-                .expect("Line serialization should never produce invalid UTF-8");
+    /// Enables autojump on the frame bulks from `start_bulk` to `end_bulk` (inclusive).
+    ///
+    /// Frame bulks that already have autojump are left as is, and ducktap is replaced with
+    /// autojump, same as when toggling autojump on each frame bulk.
+    pub fn autojump_range(&mut self, start_bulk: usize, end_bulk: usize) -> ManualOpResult<()> {
+        // Don't toggle during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
 
-            let mut new_line = line.clone();
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
 
-            let bulk = line.frame_bulk_mut().unwrap();
-            let new_bulk = new_line.frame_bulk_mut().unwrap();
+        let (first_line_idx, last_line_idx) = self.bulk_range_line_idxs(start_bulk, end_bulk)?;
 
-            bulk.frame_count = NonZeroU32::new(repeat).unwrap();
-            new_bulk.frame_count = NonZeroU32::new(new_bulk.frame_count.get() - repeat).unwrap();
+        let script = self.script();
+        let mut to = script.lines[first_line_idx..=last_line_idx].to_vec();
+        for (bulk, bulk_idx) in zip(
+            to.iter_mut().filter_map(Line::frame_bulk_mut),
+            start_bulk..=end_bulk,
+        ) {
+            if matches!(
+                bulk.auto_actions.leave_ground_action,
+                Some(LeaveGroundAction {
+                    type_: LeaveGroundActionType::Jump,
+                    ..
+                })
+            ) {
+                continue;
+            }
 
-            let mut buffer = Vec::new();
-            hltas::write::gen_lines(&mut buffer, [&line, &target_yaw_override, &new_line])
-                .expect("writing to an in-memory buffer should never fail");
-            let to = String::from_utf8(buffer)
-                .expect("Line serialization should never produce invalid UTF-8");
+            *bulk = ToggleAutoActionTarget::AutoJump.apply(script, bulk_idx);
+        }
 
-            let op = Operation::ReplaceMultiple {
-                first_line_idx: line_idx,
-                from,
-                to,
-            };
-            self.apply_operation(op)?;
-            Ok(())
+        if to == script.lines[first_line_idx..=last_line_idx] {
+            return Ok(());
         }
+
+        self.replace_multiple(first_line_idx, to.len(), &to)?;
+
+        Ok(())
     }
 
-    /// Hides frames before the hovered frame, or shows all frames if there's no hovered frame.
-    pub fn hide_frames_up_to_hovered(&mut self) -> ManualOpResult<()> {
-        // Don't apply during active adjustments for consistency with other operations.
+    /// Sets the yaws of the frame bulks from `start_bulk` to `end_bulk` (inclusive) to their
+    /// average.
+    ///
+    /// Every yaw is weighted by the horizontal speed summed over the simulated frames of its frame
+    /// bulk, so the frame bulks that cover more distance matter more. If there are no simulated
+    /// frames with speed, all yaws are weighted equally. Only constant yaws and strafing towards a
+    /// yaw or along a line are changed.
+    pub fn flatten_yaws_in_range(
+        &mut self,
+        start_bulk: usize,
+        end_bulk: usize,
+    ) -> ManualOpResult<()> {
+        // Don't toggle during active adjustments for consistency with other operations.
         if self.is_any_adjustment_active() {
             return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        match self.hovered_frame_idx {
-            None => self.first_shown_frame_idx = 0,
-            // If we're pressing hide again on the first visible frame, unhide instead. This is
-            // nicer than struggling to look away to unhide.
-            Some(frame_idx) if frame_idx == self.first_shown_frame_idx => {
-                self.first_shown_frame_idx = 0;
-            }
-            Some(frame_idx) => {
-                let frame_count = self
-                    .branch()
-                    .branch
-                    .script
-                    .frame_bulks()
-                    .map(|bulk| bulk.frame_count.get() as usize)
-                    .sum::<usize>();
-
-                self.first_shown_frame_idx = min(frame_idx, frame_count.saturating_sub(1));
-
-                // Check if we need to unselect or unhover anything now hidden.
-                let hovered_frame_bulk_idx =
-                    bulk_idx_and_repeat_at_frame(self.script(), self.first_shown_frame_idx)
-                        .unwrap()
-                        .0;
-
-                if let Some(selected_bulk_idx) = self.selected_bulk_idx {
-                    if selected_bulk_idx < hovered_frame_bulk_idx {
-                        // All frames of the selected bulk got hidden, so unselect it.
-                        self.selected_bulk_idx = None;
-                    }
-                }
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
 
-                if let Some(hovered_bulk_idx) = self.hovered_bulk_idx {
-                    if hovered_bulk_idx < hovered_frame_bulk_idx {
-                        // All frames of the hovered bulk got hidden, so unhover it.
-                        self.hovered_bulk_idx = None;
-                    }
-                }
-            }
+        let (first_line_idx, last_line_idx) = self.bulk_range_line_idxs(start_bulk, end_bulk)?;
+
+        let frames = &self.branch().frames;
+        let (yaws, weights): (Vec<f32>, Vec<f32>) = bulk_and_first_frame_idx(self.script())
+            .skip(start_bulk)
+            .take(end_bulk - start_bulk + 1)
+            .filter_map(|(bulk, first_frame_idx)| {
+                let yaw = bulk.yaw()?;
+                let weight = frames
+                    .iter()
+                    .skip(first_frame_idx)
+                    .take(bulk.frame_count.get() as usize)
+                    .map(|frame| frame.state.player.vel.truncate().length())
+                    .sum::<f32>();
+                Some((yaw.to_radians(), weight))
+            })
+            .unzip();
+
+        if yaws.is_empty() {
+            return Err(ManualOpError::UserError(
+                "there are no frame bulks with a yaw in the range".to_owned(),
+            ));
+        }
+
+        // Unwrap so that averaging yaws across ±180 works.
+        let yaws: Vec<f32> = unwrap_angles(yaws.into_iter()).collect();
+        let total_weight: f32 = weights.iter().sum();
+        let average = if total_weight > 0. {
+            zip(&yaws, &weights)
+                .map(|(yaw, weight)| yaw * weight)
+                .sum::<f32>()
+                / total_weight
+        } else {
+            yaws.iter().sum::<f32>() / yaws.len() as f32
+        };
+        let average = normalize_yaw(average.to_degrees());
+
+        let from = &self.script().lines[first_line_idx..=last_line_idx];
+        let mut to = from.to_vec();
+        for yaw in to
+            .iter_mut()
+            .filter_map(Line::frame_bulk_mut)
+            .filter_map(|bulk| bulk.yaw_mut())
+        {
+            *yaw = average;
+        }
+
+        if to == from {
+            return Ok(());
         }
 
+        self.replace_multiple(first_line_idx, to.len(), &to)?;
+
         Ok(())
     }
 
-    // You MUST check and recompute `extra_cam` after calling this.
-    pub fn apply_accurate_frame(
+    /// Sets the frame time of the frame bulks from `start_bulk` to `end_bulk` (inclusive),
+    /// scaling their frame counts to preserve their duration.
+    ///
+    /// Returns the indices of the frame bulks whose duration could not be preserved exactly
+    /// because the scaled frame count is not an integer. Their frame count is rounded.
+    pub fn retime_range(
         &mut self,
-        frame: AccurateFrame,
-        truncate_on_mismatch: bool,
-    ) -> Option<PlayRequest> {
-        if frame.generation != self.generation {
-            return None;
+        start_bulk: usize,
+        end_bulk: usize,
+        new_frame_time: &str,
+    ) -> ManualOpResult<Vec<usize>> {
+        // Don't toggle during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        // TODO: make this nicer somehow maybe?
-        if frame.frame_idx == 0 {
-            // Initial frame is the same for all branches and between smoothed/unsmoothed.
-            for branch_idx in 0..self.branches.len() {
-                let branch = &mut self.branches[branch_idx];
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
 
-                if branch.auto_smoothing.frames.is_empty() {
-                    branch.auto_smoothing.frames.push(frame.frame.clone());
-                }
+        let new_frame_time_value = match new_frame_time.parse::<f32>() {
+            Ok(x) if x > 0. => x,
+            _ => {
+                return Err(ManualOpError::UserError(format!(
+                    "invalid frame time: {new_frame_time}"
+                )))
+            }
+        };
 
-                if branch.frames.is_empty() {
-                    branch.frames.push(frame.frame.clone());
-                    branch.first_predicted_frame = 1;
-                    branch.extra_cam.clear();
-                }
+        let (first_line_idx, last_line_idx) = self.bulk_range_line_idxs(start_bulk, end_bulk)?;
+
+        let from = &self.script().lines[first_line_idx..=last_line_idx];
+        let mut to = from.to_vec();
+        let mut inexact = Vec::new();
+        for (bulk_idx, bulk) in (start_bulk..).zip(to.iter_mut().filter_map(Line::frame_bulk_mut)) {
+            let Ok(frame_time) = bulk.frame_time.parse::<f32>() else {
+                return Err(ManualOpError::UserError(format!(
+                    "frame bulk {bulk_idx} has an invalid frame time: {}",
+                    bulk.frame_time
+                )));
+            };
+
+            let frame_count = bulk.frame_count.get() as f32 * frame_time / new_frame_time_value;
+            let rounded = frame_count.round();
+            // Frame times like 0.010000001 are not exact, so allow for a small error.
+            if (frame_count - rounded).abs() > 0.01 || rounded < 1. {
+                inexact.push(bulk_idx);
             }
 
-            return None;
+            bulk.frame_count = NonZeroU32::new((rounded as u32).max(1)).unwrap();
+            bulk.frame_time = new_frame_time.to_owned();
         }
 
-        let branch = &mut self.branches[frame.branch_idx];
-
-        if frame.frame_idx > branch.first_predicted_frame {
-            // TODO: we can still use newer frames.
-            return None;
+        if to == from {
+            return Ok(inexact);
         }
 
-        if frame.is_smoothed {
-            if !self.auto_smoothing {
-                return None;
-            }
+        let frame_count = (lines_frame_count(&self.script().lines) - lines_frame_count(from))
+            .saturating_add(lines_frame_count(&to));
+        self.check_frame_count(frame_count)?;
 
-            let frames = &mut branch.auto_smoothing.frames;
+        self.replace_multiple(first_line_idx, to.len(), &to)?;
 
-            if frames.len() == frame.frame_idx {
-                frames.push(frame.frame);
-            } else {
-                let current_frame = &mut frames[frame.frame_idx];
-                if *current_frame != frame.frame {
-                    *current_frame = frame.frame;
-                    frames.truncate(frame.frame_idx + 1);
-                }
-            }
+        Ok(inexact)
+    }
 
-            return None;
+    /// Returns the line indices of the frame bulks `start_bulk` and `end_bulk`.
+    fn bulk_range_line_idxs(
+        &self,
+        start_bulk: usize,
+        end_bulk: usize,
+    ) -> ManualOpResult<(usize, usize)> {
+        if start_bulk > end_bulk {
+            return Err(ManualOpError::UserError(
+                "the start frame bulk must not be after the end frame bulk".to_owned(),
+            ));
         }
 
-        branch.first_predicted_frame = max(frame.frame_idx + 1, branch.first_predicted_frame);
-
-        if branch.frames.len() == frame.frame_idx {
-            branch.frames.push(frame.frame);
-            branch.extra_cam.clear();
-        } else {
-            let current_frame = &mut branch.frames[frame.frame_idx];
-            if *current_frame != frame.frame {
-                *current_frame = frame.frame;
+        let bulk_line_idxs: Vec<usize> = self
+            .script()
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| matches!(line, Line::FrameBulk(_)))
+            .map(|(line_idx, _)| line_idx)
+            .collect();
+        let (Some(&first_line_idx), Some(&last_line_idx)) =
+            (bulk_line_idxs.get(start_bulk), bulk_line_idxs.get(end_bulk))
+        else {
+            return Err(ManualOpError::UserError(
+                "the frame bulk range is out of bounds".to_owned(),
+            ));
+        };
 
-                branch.first_predicted_frame =
-                    min(branch.first_predicted_frame, frame.frame_idx + 1);
-                branch.extra_cam.clear();
+        Ok((first_line_idx, last_line_idx))
+    }
 
-                if truncate_on_mismatch {
-                    branch.frames.truncate(frame.frame_idx + 1);
-                }
-            }
+    /// Normalizes the yaws of all frame bulks into the [-180, 180) range.
+    ///
+    /// Only constant yaws and strafing towards a yaw or along a line are changed. These only depend
+    /// on the yaw modulo 360, so the movement stays the same. Yaws in change lines are left as is
+    /// because they are interpolated towards.
+    pub fn normalize_yaws(&mut self) -> ManualOpResult<()> {
+        // Don't normalize during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        if self.auto_smoothing {
-            let branch = &mut self.branches[frame.branch_idx];
-            let frame_count = branch
-                .branch
-                .script
-                .frame_bulks()
-                .map(|bulk| bulk.frame_count.get() as usize)
-                .sum::<usize>();
+        let mut new_script = self.script().clone();
+        for yaw in new_script
+            .frame_bulks_mut()
+            .filter_map(|bulk| bulk.yaw_mut())
+        {
+            *yaw = normalize_yaw(*yaw);
+        }
 
-            if frame.frame_idx + 1 == frame_count {
-                let mut smoothed_script = branch.branch.script.clone();
+        // Goes through a single operation, so it's undone all at once.
+        self.rewrite(new_script)
+    }
 
-                // Enable vectorial strafing if it wasn't enabled.
-                smoothed_script
-                    .lines
-                    .insert(0, Line::VectorialStrafing(true));
-                smoothed_script.lines.insert(
-                    1,
-                    Line::VectorialStrafingConstraints(
-                        VectorialStrafingConstraints::VelocityYawLocking { tolerance: 0. },
-                    ),
-                );
+    /// Applies global smoothing to the entire script.
+    pub fn apply_global_smoothing(&mut self) -> ManualOpResult<()> {
+        // Don't apply during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
 
-                // Compute and insert the smoothed TargetYawOverride and PitchOverride line.
-                let yaws = branch.frames.iter().map(|f| f.state.prev_frame_input.yaw);
-                let pitches = branch.frames.iter().map(|f| f.state.prev_frame_input.pitch);
+        // Only smooth when we have all accurate frames.
+        if !self.has_all_accurate_frames() {
+            return Err(ManualOpError::UserError(
+                "all frames must be accurate (simulated by the \
+                 second game) to apply global smoothing"
+                    .to_owned(),
+            ));
+        }
 
-                let mut smoothed_yaws = smoothed_views(
-                    self.smooth_window_s,
-                    self.smooth_small_window_s,
-                    self.smooth_small_window_multiplier,
-                    &branch.frames,
-                    yaws,
-                );
-                let mut smoothed_pitches = smoothed_views(
-                    self.smooth_window_s,
-                    self.smooth_small_window_s,
-                    self.smooth_small_window_multiplier,
-                    &branch.frames,
-                    pitches,
-                );
+        let frames = &self.branch().frames;
 
-                // First yaw corresponds to the initial frame, which is not controlled by the TAS.
-                smoothed_yaws.remove(0);
-                for yaw in &mut smoothed_yaws {
-                    *yaw = yaw.to_degrees();
-                }
-
-                let line = Line::TargetYawOverride(smoothed_yaws);
-                smoothed_script.lines.insert(2, line);
+        let frame_times: Vec<f32> = frames.iter().map(|f| f.parameters.frame_time).collect();
+        let yaws: Vec<f32> = frames
+            .iter()
+            .map(|f| f.state.prev_frame_input.yaw)
+            .collect();
+        let pitches: Vec<f32> = frames
+            .iter()
+            .map(|f| f.state.prev_frame_input.pitch)
+            .collect();
 
-                smoothed_pitches.remove(0);
-                for pitch in &mut smoothed_pitches {
-                    *pitch = pitch.to_degrees();
-                }
-                let line = Line::PitchOverride(smoothed_pitches);
-                smoothed_script.lines.insert(3, line);
+        let smoothed_yaws = self.smooth_kind.smoothed_views(
+            self.smooth_window_s,
+            self.smooth_small_window_s,
+            self.smooth_small_window_multiplier,
+            &frame_times,
+            &yaws,
+        );
 
-                // Remove all lines disabling vectorial strafing.
-                let mut i = 0;
-                while i < smoothed_script.lines.len() {
-                    if matches!(smoothed_script.lines[i], Line::VectorialStrafing(false)) {
-                        smoothed_script.lines.remove(i);
-                    } else {
-                        i += 1;
-                    }
-                }
+        let smoothed_pitches = self.smooth_kind.smoothed_views(
+            self.smooth_window_s,
+            self.smooth_small_window_s,
+            self.smooth_small_window_multiplier,
+            &frame_times,
+            &pitches,
+        );
 
-                branch.auto_smoothing.script = Some(smoothed_script.clone());
-                return Some(PlayRequest {
-                    script: smoothed_script,
-                    generation: self.generation,
-                    branch_idx: self.branch_idx,
-                    is_smoothed: true,
-                });
-            }
+        let mut line = "target_yaw_override".to_string();
+        // Skip the first frame because it is the initial frame before the start of the TAS.
+        for yaw in &smoothed_yaws[1..] {
+            let yaw = yaw.to_degrees();
+            write!(&mut line, " {yaw}").unwrap();
         }
 
-        None
-    }
+        let yaw_op = Operation::Insert { line_idx: 0, line };
 
-    pub fn set_stop_frame(&mut self, stop_frame: u32) -> ManualOpResult<()> {
-        // Don't do this during active adjustments for consistency with other operations.
-        if self.is_any_adjustment_active() {
-            return Err(ManualOpError::CannotDoDuringAdjustment);
+        let mut line = "pitch_override".to_string();
+        for pitch in &smoothed_pitches[1..] {
+            let pitch = pitch.to_degrees();
+            write!(&mut line, " {pitch}").unwrap();
         }
 
-        self.branch_mut().branch.stop_frame = stop_frame;
-        self.db.update_branch(&self.branch().branch)?;
+        let pitch_op = Operation::Insert { line_idx: 1, line };
+
+        self.begin_undo_group();
+        let result = self.apply_operations(vec![yaw_op, pitch_op]);
+        let end_result = self.end_undo_group();
+        result?;
+        end_result?;
 
         Ok(())
     }
 
-    pub fn set_stop_frame_to_hovered(&mut self) -> ManualOpResult<()> {
-        // Don't do this during active adjustments for consistency with other operations.
-        if self.is_any_adjustment_active() {
-            return Err(ManualOpError::CannotDoDuringAdjustment);
+    /// Sets the smoothing window size to the duration of the segment under cursor.
+    ///
+    /// Returns the new smoothing window size in seconds.
+    pub fn set_smooth_window_from_hovered_segment(&mut self) -> ManualOpResult<f32> {
+        if !self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInMovementEditor);
         }
 
-        let Some(frame_idx) = self.hovered_frame_idx else {
+        let Some(hovered_frame_idx) = self.hovered_frame_idx else {
             return Err(ManualOpError::NoHoveredFrame);
         };
-        self.set_stop_frame(frame_idx.try_into().unwrap())?;
-
-        Ok(())
-    }
-
-    pub fn branch_clone(&mut self) -> ManualOpResult<()> {
-        // Don't do this during active adjustments for consistency with other operations.
-        if self.is_any_adjustment_active() {
-            return Err(ManualOpError::CannotDoDuringAdjustment);
-        }
 
-        let mut new_branch = self.branch().clone();
-        self.db.insert_branch(&mut new_branch.branch)?;
-        self.undo_log.push(Action {
-            branch_id: new_branch.branch.branch_id,
-            kind: if new_branch.branch.is_hidden {
-                ActionKind::Hide
-            } else {
-                ActionKind::Show
-            },
-        });
-        self.redo_log.clear();
-        self.branches.push(new_branch);
+        let Some((start, end)) = self.branch().extra_cam[hovered_frame_idx].smoothing_input_region
+        else {
+            return Err(ManualOpError::UserError(
+                "you need to point the cursor at an orange / blue segment to do this".to_owned(),
+            ));
+        };
 
-        // Switch to the cloned branch.
-        self.branch_focus(self.branches.len() - 1)?;
+        let duration = self.branch().frames[start..=end]
+            .iter()
+            .map(|frame| frame.parameters.frame_time)
+            .sum();
+        self.set_smooth_window_s(duration);
 
-        Ok(())
+        Ok(duration)
     }
 
-    pub fn branch_focus(&mut self, branch_idx: usize) -> ManualOpResult<()> {
-        // Don't do this during active adjustments for consistency with other operations.
+    /// Applies smoothing to the segment under cursor.
+    pub fn apply_smoothing_to_hovered_segment(&mut self) -> ManualOpResult<()> {
+        // Don't apply during active adjustments for consistency with other operations.
         if self.is_any_adjustment_active() {
             return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        if self.branch_idx == branch_idx {
-            return Err(ManualOpError::UserError(
-                "this branch is already focused".to_owned(),
-            ));
+        if !self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInMovementEditor);
         }
 
-        let Some(branch) = self.branches.get(branch_idx) else {
-            return Err(ManualOpError::BranchDoesNotExist);
+        let Some(hovered_frame_idx) = self.hovered_frame_idx else {
+            return Err(ManualOpError::NoHoveredFrame);
         };
 
-        self.branch_idx = branch_idx;
-        self.selected_bulk_idx = None;
-        self.hovered_bulk_idx = None;
-        self.hovered_frame_idx = None;
-        self.db.switch_to_branch(&branch.branch)?;
-
-        if let Some((last_bulk, frame_idx)) = bulk_and_first_frame_idx(self.script()).last() {
-            let last_frame_idx = frame_idx + last_bulk.frame_count.get() as usize;
-            if self.first_shown_frame_idx + 1 >= last_frame_idx {
-                // The whole branch would be hidden, so show all frames instead.
-                self.first_shown_frame_idx = 0
-            }
-        }
-
-        Ok(())
-    }
-
-    pub fn branch_focus_next(&mut self) -> ManualOpResult<()> {
-        let Some(branch_idx) = (self.branch_idx + 1..self.branches.len())
-            .chain(0..self.branch_idx)
-            .find(|&idx| !self.branches[idx].branch.is_hidden)
+        // Find the input region the user is pointing at.
+        let Some((start, end)) = self.branch().extra_cam[hovered_frame_idx].smoothing_input_region
         else {
             return Err(ManualOpError::UserError(
-                "there are no other visible branches".to_owned(),
+                "you need to point the cursor at an orange / blue segment to do this".to_owned(),
             ));
         };
 
-        self.branch_focus(branch_idx)
+        self.insert_smoothed_yaws(start, end)
     }
 
-    pub fn branch_hide(&mut self, branch_idx: usize) -> ManualOpResult<()> {
-        // Don't do this during active adjustments for consistency with other operations.
+    /// Applies smoothing to the frames from `start_frame` to `end_frame` (inclusive).
+    ///
+    /// Inserts a `target_yaw_override` line with the smoothed yaws, splitting the frame bulk if
+    /// needed.
+    pub fn apply_smoothing_to_range(
+        &mut self,
+        start_frame: usize,
+        end_frame: usize,
+    ) -> ManualOpResult<()> {
+        // Don't apply during active adjustments for consistency with other operations.
         if self.is_any_adjustment_active() {
             return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        // Don't try to hide the current branch on its own: this causes it to hide only when we
-        // switch from it subsequently, which is confusing.
-        if self.branch_idx == branch_idx {
+        if self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInCameraEditor);
+        }
+
+        if start_frame > end_frame {
             return Err(ManualOpError::UserError(
-                "you cannot hide the currently focused branch".to_owned(),
+                "the start frame must not be after the end frame".to_owned(),
             ));
         }
 
-        let Some(branch) = self.branches.get_mut(branch_idx) else {
-            return Err(ManualOpError::BranchDoesNotExist);
-        };
-
-        if branch.branch.is_hidden {
-            return Ok(());
+        // Frame 0 is the initial frame which has no input.
+        if end_frame == 0 {
+            return Err(ManualOpError::UserError(
+                "the range must include frames after the initial frame".to_owned(),
+            ));
         }
 
-        branch.branch.is_hidden = true;
-        self.db.hide_branch(&branch.branch)?;
-        self.undo_log.push(Action {
-            branch_id: branch.branch.branch_id,
-            kind: ActionKind::Hide,
-        });
-        self.redo_log.clear();
-
-        Ok(())
+        self.insert_smoothed_yaws(start_frame, end_frame)
     }
 
-    pub fn branch_hide_and_focus_next(&mut self) -> ManualOpResult<()> {
-        let Some(next_branch_idx) = (self.branch_idx + 1..self.branches.len())
-            .chain(0..self.branch_idx)
-            .find(|&idx| !self.branches[idx].branch.is_hidden)
-        else {
+    /// Inserts a `target_yaw_override` line with smoothed yaws of frames from `start` to `end`
+    /// (inclusive), splitting the frame bulk if needed.
+    fn insert_smoothed_yaws(&mut self, start: usize, end: usize) -> ManualOpResult<()> {
+        // Only smooth when we have all accurate frames.
+        if self.branch().first_predicted_frame <= end || self.branch().frames.len() <= end {
             return Err(ManualOpError::UserError(
-                "there are no other visible branches".to_owned(),
+                "all frames in the segment must be accurate (simulated by the \
+                 second game) to apply smoothing"
+                    .to_owned(),
             ));
-        };
+        }
 
-        let curr_branch_idx = self.branch_idx;
-        self.branch_focus(next_branch_idx)?;
-        self.branch_hide(curr_branch_idx)
+        let frames = &self.branch().frames[start..=end];
+        let frame_times: Vec<f32> = frames.iter().map(|f| f.parameters.frame_time).collect();
+        let yaws: Vec<f32> = frames
+            .iter()
+            .map(|f| f.state.prev_frame_input.yaw)
+            .collect();
+        let mut smoothed_yaws = self.smooth_kind.smoothed_views(
+            self.smooth_window_s,
+            self.smooth_small_window_s,
+            self.smooth_small_window_multiplier,
+            &frame_times,
+            &yaws,
+        );
+
+        // Skip the first frame because it is the initial frame before the start of the TAS.
+        let first = start.max(1);
+        if start == 0 {
+            smoothed_yaws.remove(0);
+        }
+
+        // Convert to degrees for .hltas.
+        for yaw in &mut smoothed_yaws {
+            *yaw = yaw.to_degrees();
+        }
+
+        let target_yaw_override = Line::TargetYawOverride(smoothed_yaws);
+        let op = self
+            .insert_line_at_frame_op(first - 1, &target_yaw_override)
+            .unwrap();
+        self.apply_operation(op)?;
+        Ok(())
     }
 
-    pub fn branch_show(&mut self, branch_idx: usize) -> ManualOpResult<()> {
-        // Don't do this during active adjustments for consistency with other operations.
+    /// Inserts a vectorial strafing yaw offset change line at the hovered frame.
+    ///
+    /// The offset changes to `final_value` degrees over `over` seconds. The frame bulk is split if
+    /// the hovered frame is in the middle of it.
+    pub fn add_yaw_offset_change(&mut self, final_value: f32, over: f32) -> ManualOpResult<()> {
+        // Don't apply during active adjustments for consistency with other operations.
         if self.is_any_adjustment_active() {
             return Err(ManualOpError::CannotDoDuringAdjustment);
         }
 
-        let Some(branch) = self.branches.get_mut(branch_idx) else {
-            return Err(ManualOpError::BranchDoesNotExist);
+        if !self.in_camera_editor {
+            return Err(ManualOpError::CannotDoInMovementEditor);
+        }
+
+        let Some(hovered_frame_idx) = self.hovered_frame_idx else {
+            return Err(ManualOpError::NoHoveredFrame);
         };
 
-        if !branch.branch.is_hidden {
-            return Ok(());
+        if over < 0. {
+            return Err(ManualOpError::UserError(
+                "the change duration must not be negative".to_owned(),
+            ));
         }
 
-        branch.branch.is_hidden = false;
-        self.db.show_branch(&branch.branch)?;
-        self.undo_log.push(Action {
-            branch_id: branch.branch.branch_id,
-            kind: ActionKind::Show,
+        let change = Line::Change(Change {
+            target: ChangeTarget::VectorialStrafingYawOffset,
+            final_value,
+            over,
         });
-        self.redo_log.clear();
 
+        let Some(op) = self.insert_line_at_frame_op(hovered_frame_idx, &change) else {
+            return Err(ManualOpError::UserError(
+                "there is no frame bulk at the hovered frame".to_owned(),
+            ));
+        };
+
+        self.apply_operation(op)?;
         Ok(())
     }
 
-    fn draw_current_branch(&self, mut draw: impl FnMut(DrawLine)) {
-        let branch = self.branch();
+    /// Returns the operation inserting `line` so that it starts applying at `frame_idx`.
+    ///
+    /// If `frame_idx` is in the middle of a frame bulk, the frame bulk is split there. Returns
+    /// `None` if `frame_idx` is past the end of the script.
+    fn insert_line_at_frame_op(&self, frame_idx: usize, line: &Line) -> Option<Operation> {
+        let lines = &self.script().lines;
+        let (line_idx, split) = split_line_at_frame(lines, frame_idx)?;
 
-        let smoothing_input_region = if self.in_camera_editor {
-            self.hovered_frame_idx
-                .and_then(|idx| branch.extra_cam[idx].smoothing_input_region)
-        } else {
-            None
+        let Some((first, second)) = split else {
+            // There's already a frame bulk edge here, no need to split.
+            let mut buffer = Vec::new();
+            hltas::write::gen_lines(&mut buffer, [line])
+                .expect("writing to an in-memory buffer should never fail");
+            let line = String::from_utf8(buffer)
+                .expect("Line serialization should never produce invalid UTF-8");
+
+            return Some(Operation::Insert { line_idx, line });
         };
 
-        // Draw regular frames.
-        let mut collided_this_bulk = false;
+        // We need to insert the line in the middle of a frame bulk, so split it.
+        let mut buffer = Vec::new();
+        hltas::write::gen_lines(&mut buffer, [&lines[line_idx]])
+            .expect("writing to an in-memory buffer should never fail");
+        let from = String::from_utf8(buffer)
+            .expect("Line serialization should never produce invalid UTF-8");
 
-        // For drawing small camera lines in the movement editor.
-        let mut last_camera_line_origin_vector = None;
-
-        // Note: there's no iterator cloning, which means all values are computed once, in one go.
-        let iter = iter::zip(
-            // Pairs of frames: (0, 1), (1, 2), (2, 3) and so on.
-            branch.frames.iter().tuple_windows(),
-            // For second frame in pair: its frame bulk index and whether it's last in its bulk.
-            bulk_idx_and_is_last(&branch.branch.script.lines),
-        )
-        .enumerate();
-        for (prev_idx, ((prev, frame), (bulk_idx, bulk, is_last_in_bulk))) in iter {
-            let idx = prev_idx + 1;
+        let mut buffer = Vec::new();
+        hltas::write::gen_lines(&mut buffer, [&first, line, &second])
+            .expect("writing to an in-memory buffer should never fail");
+        let to = String::from_utf8(buffer)
+            .expect("Line serialization should never produce invalid UTF-8");
 
-            // Figure out if we had a collision this frame.
-            let mut collided_this_frame = false;
-            for trace in &frame.state.move_traces {
-                // If we bumped into something along the way...
-                if trace.fraction == 1. {
-                    break;
-                }
+        Some(Operation::ReplaceMultiple {
+            first_line_idx: line_idx,
+            from,
+            to,
+        })
+    }
 
-                // And it wasn't a ground or a ceiling...
-                let n = trace.plane_normal.z;
-                if n != -1. && n != 1. {
-                    // We have a collision.
-                    collided_this_frame = true;
-                    break;
-                }
-            }
+    /// Hides frames before the hovered frame, or shows all frames if there's no hovered frame.
+    pub fn hide_frames_up_to_hovered(&mut self) -> ManualOpResult<()> {
+        // Don't apply during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
 
-            if collided_this_frame {
-                collided_this_bulk = true;
+        match self.hovered_frame_idx {
+            None => self.first_shown_frame_idx = 0,
+            // If we're pressing hide again on the first visible frame, unhide instead. This is
+            // nicer than struggling to look away to unhide.
+            Some(frame_idx) if frame_idx == self.first_shown_frame_idx => {
+                self.first_shown_frame_idx = 0;
             }
+            Some(frame_idx) => self.set_first_shown_frame_idx(frame_idx),
+        }
 
-            // If frame is predicted (inaccurate).
-            let is_predicted = idx >= branch.first_predicted_frame;
-            // If frame is part of selected frame bulk.
-            let is_selected_bulk = self.selected_bulk_idx == Some(bulk_idx);
-            // If frame is part of hovered frame bulk.
-            let is_hovered_bulk = self.hovered_bulk_idx == Some(bulk_idx);
-            // If frame is hovered.
-            let is_hovered = self.hovered_frame_idx == Some(idx);
-            // If frame is the stop frame.
-            let is_stop_frame = branch.branch.stop_frame as usize == idx;
-            // If frame is the end of norefresh for norefresh until stop frame.
-            let is_norefresh_until_stop_frame =
-                !is_stop_frame && self.norefresh_until_stop_frame_frame_idx == idx;
+        Ok(())
+    }
 
-            // If frame is in the smoothing input region.
-            let in_smoothing_input_region =
-                smoothing_input_region.map_or(false, |(start, end)| idx >= start && idx <= end);
+    /// Hides frames before the given frame.
+    ///
+    /// The frame index is clamped to the last frame of the script.
+    pub fn hide_frames_before(&mut self, frame_idx: usize) -> ManualOpResult<()> {
+        // Don't apply during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
 
-            // How many frames until the visible part, clamped in a way to allow for smooth dimming.
-            let frames_until_hidden = self.first_shown_frame_idx.saturating_sub(idx - 1).min(20);
+        self.set_first_shown_frame_idx(frame_idx);
 
-            // Inaccurate frames get dimmed.
-            let dim_inaccurate = if is_predicted { 0.6 } else { 1. };
-            // Unhovered bulks get dimmed.
-            let dim_unhovered = if is_hovered_bulk || self.in_camera_editor {
-                1.
-            } else {
-                0.7
-            };
-            // Hidden frames become invisible, smoothly transition into visible.
-            let dim_hidden = if frames_until_hidden == 0 {
-                1.
-            } else {
-                (20 - frames_until_hidden) as f32 / 40.
-            };
-            let dim = dim_inaccurate * dim_unhovered * dim_hidden;
+        Ok(())
+    }
 
-            const WHITE: Vec3 = Vec3::new(1., 1., 1.);
-            let color = if self.in_camera_editor {
-                WHITE * dim * 0.5
-            } else {
-                // Deselected bulks get desaturated.
-                let saturation = if is_selected_bulk { 1. } else { 0.3 };
+    fn set_first_shown_frame_idx(&mut self, frame_idx: usize) {
+        let frame_count = lines_frame_count(&self.script().lines);
+        if frame_count == 0 {
+            // There are no frame bulks, so there's nothing to hide.
+            self.first_shown_frame_idx = 0;
+            return;
+        }
 
-                let hue = if collided_this_frame {
-                    // Collided frames are red.
-                    Vec3::new(1., 0., 0.)
-                } else if collided_this_bulk {
-                    // Non-collided frames in collided bulk are pink.
-                    Vec3::new(1., 0.6, 0.6)
-                } else {
-                    // Other frames are green.
-                    Vec3::new(0., 1., 0.)
-                };
+        self.first_shown_frame_idx = min(frame_idx, frame_count.saturating_sub(1));
 
-                WHITE.lerp(hue, saturation) * dim
-            };
+        // Check if we need to unselect or unhover anything now hidden.
+        let hovered_frame_bulk_idx =
+            bulk_idx_and_repeat_at_frame(self.script(), self.first_shown_frame_idx)
+                .unwrap()
+                .0;
 
-            let prev_pos = prev.state.player.pos;
-            let pos = frame.state.player.pos;
+        if let Some(selected_bulk_idx) = self.selected_bulk_idx {
+            if selected_bulk_idx < hovered_frame_bulk_idx {
+                // All frames of the selected bulk got hidden, so unselect it.
+                self.selected_bulk_idx = None;
+            }
+        }
 
-            // Line from previous to this frame position.
-            draw(DrawLine {
-                start: prev_pos,
-                end: pos,
-                color,
-            });
+        if let Some(hovered_bulk_idx) = self.hovered_bulk_idx {
+            if hovered_bulk_idx < hovered_frame_bulk_idx {
+                // All frames of the hovered bulk got hidden, so unhover it.
+                self.hovered_bulk_idx = None;
+            }
+        }
+    }
 
-            let camera_pitch = frame.state.prev_frame_input.pitch;
-            let camera_yaw = frame.state.prev_frame_input.yaw;
-            let camera_vector = forward(camera_pitch, camera_yaw);
+    // You MUST check and recompute `extra_cam` after calling this.
+    pub fn apply_accurate_frame(
+        &mut self,
+        frame: AccurateFrame,
+        truncate_on_mismatch: bool,
+    ) -> Option<PlayRequest> {
+        if frame.generation != self.generation {
+            return None;
+        }
 
-            if self.in_camera_editor {
-                let extra_cam = &branch.extra_cam[idx];
+        // TODO: make this nicer somehow maybe?
+        if frame.frame_idx == 0 {
+            // Initial frame is the same for all branches and between smoothed/unsmoothed.
+            for branch_idx in 0..self.branches.len() {
+                let branch = &mut self.branches[branch_idx];
 
-                // Draw camera angle line.
-                let hue = if in_smoothing_input_region {
-                    Vec3::new(1., 0.75, 0.5)
-                } else if extra_cam.in_smoothing_idempotent_region {
-                    Vec3::new(0., 1., 0.)
-                } else {
-                    Vec3::new(0.5, 0.5, 1.)
-                };
+                if branch.auto_smoothing.frames.is_empty() {
+                    branch.auto_smoothing.frames.push(frame.frame.clone());
+                }
 
-                draw(DrawLine {
-                    start: pos,
-                    end: pos + camera_vector * 5.,
-                    color: hue * dim_inaccurate * dim_hidden,
-                });
+                if branch.frames.is_empty() {
+                    branch.frames.push(frame.frame.clone());
+                    branch.first_predicted_frame = 1;
+                    branch.extra_cam.clear();
+                }
+            }
 
-                for &line_idx in &extra_cam.change_line_that_ends_here {
-                    let perp = perpendicular(prev_pos, pos) * 5.;
-                    let diff = (pos - prev_pos).normalize_or_zero() * 5.;
+            return None;
+        }
 
-                    let dim_unhovered = if self.hovered_line_idx == Some(line_idx) {
-                        1.
-                    } else {
-                        0.7
-                    };
+        let branch = &mut self.branches[frame.branch_idx];
 
-                    // Draw the arrow.
-                    draw(DrawLine {
-                        start: pos - perp - diff,
-                        end: pos,
-                        color: WHITE * dim_hidden * dim_unhovered,
-                    });
-                    draw(DrawLine {
-                        start: pos,
-                        end: pos + perp - diff,
-                        color: WHITE * dim_hidden * dim_unhovered,
-                    });
+        if frame.frame_idx > branch.first_predicted_frame {
+            // TODO: we can still use newer frames.
+            return None;
+        }
 
-                    // Draw the target angle.
-                    let Line::Change(Change {
-                        target,
-                        final_value,
-                        ..
-                    }) = branch.branch.script.lines[line_idx]
-                    else {
-                        unreachable!()
-                    };
+        if frame.is_smoothed {
+            if !self.auto_smoothing {
+                return None;
+            }
 
-                    let target_vector = match target {
-                        ChangeTarget::Yaw | ChangeTarget::VectorialStrafingYaw => {
-                            forward(0., final_value.to_radians())
-                        }
-                        ChangeTarget::Pitch => forward(final_value.to_radians(), camera_yaw),
-                        // TODO: how to draw this? Maybe skip it?
-                        ChangeTarget::VectorialStrafingYawOffset => camera_vector,
-                    };
+            let frames = &mut branch.auto_smoothing.frames;
 
-                    draw(DrawLine {
-                        start: pos,
-                        end: pos + target_vector * 20.,
-                        color: Vec3::new(1., 1., 0.) * dim_hidden * dim_unhovered,
-                    });
+            if frames.len() == frame.frame_idx {
+                frames.push(frame.frame);
+            } else {
+                let current_frame = &mut frames[frame.frame_idx];
+                current_frame.state.rendered_viewangles = frame.frame.state.rendered_viewangles;
+                if *current_frame != frame.frame {
+                    *current_frame = frame.frame;
+                    frames.truncate(frame.frame_idx + 1);
                 }
+            }
 
-                for &camera_line_idx in &extra_cam.camera_line_that_starts_here {
-                    let camera_line = &branch.branch.script.lines[camera_line_idx];
-                    let perp = perpendicular(prev_pos, pos) * 5.;
+            return None;
+        }
 
-                    let hue = match camera_line {
-                        Line::TargetYawOverride { .. } => Vec3::new(1., 0.75, 0.5),
-                        Line::RenderYawOverride { .. } => Vec3::new(1., 0., 0.),
-                        _ => WHITE,
-                    };
+        branch.first_predicted_frame = max(frame.frame_idx + 1, branch.first_predicted_frame);
 
-                    let dim_unhovered = if self.hovered_line_idx == Some(camera_line_idx) {
-                        1.
-                    } else {
-                        0.7
-                    };
+        if branch.frames.len() == frame.frame_idx {
+            branch.frames.push(frame.frame);
+            branch.extra_cam.clear();
+        } else {
+            let current_frame = &mut branch.frames[frame.frame_idx];
 
-                    if let Line::Change(Change { target, .. }) = camera_line {
-                        let diff = (pos - prev_pos).normalize_or_zero() * 5.;
+            // Rendered view angles are only known for accurate frames, so they can't mismatch.
+            current_frame.state.rendered_viewangles = frame.frame.state.rendered_viewangles;
 
-                        // Draw the arrow.
-                        draw(DrawLine {
-                            start: prev_pos - perp + diff,
-                            end: prev_pos,
-                            color: hue * dim_hidden * dim_unhovered,
-                        });
-                        draw(DrawLine {
-                            start: prev_pos,
-                            end: prev_pos + perp + diff,
-                            color: hue * dim_hidden * dim_unhovered,
-                        });
+            if *current_frame != frame.frame {
+                for (name, predicted, accurate) in
+                    current_frame.parameters.diff(&frame.frame.parameters)
+                {
+                    debug!(
+                        "frame {} parameter mismatch: {name} predicted {predicted}, accurate \
+                         {accurate}",
+                        frame.frame_idx
+                    );
+                }
 
-                        // Draw the starting angle.
-                        let target_vector = match target {
-                            ChangeTarget::Yaw | ChangeTarget::VectorialStrafingYaw => {
-                                forward(0., camera_yaw)
-                            }
-                            ChangeTarget::Pitch => camera_vector,
-                            // TODO: how to draw this? Maybe skip it?
-                            ChangeTarget::VectorialStrafingYawOffset => camera_vector,
-                        };
-
-                        draw(DrawLine {
-                            start: prev_pos,
-                            end: prev_pos + target_vector * 20.,
-                            color: Vec3::new(1., 0., 0.) * dim_hidden * dim_unhovered,
-                        });
-                    } else {
-                        draw(DrawLine {
-                            start: prev_pos - perp,
-                            end: prev_pos + perp,
-                            color: hue * dim_hidden * dim_unhovered,
-                        });
+                *current_frame = frame.frame;
 
-                        if let Line::VectorialStrafingConstraints(constraints) = camera_line {
-                            let hue = match constraints {
-                                VectorialStrafingConstraints::VelocityYaw { .. }
-                                | VectorialStrafingConstraints::AvgVelocityYaw { .. }
-                                | VectorialStrafingConstraints::VelocityYawLocking { .. } => {
-                                    Vec3::new(0., 1., 0.)
-                                }
-                                // TODO: for Yaw we can draw the Yaw itself, for YawRange we can
-                                // draw the range too.
-                                VectorialStrafingConstraints::Yaw { .. }
-                                | VectorialStrafingConstraints::YawRange { .. } => {
-                                    Vec3::new(0., 1., 1.)
-                                }
-                                VectorialStrafingConstraints::LookAt { .. } => {
-                                    Vec3::new(1., 0., 1.)
-                                }
-                            };
+                branch.first_predicted_frame =
+                    min(branch.first_predicted_frame, frame.frame_idx + 1);
+                branch.extra_cam.clear();
 
-                            draw(DrawLine {
-                                start: prev_pos,
-                                end: prev_pos + camera_vector * 20.,
-                                color: hue * dim_hidden * dim_unhovered,
-                            });
-                        }
-                    }
+                if truncate_on_mismatch {
+                    branch.frames.truncate(frame.frame_idx + 1);
                 }
-            } else {
-                // If this frame is last in its frame bulk, draw a frame bulk handle.
-                if is_last_in_bulk {
-                    let perp = perpendicular(prev_pos, pos) * 5.;
+            }
+        }
 
-                    draw(DrawLine {
-                        start: pos - perp,
-                        end: pos + perp,
-                        color,
-                    });
-                }
+        if self.auto_smoothing {
+            let branch = &mut self.branches[frame.branch_idx];
+            let frame_count = lines_frame_count(&branch.branch.script.lines);
 
-                // If it's selected and last and the frame bulk has a yaw, draw that.
-                if is_selected_bulk && is_last_in_bulk {
-                    if let Some(yaw) = bulk.yaw() {
-                        let yaw_dir = Vec2::from_angle(yaw.to_radians()).extend(0.);
+            if frame.frame_idx + 1 == frame_count {
+                let mut smoothed_script = branch.branch.script.clone();
 
-                        draw(DrawLine {
-                            start: pos - yaw_dir * 5.,
-                            end: pos + yaw_dir * 20.,
-                            color: Vec3::new(0.5, 0.5, 1.) * dim,
-                        });
-                    }
+                // Enable vectorial strafing if it wasn't enabled.
+                smoothed_script
+                    .lines
+                    .insert(0, Line::VectorialStrafing(true));
+                smoothed_script.lines.insert(
+                    1,
+                    Line::VectorialStrafingConstraints(
+                        VectorialStrafingConstraints::VelocityYawLocking { tolerance: 0. },
+                    ),
+                );
+
+                // Compute and insert the smoothed TargetYawOverride and PitchOverride line.
+                let frame_times: Vec<f32> = branch
+                    .frames
+                    .iter()
+                    .map(|f| f.parameters.frame_time)
+                    .collect();
+                let yaws: Vec<f32> = branch
+                    .frames
+                    .iter()
+                    .map(|f| f.state.prev_frame_input.yaw)
+                    .collect();
+                let pitches: Vec<f32> = branch
+                    .frames
+                    .iter()
+                    .map(|f| f.state.prev_frame_input.pitch)
+                    .collect();
+
+                let mut smoothed_yaws = self.smooth_kind.smoothed_views(
+                    self.smooth_window_s,
+                    self.smooth_small_window_s,
+                    self.smooth_small_window_multiplier,
+                    &frame_times,
+                    &yaws,
+                );
+                let mut smoothed_pitches = self.smooth_kind.smoothed_views(
+                    self.smooth_window_s,
+                    self.smooth_small_window_s,
+                    self.smooth_small_window_multiplier,
+                    &frame_times,
+                    &pitches,
+                );
+
+                // First yaw corresponds to the initial frame, which is not controlled by the TAS.
+                smoothed_yaws.remove(0);
+                for yaw in &mut smoothed_yaws {
+                    *yaw = yaw.to_degrees();
                 }
 
-                // Draw camera angle line if it's different enough from the last one.
-                if last_camera_line_origin_vector
-                    .map(|(origin, angle)| {
-                        pos.distance(origin) > 50. || camera_vector.dot(angle) < 0.98
-                    })
-                    .unwrap_or(true)
-                {
-                    last_camera_line_origin_vector = Some((pos, camera_vector));
+                let line = Line::TargetYawOverride(smoothed_yaws);
+                smoothed_script.lines.insert(2, line);
 
-                    draw(DrawLine {
-                        start: pos,
-                        end: pos + camera_vector * 5.,
-                        color: Vec3::new(0.3, 0.3, 1.) * dim_inaccurate * dim_hidden,
-                    });
+                smoothed_pitches.remove(0);
+                for pitch in &mut smoothed_pitches {
+                    *pitch = pitch.to_degrees();
+                }
+                let line = Line::PitchOverride(smoothed_pitches);
+                smoothed_script.lines.insert(3, line);
+
+                // Remove all lines disabling vectorial strafing.
+                let mut i = 0;
+                while i < smoothed_script.lines.len() {
+                    if matches!(smoothed_script.lines[i], Line::VectorialStrafing(false)) {
+                        smoothed_script.lines.remove(i);
+                    } else {
+                        i += 1;
+                    }
                 }
+
+                branch.auto_smoothing.script = Some(smoothed_script.clone());
+                branch.auto_smoothing.scratch = None;
+                return Some(PlayRequest {
+                    script: smoothed_script,
+                    generation: self.generation,
+                    branch_idx: self.branch_idx,
+                    is_smoothed: true,
+                });
             }
+        }
 
-            // If the frame is hovered and not last in bulk, draw a splitting guide.
-            if is_hovered && (!is_last_in_bulk || self.in_camera_editor) {
-                let perp = perpendicular(prev_pos, pos) * 2.;
+        None
+    }
 
-                let splitting_guide_color = if self.in_camera_editor {
-                    color
-                } else {
-                    color * 0.5
+    /// Applies an operation to a scratch copy of the smoothed script.
+    ///
+    /// The scratch copy is re-simulated and drawn next to the smoothed path, but never saved. It
+    /// is discarded when the smoothed script is recomputed.
+    pub fn smoothed_scratch_apply(&mut self, op: Operation) -> ManualOpResult<()> {
+        if !self.auto_smoothing {
+            return Err(ManualOpError::UserError(
+                "auto-smoothing is not enabled".to_owned(),
+            ));
+        }
+
+        let auto_smoothing = &mut self.branch_mut().auto_smoothing;
+        let Some(smoothed_script) = &auto_smoothing.script else {
+            return Err(ManualOpError::UserError(
+                "the smoothed script is not available yet".to_owned(),
+            ));
+        };
+        if auto_smoothing.frames.is_empty() {
+            return Err(ManualOpError::UserError(
+                "the smoothed frames are not available yet".to_owned(),
+            ));
+        }
+
+        let scratch = auto_smoothing
+            .scratch
+            .get_or_insert_with(|| SmoothingScratch {
+                script: smoothed_script.clone(),
+                frames: auto_smoothing.frames.clone(),
+            });
+
+        if let Some(frame_idx) = op.apply(&mut scratch.script) {
+            scratch.frames.truncate(frame_idx.max(1));
+        }
+
+        Ok(())
+    }
+
+    /// Sets the yaw of a frame bulk in the scratch copy of the smoothed script.
+    ///
+    /// See [`Editor::smoothed_scratch_apply()`].
+    pub fn smoothed_scratch_set_yaw(&mut self, bulk_idx: usize, yaw: f32) -> ManualOpResult<()> {
+        let Some(script) = self
+            .smoothed_scratch_script()
+            .or_else(|| self.smoothed_script())
+        else {
+            return Err(ManualOpError::UserError(
+                "the smoothed script is not available yet".to_owned(),
+            ));
+        };
+
+        let Some(bulk) = script.frame_bulks().nth(bulk_idx) else {
+            return Err(ManualOpError::UserError(format!(
+                "frame bulk {bulk_idx} does not exist"
+            )));
+        };
+        let Some(&from) = bulk.yaw() else {
+            return Err(ManualOpError::UserError(format!(
+                "frame bulk {bulk_idx} does not have a yaw"
+            )));
+        };
+
+        self.smoothed_scratch_apply(Operation::SetYaw {
+            bulk_idx,
+            from,
+            to: yaw,
+        })
+    }
+
+    /// Discards the scratch copy of the smoothed script.
+    pub fn smoothed_scratch_clear(&mut self) {
+        self.branch_mut().auto_smoothing.scratch = None;
+    }
+
+    /// Returns the scratch copy of the smoothed script, if there is one.
+    pub fn smoothed_scratch_script(&self) -> Option<&HLTAS> {
+        self.branch()
+            .auto_smoothing
+            .scratch
+            .as_ref()
+            .map(|scratch| &scratch.script)
+    }
+
+    /// Overrides a movement parameter for predicted frames of the current branch starting from the
+    /// given frame.
+    pub fn set_parameter_override(
+        &mut self,
+        frame_idx: usize,
+        name: String,
+        value: f32,
+    ) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if frame_idx == 0 {
+            return Err(ManualOpError::UserError(
+                "the initial frame cannot have parameter overrides".to_owned(),
+            ));
+        }
+
+        if !Parameters::builder().build().set_by_name(&name, value) {
+            return Err(ManualOpError::UserError(format!(
+                "there's no parameter named {name}"
+            )));
+        }
+
+        let branch = self.branch_mut();
+        if branch.parameter_overrides.is_empty() {
+            branch.parameter_overrides_frame_count = lines_frame_count(&branch.branch.script.lines);
+        }
+
+        let overrides = branch.parameter_overrides.entry(frame_idx).or_default();
+        overrides.retain(|(other, _)| *other != name);
+        overrides.push((name, value));
+
+        self.invalidate_predicted_in_branch(frame_idx);
+
+        Ok(())
+    }
+
+    /// Clears parameter overrides of the current branch at the given frame, or all overrides if no
+    /// frame is given.
+    pub fn clear_parameter_overrides(&mut self, frame_idx: Option<usize>) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        let first_frame_idx = match frame_idx {
+            Some(frame_idx) => {
+                if self
+                    .branch_mut()
+                    .parameter_overrides
+                    .remove(&frame_idx)
+                    .is_none()
+                {
+                    return Err(ManualOpError::UserError(
+                        "there are no parameter overrides at this frame".to_owned(),
+                    ));
+                }
+
+                frame_idx
+            }
+            None => {
+                let overrides = &mut self.branch_mut().parameter_overrides;
+                let Some(&first_frame_idx) = overrides.keys().next() else {
+                    return Ok(());
                 };
 
-                draw(DrawLine {
-                    start: pos - perp,
-                    end: pos + perp,
-                    color: splitting_guide_color,
-                });
+                overrides.clear();
+                first_frame_idx
             }
+        };
 
-            // If the frame is hovered, draw the player bbox.
-            if is_hovered && self.show_player_bbox {
-                let hull = frame.state.player.hull();
+        self.invalidate_predicted_in_branch(first_frame_idx);
 
-                const HALF_SIZE: f32 = 16.;
+        Ok(())
+    }
 
-                let half_height = match hull {
-                    Hull::Standing => 36.,
-                    Hull::Ducked => 18.,
-                    Hull::Point => unreachable!(),
-                };
+    pub fn set_stop_frame(&mut self, stop_frame: u32) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
 
-                let offset = Vec3::new(HALF_SIZE, HALF_SIZE, half_height);
+        self.branch_mut().branch.stop_frame = stop_frame;
+        self.db.update_branch(&self.branch().branch)?;
 
-                let mut draw_aa_cuboid = |corner1: Vec3, corner2: Vec3, color: Vec3| {
-                    let delta = corner2 - corner1;
-                    let dx = delta * Vec3::X;
-                    let dy = delta * Vec3::Y;
-                    let dz = delta * Vec3::Z;
+        Ok(())
+    }
 
-                    let lines = [
-                        // Bottom.
-                        (corner1, corner1 + dx),
+    pub fn set_stop_frame_to_hovered(&mut self) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        let Some(frame_idx) = self.hovered_frame_idx else {
+            return Err(ManualOpError::NoHoveredFrame);
+        };
+        self.set_stop_frame(frame_idx.try_into().unwrap())?;
+
+        Ok(())
+    }
+
+    /// Moves the stop frame by `delta` frames, clamping it to the frames of the script.
+    pub fn nudge_stop_frame(&mut self, delta: i64) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        let total_frames = lines_frame_count(&self.script().lines).min(u32::MAX as usize) as i64;
+        let stop_frame = i64::from(self.stop_frame())
+            .saturating_add(delta)
+            .clamp(0, total_frames);
+        self.set_stop_frame(stop_frame as u32)?;
+
+        Ok(())
+    }
+
+    /// Returns the `bxt_tas_norefresh_until_last_frames` value which puts the norefresh marker on
+    /// the hovered frame.
+    pub fn norefresh_until_last_frames_for_hovered(&self) -> ManualOpResult<usize> {
+        let Some(frame_idx) = self.hovered_frame_idx else {
+            return Err(ManualOpError::NoHoveredFrame);
+        };
+
+        let last_frame_idx = if self.stop_frame() == 0 {
+            self.branch().frames.len() - 1
+        } else {
+            self.stop_frame() as usize
+        };
+
+        if frame_idx > last_frame_idx {
+            return Err(ManualOpError::UserError(
+                "the hovered frame is after the stop frame".to_owned(),
+            ));
+        }
+
+        Ok(last_frame_idx - frame_idx)
+    }
+
+    pub fn branch_clone(&mut self) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        let mut new_branch = self.branch().clone();
+        new_branch.branch.color = Some(self.next_branch_color());
+        self.db.insert_branch(&mut new_branch.branch)?;
+        self.undo_log.push(Action {
+            branch_id: new_branch.branch.branch_id,
+            kind: if new_branch.branch.is_hidden {
+                ActionKind::Hide
+            } else {
+                ActionKind::Show
+            },
+        });
+        self.redo_log.clear();
+        self.branches.push(new_branch);
+
+        // Switch to the cloned branch.
+        self.branch_focus(self.branches.len() - 1)?;
+
+        Ok(())
+    }
+
+    /// Returns the palette color for a newly created branch.
+    fn next_branch_color(&self) -> [u8; 3] {
+        BRANCH_COLORS[self.branches.len() % BRANCH_COLORS.len()]
+    }
+
+    /// Creates a new branch with the frames from `start_frame` to `end_frame` of the current
+    /// branch.
+    ///
+    /// The new branch contains the frame bulks covering the frame range, split at the boundaries,
+    /// and the other lines in between, such as camera lines. Settings lines like `buttons` that
+    /// were in effect at the start of the range are carried over. Prediction in the new branch
+    /// starts from the player state at `start_frame`, but the game will play it from the start of
+    /// the map, so unless `start_frame` is 0, the script needs a save to load, which is noted in a
+    /// comment at the top.
+    pub fn extract_branch(&mut self, start_frame: usize, end_frame: usize) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if start_frame >= end_frame {
+            return Err(ManualOpError::UserError(
+                "the start frame must be before the end frame".to_owned(),
+            ));
+        }
+
+        let script = self.script();
+        let frame_count = lines_frame_count(&script.lines);
+        if end_frame > frame_count {
+            return Err(ManualOpError::UserError(
+                "the frame range is out of bounds".to_owned(),
+            ));
+        }
+
+        let mut lines = Vec::new();
+        if start_frame > 0 {
+            lines.push(Line::Comment(format!(
+                " Extracted from frames {start_frame} to {end_frame}, \
+                 needs a save with the player state at frame {start_frame}."
+            )));
+        }
+
+        // Settings in effect at the start of the range, the last one of every kind.
+        let mut settings: Vec<Line> = Vec::new();
+        let mut extracted = Vec::new();
+
+        let mut frame_idx = 0;
+        for line in &script.lines {
+            match line {
+                Line::FrameBulk(bulk) => {
+                    let count = bulk.frame_count.get() as usize;
+                    let first = frame_idx.max(start_frame);
+                    let last = (frame_idx + count).min(end_frame);
+                    if first < last {
+                        let mut bulk = bulk.clone();
+                        bulk.frame_count = NonZeroU32::new((last - first) as u32).unwrap();
+                        extracted.push(Line::FrameBulk(bulk));
+                    }
+
+                    frame_idx += count;
+                }
+                Line::Buttons(_)
+                | Line::LGAGSTMinSpeed(_)
+                | Line::VectorialStrafing(_)
+                | Line::VectorialStrafingConstraints(_)
+                    if frame_idx < start_frame =>
+                {
+                    settings.retain(|other| mem::discriminant(other) != mem::discriminant(line));
+                    settings.push(line.clone());
+                }
+                _ => {
+                    if (start_frame..end_frame).contains(&frame_idx) {
+                        extracted.push(line.clone());
+                    }
+                }
+            }
+        }
+
+        lines.append(&mut settings);
+        lines.append(&mut extracted);
+
+        let branch = self.branch();
+        let mut new_branch = BranchData::new(Branch {
+            script: HLTAS {
+                properties: script.properties.clone(),
+                lines,
+            },
+            stop_frame: 0,
+            color: Some(self.next_branch_color()),
+            ..branch.branch.clone()
+        });
+
+        // Start prediction from the player state at the start of the range.
+        if let Some(frame) = branch.frames.get(start_frame) {
+            new_branch.frames.push(frame.clone());
+            new_branch.first_predicted_frame =
+                usize::from(start_frame < branch.first_predicted_frame);
+        }
+
+        self.db.insert_branch(&mut new_branch.branch)?;
+        self.undo_log.push(Action {
+            branch_id: new_branch.branch.branch_id,
+            kind: if new_branch.branch.is_hidden {
+                ActionKind::Hide
+            } else {
+                ActionKind::Show
+            },
+        });
+        self.redo_log.clear();
+        self.branches.push(new_branch);
+
+        // Switch to the new branch.
+        self.branch_focus(self.branches.len() - 1)?;
+
+        Ok(())
+    }
+
+    /// Focuses the first visible branch with the given name.
+    pub fn branch_focus_by_name(&mut self, name: &str) -> ManualOpResult<()> {
+        let Some(branch_idx) = self
+            .branches
+            .iter()
+            .position(|branch| !branch.branch.is_hidden && branch.branch.name == name)
+        else {
+            return Err(ManualOpError::UserError(format!(
+                "there's no visible branch named {name:?}"
+            )));
+        };
+
+        self.branch_focus(branch_idx)
+    }
+
+    pub fn branch_focus(&mut self, branch_idx: usize) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        if self.branch_idx == branch_idx {
+            return Err(ManualOpError::UserError(
+                "this branch is already focused".to_owned(),
+            ));
+        }
+
+        let Some(branch) = self.branches.get(branch_idx) else {
+            return Err(ManualOpError::BranchDoesNotExist);
+        };
+
+        self.branch_idx = branch_idx;
+        self.selected_bulk_idx = None;
+        self.selection_anchor_bulk_idx = None;
+        self.hovered_bulk_idx = None;
+        self.hovered_frame_idx = None;
+        self.db.switch_to_branch(&branch.branch)?;
+
+        if let Some((last_bulk, frame_idx)) = bulk_and_first_frame_idx(self.script()).last() {
+            let last_frame_idx = frame_idx.saturating_add(last_bulk.frame_count.get() as usize);
+            if self.first_shown_frame_idx + 1 >= last_frame_idx {
+                // The whole branch would be hidden, so show all frames instead.
+                self.first_shown_frame_idx = 0
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn branch_focus_next(&mut self) -> ManualOpResult<()> {
+        let Some(branch_idx) = (self.branch_idx + 1..self.branches.len())
+            .chain(0..self.branch_idx)
+            .find(|&idx| !self.branches[idx].branch.is_hidden)
+        else {
+            return Err(ManualOpError::UserError(
+                "there are no other visible branches".to_owned(),
+            ));
+        };
+
+        self.branch_focus(branch_idx)
+    }
+
+    pub fn branch_hide(&mut self, branch_idx: usize) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        // Don't try to hide the current branch on its own: this causes it to hide only when we
+        // switch from it subsequently, which is confusing.
+        if self.branch_idx == branch_idx {
+            return Err(ManualOpError::UserError(
+                "you cannot hide the currently focused branch".to_owned(),
+            ));
+        }
+
+        let Some(branch) = self.branches.get_mut(branch_idx) else {
+            return Err(ManualOpError::BranchDoesNotExist);
+        };
+
+        if branch.branch.is_hidden {
+            return Ok(());
+        }
+
+        branch.branch.is_hidden = true;
+        self.db.hide_branch(&branch.branch)?;
+        self.undo_log.push(Action {
+            branch_id: branch.branch.branch_id,
+            kind: ActionKind::Hide,
+        });
+        self.redo_log.clear();
+
+        Ok(())
+    }
+
+    pub fn branch_hide_and_focus_next(&mut self) -> ManualOpResult<()> {
+        let Some(next_branch_idx) = (self.branch_idx + 1..self.branches.len())
+            .chain(0..self.branch_idx)
+            .find(|&idx| !self.branches[idx].branch.is_hidden)
+        else {
+            return Err(ManualOpError::UserError(
+                "there are no other visible branches".to_owned(),
+            ));
+        };
+
+        let curr_branch_idx = self.branch_idx;
+        self.branch_focus(next_branch_idx)?;
+        self.branch_hide(curr_branch_idx)
+    }
+
+    pub fn branch_show(&mut self, branch_idx: usize) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        let Some(branch) = self.branches.get_mut(branch_idx) else {
+            return Err(ManualOpError::BranchDoesNotExist);
+        };
+
+        if !branch.branch.is_hidden {
+            return Ok(());
+        }
+
+        branch.branch.is_hidden = false;
+        self.db.show_branch(&branch.branch)?;
+        self.undo_log.push(Action {
+            branch_id: branch.branch.branch_id,
+            kind: ActionKind::Show,
+        });
+        self.redo_log.clear();
+
+        Ok(())
+    }
+
+    /// Sets the color used to draw the branch when it's not focused.
+    ///
+    /// `None` resets the color to the default.
+    pub fn branch_set_color(
+        &mut self,
+        branch_idx: usize,
+        color: Option<[u8; 3]>,
+    ) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        let Some(branch) = self.branches.get_mut(branch_idx) else {
+            return Err(ManualOpError::BranchDoesNotExist);
+        };
+
+        branch.branch.color = color;
+        self.db.update_branch(&branch.branch)?;
+
+        Ok(())
+    }
+
+    /// Renames the branch with the given index.
+    pub fn rename_branch(&mut self, branch_idx: usize, name: String) -> ManualOpResult<()> {
+        // Don't do this during active adjustments for consistency with other operations.
+        if self.is_any_adjustment_active() {
+            return Err(ManualOpError::CannotDoDuringAdjustment);
+        }
+
+        let Some(branch) = self.branches.get_mut(branch_idx) else {
+            return Err(ManualOpError::BranchDoesNotExist);
+        };
+
+        branch.branch.name = name;
+        self.db.update_branch(&branch.branch)?;
+
+        Ok(())
+    }
+
+    /// Returns the inputs of the simulated frames of the branch in chronological order.
+    ///
+    /// Every frame stores the input that produced it in `prev_frame_input`. The initial frame
+    /// (frame 0) is not simulated, so it has no input of its own: the returned vector starts with
+    /// the input of frame 1, which means that the input at index `i` produced frame `i + 1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the branch with the given index does not exist.
+    pub fn branch_inputs(&self, branch_idx: usize) -> Vec<Input> {
+        self.branches[branch_idx]
+            .frames
+            .iter()
+            .skip(1)
+            .map(|frame| frame.state.prev_frame_input)
+            .collect()
+    }
+
+    /// Returns information about every branch, in branch index order.
+    pub fn branches_info(&self) -> Vec<BranchInfo> {
+        self.branches
+            .iter()
+            .enumerate()
+            .map(|(branch_idx, branch)| BranchInfo {
+                branch_id: branch.branch.branch_id,
+                name: branch.branch.name.clone(),
+                is_hidden: branch.branch.is_hidden,
+                frame_count: lines_frame_count(&branch.branch.script.lines),
+                is_focused: branch_idx == self.branch_idx,
+            })
+            .collect()
+    }
+
+    /// Simulates a single frame starting from the hovered frame.
+    ///
+    /// The hovered frame's state is advanced using the frame bulk and parameters of the frame
+    /// right after it. The branch frames are not modified.
+    pub fn sim_step<T: Trace>(&self, tracer: &T) -> ManualOpResult<bxt_strafe::State> {
+        let Some(frame_idx) = self.hovered_frame_idx else {
+            return Err(ManualOpError::NoHoveredFrame);
+        };
+
+        let frames = &self.branch().frames;
+        let (Some(frame), Some(next_frame)) = (frames.get(frame_idx), frames.get(frame_idx + 1))
+        else {
+            return Err(ManualOpError::UserError(
+                "there are no simulated frames after the hovered frame".to_owned(),
+            ));
+        };
+
+        let Some((bulk_idx, _)) = bulk_idx_and_repeat_at_frame(self.script(), frame_idx) else {
+            return Err(ManualOpError::UserError(
+                "there is no frame bulk after the hovered frame".to_owned(),
+            ));
+        };
+        let bulk = self.script().frame_bulks().nth(bulk_idx).unwrap();
+
+        let (state, _input) = frame
+            .state
+            .clone()
+            .simulate(tracer, next_frame.parameters, bulk);
+        Ok(state)
+    }
+
+    /// Returns how much the final player position moves when a movement parameter is nudged.
+    ///
+    /// The branch is re-simulated twice from the initial frame using the parameters of every frame:
+    /// once as is and once with the field `name` (as in [`Parameters::set_by_name`]) increased by
+    /// `delta`. Returns the difference between the two final positions. The branch is not
+    /// modified.
+    pub fn param_sensitivity<T: Trace>(
+        &self,
+        tracer: &T,
+        name: &str,
+        delta: f32,
+    ) -> ManualOpResult<Vec3> {
+        if Parameters::default().get_by_name(name).is_none() {
+            return Err(ManualOpError::UserError(format!(
+                "there's no parameter named {name}"
+            )));
+        }
+
+        let frames = &self.branch().frames;
+        if frames.len() < 2 {
+            return Err(ManualOpError::UserError(
+                "there are no simulated frames".to_owned(),
+            ));
+        }
+
+        let simulate = |delta: f32| {
+            let mut state = frames[0].state.clone();
+            for (frame, (_, bulk, _)) in
+                zip(&frames[1..], bulk_idx_and_is_last(&self.script().lines))
+            {
+                let mut parameters = frame.parameters;
+                let value = parameters.get_by_name(name).unwrap();
+                parameters.set_by_name(name, value + delta);
+
+                (state, _) = state.simulate(tracer, parameters, bulk);
+            }
+            state.player.pos
+        };
+
+        Ok(simulate(delta) - simulate(0.))
+    }
+
+    /// Suggests a yaw that would avoid the collision on the given frame.
+    ///
+    /// The suggested yaw, in degrees, runs along the wall that the player collided with, in the
+    /// direction of the player's movement. Returns `None` if there was no wall collision.
+    pub fn suggest_yaw_to_avoid_collision(&self, frame_idx: usize) -> Option<f32> {
+        let frames = &self.branch().frames;
+        let frame = frames.get(frame_idx)?;
+        let prev = &frames[frame_idx.checked_sub(1)?];
+
+        let normal = wall_collision_normal(frame)?;
+
+        // Moving perpendicular to the normal grazes the wall.
+        let mut along_wall = Vec2::new(-normal.y, normal.x).try_normalize()?;
+
+        let movement = (frame.state.player.pos - prev.state.player.pos).truncate();
+        if along_wall.dot(movement) < 0. {
+            along_wall = -along_wall;
+        }
+
+        Some(along_wall.y.atan2(along_wall.x).to_degrees())
+    }
+
+    /// Returns the index of the first frame where the player position is outside of the box.
+    ///
+    /// The box is axis-aligned and given by its two opposite corners, `mins` and `maxs`. A position
+    /// exactly on the box boundary counts as inside. Returns `None` if every simulated frame is
+    /// inside the box.
+    pub fn first_frame_leaving_box(&self, mins: Vec3, maxs: Vec3) -> Option<usize> {
+        // Accept the corners in any order.
+        let (mins, maxs) = (mins.min(maxs), mins.max(maxs));
+
+        self.branch().frames.iter().position(|frame| {
+            let pos = frame.state.player.pos;
+            pos.cmplt(mins).any() || pos.cmpgt(maxs).any()
+        })
+    }
+
+    /// Returns the view origin that puts the player position on the given frame at the center of
+    /// the screen when looking with `view_angles` (pitch, yaw, roll in degrees).
+    ///
+    /// The view origin is placed `distance` units back from the player position along the view
+    /// direction. Returns `None` if the frame hasn't been simulated.
+    pub fn recentered_view_origin(
+        &self,
+        frame_idx: usize,
+        view_angles: [f32; 3],
+        distance: f32,
+    ) -> Option<Vec3> {
+        let pos = self.branch().frames.get(frame_idx)?.state.player.pos;
+        let dir = forward(view_angles[0].to_radians(), view_angles[1].to_radians());
+        Some(pos - dir * distance)
+    }
+
+    /// Returns the player path as a Wavefront OBJ.
+    ///
+    /// Every branch is written as a separate object containing a vertex for every frame and a
+    /// polyline through them. The current branch is written first, followed by other visible
+    /// branches if `include_other_branches` is `true`.
+    pub fn path_obj(&self, include_other_branches: bool) -> String {
+        let mut obj = String::new();
+
+        let other_branch_idxs = (0..self.branches.len()).filter(|&idx| {
+            include_other_branches && idx != self.branch_idx && !self.branches[idx].branch.is_hidden
+        });
+
+        // OBJ vertex indices are global and start from 1.
+        let mut first_vertex_idx = 1;
+        for branch_idx in iter::once(self.branch_idx).chain(other_branch_idxs) {
+            let frames = &self.branches[branch_idx].frames;
+
+            writeln!(&mut obj, "o branch_{branch_idx}").unwrap();
+            for frame in frames {
+                let pos = frame.state.player.pos;
+                writeln!(&mut obj, "v {} {} {}", pos.x, pos.y, pos.z).unwrap();
+            }
+
+            if frames.len() >= 2 {
+                obj.push('l');
+                for vertex_idx in first_vertex_idx..first_vertex_idx + frames.len() {
+                    write!(&mut obj, " {vertex_idx}").unwrap();
+                }
+                obj.push('\n');
+            }
+
+            first_vertex_idx += frames.len();
+        }
+
+        obj
+    }
+
+    /// Writes the script of the current branch to a `.hltas` file at `path`.
+    ///
+    /// If `smoothed` is `true`, writes the auto-smoothed script instead, which is available once
+    /// auto-smoothing has processed all frames.
+    pub fn export_hltas_to_path(&self, path: &Path, smoothed: bool) -> eyre::Result<()> {
+        let script = if smoothed {
+            let Some(script) = self.smoothed_script() else {
+                bail!(
+                    "the smoothed script is not available, enable auto-smoothing and wait for \
+                     all frames to be simulated"
+                );
+            };
+            script
+        } else {
+            self.script()
+        };
+
+        let file = File::create(path).context("could not create the file")?;
+        script
+            .to_writer(file)
+            .context("could not write HLTAS to file")?;
+
+        Ok(())
+    }
+
+    /// Converts the positions of the frames of the current branch to screen coordinates.
+    ///
+    /// Hidden and not drawn frames are `None`, as are frames that fail to convert.
+    fn project_shown_frames(
+        &self,
+        world_to_screen: impl Fn(Vec3) -> Option<Vec2>,
+    ) -> Vec<Option<Vec2>> {
+        self.branch()
+            .frames
+            .iter()
+            .enumerate()
+            .map(|(frame_idx, frame)| {
+                if frame_idx < self.first_shown_frame_idx || !self.drawn_frames.contains(&frame_idx)
+                {
+                    None
+                } else {
+                    world_to_screen(frame.state.player.pos)
+                }
+            })
+            .collect()
+    }
+
+    /// Updates the range of drawn frames to be around the hovered frame.
+    fn update_drawn_frames(&mut self) {
+        let Some(max_drawn_frames) = self.max_drawn_frames else {
+            self.drawn_frames = 0..usize::MAX;
+            return;
+        };
+
+        // Keep the window where it was if nothing is hovered, so it doesn't jump around.
+        let center = self
+            .hovered_frame_idx
+            .unwrap_or(self.drawn_frames.start + max_drawn_frames / 2);
+        let start = center
+            .saturating_sub(max_drawn_frames / 2)
+            .max(self.first_shown_frame_idx);
+        self.drawn_frames = start..start.saturating_add(max_drawn_frames);
+    }
+
+    fn draw_current_branch(&self, mut draw: impl FnMut(DrawLine)) {
+        let branch = self.branch();
+        let selected_bulk_range = self.selected_bulk_range();
+
+        let smoothing_input_region = if self.in_camera_editor {
+            self.hovered_frame_idx
+                .and_then(|idx| branch.extra_cam[idx].smoothing_input_region)
+        } else {
+            None
+        };
+
+        // Draw regular frames.
+        let mut collided_this_bulk = false;
+
+        // For drawing small camera lines in the movement editor.
+        let mut last_camera_line_origin_vector = None;
+
+        // Note: there's no iterator cloning, which means all values are computed once, in one go.
+        let iter = iter::zip(
+            // Pairs of frames: (0, 1), (1, 2), (2, 3) and so on.
+            branch.frames.iter().tuple_windows(),
+            // For second frame in pair: its frame bulk index and whether it's last in its bulk.
+            bulk_idx_and_is_last(&branch.branch.script.lines),
+        )
+        .enumerate();
+        for (prev_idx, ((prev, frame), (bulk_idx, bulk, is_last_in_bulk))) in iter {
+            let idx = prev_idx + 1;
+
+            // Figure out if we had a collision this frame.
+            let collided_this_frame = wall_collision_normal(frame).is_some();
+
+            if collided_this_frame {
+                collided_this_bulk = true;
+            }
+
+            if !self.drawn_frames.contains(&idx) {
+                if is_last_in_bulk {
+                    collided_this_bulk = false;
+                }
+                continue;
+            }
+
+            // If frame is predicted (inaccurate).
+            let is_predicted = idx >= branch.first_predicted_frame;
+            // If frame is part of selected frame bulk.
+            let is_selected_bulk = selected_bulk_range
+                .map_or(false, |(first, last)| bulk_idx >= first && bulk_idx <= last);
+            // If frame is part of hovered frame bulk.
+            let is_hovered_bulk = self.hovered_bulk_idx == Some(bulk_idx);
+            // If frame is hovered.
+            let is_hovered = self.hovered_frame_idx == Some(idx);
+            // If frame is the stop frame.
+            let is_stop_frame = branch.branch.stop_frame as usize == idx;
+            // If frame is the end of norefresh for norefresh until stop frame.
+            let is_norefresh_until_stop_frame =
+                !is_stop_frame && self.norefresh_until_stop_frame_frame_idx == idx;
+
+            // If frame is in the smoothing input region.
+            let in_smoothing_input_region =
+                smoothing_input_region.map_or(false, |(start, end)| idx >= start && idx <= end);
+
+            // How many frames until the visible part, clamped in a way to allow for smooth dimming.
+            let frames_until_hidden = self.first_shown_frame_idx.saturating_sub(idx - 1).min(20);
+
+            // Inaccurate frames get dimmed.
+            let dim_inaccurate = if is_predicted { 0.6 } else { 1. };
+            // Unhovered bulks get dimmed.
+            let dim_unhovered = if is_hovered_bulk || self.in_camera_editor {
+                1.
+            } else {
+                0.7
+            };
+            // Hidden frames become invisible, smoothly transition into visible.
+            let dim_hidden = if frames_until_hidden == 0 {
+                1.
+            } else {
+                (20 - frames_until_hidden) as f32 / 40.
+            };
+            let dim = dim_inaccurate * dim_unhovered * dim_hidden;
+
+            const WHITE: Vec3 = Vec3::new(1., 1., 1.);
+            let color = if self.in_camera_editor {
+                WHITE * dim * 0.5
+            } else {
+                // Deselected bulks get desaturated.
+                let saturation = if is_selected_bulk { 1. } else { 0.3 };
+
+                let hue = if collided_this_frame {
+                    // Collided frames are red.
+                    Vec3::new(1., 0., 0.)
+                } else if collided_this_bulk {
+                    // Non-collided frames in collided bulk are pink.
+                    Vec3::new(1., 0.6, 0.6)
+                } else if self.highlight_steep_slopes && is_on_steep_slope(frame) {
+                    // Frames on steep slopes are blue.
+                    Vec3::new(0., 0.5, 1.)
+                } else if let Some(error) = frame
+                    .state
+                    .strafe_angle_error()
+                    .filter(|_| self.color_by_strafe_angle_error)
+                {
+                    // Strafing frames go from green to yellow with the angle error.
+                    let t = (error.to_degrees().abs() / 0.05).min(1.);
+                    Vec3::new(0., 1., 0.).lerp(Vec3::new(1., 1., 0.), t)
+                } else {
+                    // Other frames are green.
+                    Vec3::new(0., 1., 0.)
+                };
+
+                WHITE.lerp(hue, saturation) * dim
+            };
+
+            let prev_pos = prev.state.player.pos;
+            let pos = frame.state.player.pos;
+
+            // Line from previous to this frame position.
+            draw(DrawLine {
+                start: prev_pos,
+                end: pos,
+                color,
+            });
+
+            // Mark frames where the velocity hit sv_maxvelocity.
+            if !self.in_camera_editor && frame.state.velocity_clamped {
+                draw(DrawLine {
+                    start: pos,
+                    end: pos + Vec3::new(0., 0., 5.),
+                    color: Vec3::new(1., 0., 1.) * dim,
+                });
+            }
+
+            // Mark frames where the player was stuck inside solid geometry with a vertical cross.
+            if !self.in_camera_editor && frame.state.stuck {
+                for offset in [Vec3::new(0., 0., 6.), Vec3::new(6., 0., 0.)] {
+                    draw(DrawLine {
+                        start: pos - offset,
+                        end: pos + offset,
+                        color: Vec3::new(1., 0., 0.) * dim,
+                    });
+                }
+            }
+
+            // Mark jumpbugs with a horizontal cross.
+            if !self.in_camera_editor && frame.state.jumpbugged {
+                for offset in [Vec3::new(4., 4., 0.), Vec3::new(4., -4., 0.)] {
+                    draw(DrawLine {
+                        start: pos - offset,
+                        end: pos + offset,
+                        color: Vec3::new(1., 0.5, 0.) * dim,
+                    });
+                }
+            }
+
+            // Prefer the rendered view angles to show the effect of yaw and pitch overrides.
+            let (camera_pitch, camera_yaw) = match frame.state.rendered_viewangles {
+                Some(angles) => (angles.x.to_radians(), angles.y.to_radians()),
+                None => (
+                    frame.state.prev_frame_input.pitch,
+                    frame.state.prev_frame_input.yaw,
+                ),
+            };
+            let camera_vector = forward(camera_pitch, camera_yaw);
+
+            if self.in_camera_editor {
+                let extra_cam = &branch.extra_cam[idx];
+
+                // Draw camera angle line.
+                let hue = if in_smoothing_input_region {
+                    Vec3::new(1., 0.75, 0.5)
+                } else if extra_cam.in_smoothing_idempotent_region {
+                    Vec3::new(0., 1., 0.)
+                } else {
+                    Vec3::new(0.5, 0.5, 1.)
+                };
+
+                draw(DrawLine {
+                    start: pos,
+                    end: pos + camera_vector * 5.,
+                    color: hue * dim_inaccurate * dim_hidden,
+                });
+
+                for &line_idx in &extra_cam.change_line_that_ends_here {
+                    let perp = perpendicular(prev_pos, pos) * 5.;
+                    let diff = (pos - prev_pos).normalize_or_zero() * 5.;
+
+                    let dim_unhovered = if self.hovered_line_idx == Some(line_idx) {
+                        1.
+                    } else {
+                        0.7
+                    };
+
+                    // Draw the arrow.
+                    draw(DrawLine {
+                        start: pos - perp - diff,
+                        end: pos,
+                        color: WHITE * dim_hidden * dim_unhovered,
+                    });
+                    draw(DrawLine {
+                        start: pos,
+                        end: pos + perp - diff,
+                        color: WHITE * dim_hidden * dim_unhovered,
+                    });
+
+                    // Draw the target angle.
+                    let Line::Change(Change {
+                        target,
+                        final_value,
+                        ..
+                    }) = branch.branch.script.lines[line_idx]
+                    else {
+                        unreachable!()
+                    };
+
+                    let target_vector = match target {
+                        ChangeTarget::Yaw | ChangeTarget::VectorialStrafingYaw => {
+                            forward(0., final_value.to_radians())
+                        }
+                        ChangeTarget::Pitch => forward(final_value.to_radians(), camera_yaw),
+                        // TODO: how to draw this? Maybe skip it?
+                        ChangeTarget::VectorialStrafingYawOffset => camera_vector,
+                    };
+
+                    draw(DrawLine {
+                        start: pos,
+                        end: pos + target_vector * 20.,
+                        color: Vec3::new(1., 1., 0.) * dim_hidden * dim_unhovered,
+                    });
+                }
+
+                for &camera_line_idx in &extra_cam.camera_line_that_starts_here {
+                    let camera_line = &branch.branch.script.lines[camera_line_idx];
+                    let perp = perpendicular(prev_pos, pos) * 5.;
+
+                    let hue = match camera_line {
+                        Line::TargetYawOverride { .. } => Vec3::new(1., 0.75, 0.5),
+                        Line::RenderYawOverride { .. } => Vec3::new(1., 0., 0.),
+                        _ => WHITE,
+                    };
+
+                    let dim_unhovered = if self.hovered_line_idx == Some(camera_line_idx) {
+                        1.
+                    } else {
+                        0.7
+                    };
+
+                    if let Line::Change(Change { target, .. }) = camera_line {
+                        let diff = (pos - prev_pos).normalize_or_zero() * 5.;
+
+                        // Draw the arrow.
+                        draw(DrawLine {
+                            start: prev_pos - perp + diff,
+                            end: prev_pos,
+                            color: hue * dim_hidden * dim_unhovered,
+                        });
+                        draw(DrawLine {
+                            start: prev_pos,
+                            end: prev_pos + perp + diff,
+                            color: hue * dim_hidden * dim_unhovered,
+                        });
+
+                        // Draw the starting angle.
+                        let target_vector = match target {
+                            ChangeTarget::Yaw | ChangeTarget::VectorialStrafingYaw => {
+                                forward(0., camera_yaw)
+                            }
+                            ChangeTarget::Pitch => camera_vector,
+                            // TODO: how to draw this? Maybe skip it?
+                            ChangeTarget::VectorialStrafingYawOffset => camera_vector,
+                        };
+
+                        draw(DrawLine {
+                            start: prev_pos,
+                            end: prev_pos + target_vector * 20.,
+                            color: Vec3::new(1., 0., 0.) * dim_hidden * dim_unhovered,
+                        });
+                    } else {
+                        draw(DrawLine {
+                            start: prev_pos - perp,
+                            end: prev_pos + perp,
+                            color: hue * dim_hidden * dim_unhovered,
+                        });
+
+                        if let Line::VectorialStrafingConstraints(constraints) = camera_line {
+                            let hue = match constraints {
+                                VectorialStrafingConstraints::VelocityYaw { .. }
+                                | VectorialStrafingConstraints::AvgVelocityYaw { .. }
+                                | VectorialStrafingConstraints::VelocityYawLocking { .. } => {
+                                    Vec3::new(0., 1., 0.)
+                                }
+                                // TODO: for Yaw we can draw the Yaw itself, for YawRange we can
+                                // draw the range too.
+                                VectorialStrafingConstraints::Yaw { .. }
+                                | VectorialStrafingConstraints::YawRange { .. } => {
+                                    Vec3::new(0., 1., 1.)
+                                }
+                                VectorialStrafingConstraints::LookAt { .. } => {
+                                    Vec3::new(1., 0., 1.)
+                                }
+                            };
+
+                            draw(DrawLine {
+                                start: prev_pos,
+                                end: prev_pos + camera_vector * 20.,
+                                color: hue * dim_hidden * dim_unhovered,
+                            });
+                        }
+                    }
+                }
+            } else {
+                // If this frame is last in its frame bulk, draw a frame bulk handle.
+                if is_last_in_bulk {
+                    let perp = perpendicular(prev_pos, pos) * 5.;
+
+                    draw(DrawLine {
+                        start: pos - perp,
+                        end: pos + perp,
+                        color,
+                    });
+                }
+
+                // If it's selected and last and the frame bulk has a yaw, draw that.
+                if is_selected_bulk && is_last_in_bulk {
+                    if let Some(yaw) = bulk.yaw() {
+                        let yaw_dir = Vec2::from_angle(yaw.to_radians()).extend(0.);
+
+                        draw(DrawLine {
+                            start: pos - yaw_dir * 5.,
+                            end: pos + yaw_dir * 20.,
+                            color: Vec3::new(0.5, 0.5, 1.) * dim,
+                        });
+                    }
+                }
+
+                // If the frame is hovered and collided, draw the yaw suggested to avoid the
+                // collision.
+                if is_hovered && collided_this_frame {
+                    if let Some(yaw) = self.suggest_yaw_to_avoid_collision(idx) {
+                        let yaw_dir = Vec2::from_angle(yaw.to_radians()).extend(0.);
+                        let perp = Vec3::new(-yaw_dir.y, yaw_dir.x, 0.) * 3.;
+                        let end = pos + yaw_dir * 20.;
+                        let color = Vec3::new(0.5, 1., 1.) * 0.6;
+
+                        draw(DrawLine {
+                            start: pos,
+                            end,
+                            color,
+                        });
+
+                        // Draw the arrow.
+                        draw(DrawLine {
+                            start: end - yaw_dir * 3. - perp,
+                            end,
+                            color,
+                        });
+                        draw(DrawLine {
+                            start: end,
+                            end: end - yaw_dir * 3. + perp,
+                            color,
+                        });
+                    }
+                }
+
+                // Draw camera angle line if it's different enough from the last one.
+                if last_camera_line_origin_vector
+                    .map(|(origin, angle)| {
+                        pos.distance(origin) > 50. || camera_vector.dot(angle) < 0.98
+                    })
+                    .unwrap_or(true)
+                {
+                    last_camera_line_origin_vector = Some((pos, camera_vector));
+
+                    draw(DrawLine {
+                        start: pos,
+                        end: pos + camera_vector * 5.,
+                        color: Vec3::new(0.3, 0.3, 1.) * dim_inaccurate * dim_hidden,
+                    });
+
+                    // Draw velocity arrow at the same frames to avoid clutter.
+                    if self.show_velocity_vectors {
+                        let vel = frame.state.player.vel;
+                        let dir = vel.normalize_or_zero();
+
+                        if dir != Vec3::ZERO {
+                            // 20 units at 400 ups.
+                            let length = (vel.length() / 20.).clamp(2., 40.);
+                            let end = pos + dir * length;
+                            let perp = perpendicular(pos, end) * 2.;
+                            let color = Vec3::new(1., 1., 0.5) * dim_inaccurate * dim_hidden;
+
+                            draw(DrawLine {
+                                start: pos,
+                                end,
+                                color,
+                            });
+
+                            // Draw the arrow.
+                            draw(DrawLine {
+                                start: end - dir * 3. - perp,
+                                end,
+                                color,
+                            });
+                            draw(DrawLine {
+                                start: end,
+                                end: end - dir * 3. + perp,
+                                color,
+                            });
+                        }
+                    }
+                }
+            }
+
+            // If the frame is hovered and not last in bulk, draw a splitting guide.
+            if is_hovered && (!is_last_in_bulk || self.in_camera_editor) {
+                let perp = perpendicular(prev_pos, pos) * 2.;
+
+                let splitting_guide_color = if self.in_camera_editor {
+                    color
+                } else {
+                    color * 0.5
+                };
+
+                draw(DrawLine {
+                    start: pos - perp,
+                    end: pos + perp,
+                    color: splitting_guide_color,
+                });
+            }
+
+            // If the frame is hovered, draw the player bbox.
+            if is_hovered && self.show_player_bbox {
+                let player = &frame.state.player;
+
+                const HALF_SIZE: f32 = 16.;
+
+                // The bbox stays on the ground during the ducking animation, so keep the bottom in
+                // place and only change the height.
+                let corner1 = pos - Vec3::new(HALF_SIZE, HALF_SIZE, player.hull().half_height());
+                let corner2 = corner1
+                    + Vec3::new(
+                        2. * HALF_SIZE,
+                        2. * HALF_SIZE,
+                        2. * player_bbox_half_height(player),
+                    );
+
+                let mut draw_aa_cuboid = |corner1: Vec3, corner2: Vec3, color: Vec3| {
+                    let delta = corner2 - corner1;
+                    let dx = delta * Vec3::X;
+                    let dy = delta * Vec3::Y;
+                    let dz = delta * Vec3::Z;
+
+                    let lines = [
+                        // Bottom.
+                        (corner1, corner1 + dx),
                         (corner1, corner1 + dy),
                         (corner1 + dx, corner1 + dx + dy),
                         (corner1 + dy, corner1 + dx + dy),
@@ -4393,716 +6957,2547 @@ impl Editor {
                         (corner1 + dx + dy, corner1 + dx + dy + dz),
                     ];
 
-                    for (start, end) in lines {
-                        draw(DrawLine { start, end, color });
-                    }
-                };
+                    for (start, end) in lines {
+                        draw(DrawLine { start, end, color });
+                    }
+                };
+
+                draw_aa_cuboid(corner1, corner2, color);
+            }
+
+            // If this is the stop frame, draw an indicator.
+            if is_stop_frame {
+                let perp = perpendicular(prev_pos, pos) * 2.;
+
+                draw(DrawLine {
+                    start: pos - perp,
+                    end: pos + perp,
+                    color: Vec3::new(1., 1., 0.5),
+                });
+            }
+
+            // If bxt_tas_studio_norefresh_until_stop_frame is set, draw another indicator.
+            if is_norefresh_until_stop_frame {
+                let perp = perpendicular(prev_pos, pos) * 2.;
+                let diff = (pos - prev_pos).normalize_or_zero() * 2.;
+
+                // Draw the arrow.
+                draw(DrawLine {
+                    start: pos - perp + diff,
+                    end: pos,
+                    color: Vec3::new(1., 1., 0.5),
+                });
+                draw(DrawLine {
+                    start: pos,
+                    end: pos + perp + diff,
+                    color: Vec3::new(1., 1., 0.5),
+                });
+            }
+
+            if is_last_in_bulk {
+                // Reset flag for next iteration.
+                collided_this_bulk = false;
+            }
+        }
+    }
+
+    fn draw_auto_smoothing(&self, mut draw: impl FnMut(DrawLine)) {
+        if !self.auto_smoothing {
+            return;
+        }
+
+        for (prev_idx, (prev, frame)) in self
+            .branch()
+            .auto_smoothing
+            .frames
+            .iter()
+            .tuple_windows()
+            .enumerate()
+        {
+            let idx = prev_idx + 1;
+
+            if !self.drawn_frames.contains(&idx) {
+                continue;
+            }
+
+            // How many frames until the visible part, clamped in a way to allow for smooth dimming.
+            let frames_until_hidden = self.first_shown_frame_idx.saturating_sub(idx - 1).min(20);
+            // Hidden frames become invisible, smoothly transition into visible.
+            let dim = if frames_until_hidden == 0 {
+                1.
+            } else {
+                (20 - frames_until_hidden) as f32 / 40.
+            };
+
+            let prev_pos = prev.state.player.pos;
+            let pos = frame.state.player.pos;
+
+            // Line from previous to this frame position.
+            draw(DrawLine {
+                start: prev_pos,
+                end: pos,
+                color: Vec3::new(1., 0.75, 0.5) * dim,
+            });
+        }
+
+        let Some(scratch) = &self.branch().auto_smoothing.scratch else {
+            return;
+        };
+
+        // Draw the scratch path where it diverges from the smoothed path.
+        let smoothed_frames = &self.branch().auto_smoothing.frames;
+        for (prev_idx, (prev, frame)) in scratch.frames.iter().tuple_windows().enumerate() {
+            let idx = prev_idx + 1;
+
+            if !self.drawn_frames.contains(&idx) || smoothed_frames.get(idx) == Some(frame) {
+                continue;
+            }
+
+            draw(DrawLine {
+                start: prev.state.player.pos,
+                end: frame.state.player.pos,
+                color: Vec3::new(0.5, 0.75, 1.),
+            });
+        }
+    }
+
+    fn draw_other_branches(&self, mut draw: impl FnMut(DrawLine)) {
+        let total_time = self
+            .branch()
+            .frames
+            .iter()
+            .skip(1)
+            .map(|f| f.parameters.frame_time)
+            .sum::<f32>();
+
+        for (idx, branch) in self.branches.iter().enumerate() {
+            if idx == self.branch_idx {
+                continue;
+            }
+
+            if branch.branch.is_hidden {
+                // Skip hidden branches.
+                continue;
+            }
+
+            let color = branch.color();
+
+            let mut time = 0.;
+            for (prev_idx, (prev, frame)) in branch.frames.iter().tuple_windows().enumerate() {
+                let idx = prev_idx + 1;
+
+                // How many frames until the visible part, clamped in a way to allow for smooth
+                // dimming.
+                let frames_until_hidden =
+                    self.first_shown_frame_idx.saturating_sub(idx - 1).min(20);
+                // Hidden frames become invisible, smoothly transition into visible.
+                let dim = if frames_until_hidden == 0 {
+                    1.
+                } else {
+                    (20 - frames_until_hidden) as f32 / 40.
+                };
+
+                let prev_pos = prev.state.player.pos;
+                let pos = frame.state.player.pos;
+
+                // Line from previous to this frame position.
+                if self.drawn_frames.contains(&idx) {
+                    draw(DrawLine {
+                        start: prev_pos,
+                        end: pos,
+                        color: color * dim,
+                    });
+                }
+
+                time += frame.parameters.frame_time;
+                let next_frame = branch.frames.get(idx + 1).unwrap_or(frame);
+                let next_time = time + next_frame.parameters.frame_time;
+                if (time - total_time).abs() < (next_time - total_time).abs() {
+                    // Draw other branches up to the length of the current branch.
+                    break;
+                }
+            }
+        }
+    }
+
+    fn draw_inner(&self, mut draw: impl FnMut(DrawLine)) {
+        // At least on my machine, things that are drawn later visually appear over things that are
+        // drawn earlier. Therefore, the drawing order should be from the least to the most
+        // important.
+        self.draw_other_branches(&mut draw);
+
+        // The raw path is only hidden when there's a smoothed path to show instead.
+        if self.smoothed_view_mode != SmoothedViewMode::RawOnly {
+            self.draw_auto_smoothing(&mut draw);
+        }
+        if !self.auto_smoothing || self.smoothed_view_mode != SmoothedViewMode::SmoothedOnly {
+            self.draw_current_branch(&mut draw);
+        }
+    }
+
+    /// Draws the editor UI.
+    #[instrument("Editor::draw", skip_all)]
+    pub fn draw(&self, tri: &TriangleApi) {
+        tri.render_mode(RenderMode::TransColor);
+
+        tri.begin(Primitive::Lines);
+
+        self.draw_inner(|DrawLine { start, end, color }| {
+            tri.color(color.x, color.y, color.z, 1.);
+            tri.vertex(start);
+            tri.vertex(end);
+        });
+
+        tri.end();
+    }
+
+    /// Draws the editor UI using camera-facing quads `width` pixels wide instead of lines.
+    ///
+    /// `world_to_screen` must return screen coordinates in pixels.
+    #[instrument("Editor::draw_quads", skip_all)]
+    pub fn draw_quads(
+        &self,
+        tri: &TriangleApi,
+        view_origin: Vec3,
+        width: f32,
+        world_to_screen: impl Fn(Vec3) -> Option<Vec2>,
+    ) {
+        tri.render_mode(RenderMode::TransColor);
+        tri.cull(CullStyle::None);
+
+        tri.begin(Primitive::Quads);
+
+        self.draw_inner(|DrawLine { start, end, color }| {
+            let Some(quad) = line_quad(start, end, view_origin, width, &world_to_screen) else {
+                return;
+            };
+
+            tri.color(color.x, color.y, color.z, 1.);
+            for vertex in quad {
+                tri.vertex(vertex);
+            }
+        });
+
+        tri.end();
+
+        tri.cull(CullStyle::Front);
+    }
+}
+
+/// Returns the corners of a camera-facing quad covering the line from `start` to `end` that is
+/// `width` pixels wide on screen.
+///
+/// Returns `None` if the quad cannot be computed, for example when the line points at the camera
+/// or either end is behind the camera.
+fn line_quad(
+    start: Vec3,
+    end: Vec3,
+    view_origin: Vec3,
+    width: f32,
+    world_to_screen: impl Fn(Vec3) -> Option<Vec2>,
+) -> Option<[Vec3; 4]> {
+    let side = (end - start)
+        .cross((start + end) / 2. - view_origin)
+        .try_normalize()?;
+
+    // The world size of a pixel depends on the distance to the camera, so compute it separately
+    // for both ends.
+    let half_width_at = |pos: Vec3| {
+        let pixels_per_unit = world_to_screen(pos + side)?.distance(world_to_screen(pos)?);
+        (pixels_per_unit > 0.).then(|| width / 2. / pixels_per_unit)
+    };
+    let start_offset = side * half_width_at(start)?;
+    let end_offset = side * half_width_at(end)?;
+
+    Some([
+        start - start_offset,
+        start + start_offset,
+        end + end_offset,
+        end - end_offset,
+    ])
+}
+
+/// Default maximum total number of frames in a script.
+///
+/// Limits the memory used for the simulated frames of pathological scripts.
+pub const DEFAULT_MAX_FRAME_COUNT: usize = 10_000_000;
+
+/// Predicts frames of the branch until the deadline, always predicting at least one frame.
+fn predict<T: Trace>(
+    tracer: &T,
+    branch: &mut BranchData,
+    max_trace_distance: Option<f32>,
+    deadline: Instant,
+) {
+    let mut simulator = Simulator::new(tracer, &branch.frames, &branch.branch.script.lines)
+        .with_parameter_overrides(&branch.parameter_overrides);
+    if let Some(max_distance) = max_trace_distance {
+        simulator = simulator.with_trace_subdivision(max_distance);
+    }
+
+    for frame in simulator {
+        // Always simulate at least one frame.
+        branch.frames.push(frame);
+
+        // Break if the deadline has passed.
+        if Instant::now() >= deadline {
+            break;
+        }
+    }
+}
+
+/// Returns the half-height of the player bbox.
+///
+/// During the ducking animation the height is interpolated from standing to ducked.
+fn player_bbox_half_height(player: &Player) -> f32 {
+    let hull = player.hull();
+    if hull != Hull::Standing || !player.in_duck_animation {
+        return hull.half_height();
+    }
+
+    // The ducking animation starts at duck_time = 1000 and finishes at duck_time = 600.
+    let progress = ((1000 - player.duck_time) as f32 / 400.).clamp(0., 1.);
+    let standing = Hull::Standing.half_height();
+    let ducked = Hull::Ducked.half_height();
+    standing + (ducked - standing) * progress
+}
+
+/// Returns the normal of the first wall that the player collided with on this frame, if any.
+fn wall_collision_normal(frame: &Frame) -> Option<Vec3> {
+    frame
+        .state
+        .move_traces
+        .iter()
+        // If we bumped into something along the way...
+        .take_while(|trace| trace.fraction != 1.)
+        .map(|trace| trace.plane_normal)
+        // And it wasn't a ground or a ceiling, we have a collision.
+        .find(|normal| normal.z != -1. && normal.z != 1.)
+}
+
+fn perpendicular(prev: Vec3, next: Vec3) -> Vec3 {
+    let line = (next - prev).normalize_or_zero();
+
+    let rv = if line.x == 0. {
+        Vec3::X
+    } else if line.y == 0. {
+        Vec3::Y
+    } else {
+        Vec3::new(1., -line.x / line.y, 0.).normalize()
+    };
+
+    // Make sure it's oriented in a particular way: this makes right-drag to change yaw behave as
+    // expected (the yaw will change in the direction where you move the mouse).
+    if rv.x * line.y - rv.y * line.x > 0. {
+        -rv
+    } else {
+        rv
+    }
+}
+
+/// Returns whether the player is on walkable ground that is steep enough to noticeably affect
+/// movement.
+fn is_on_steep_slope(frame: &Frame) -> bool {
+    // Ground with normal Z below 0.7 is not walkable, and the player is not on the ground there.
+    const STEEP_SLOPE_NORMAL_Z: f32 = 0.9;
+
+    frame
+        .state
+        .ground_normal
+        .map_or(false, |normal| normal.z < STEEP_SLOPE_NORMAL_Z)
+}
+
+fn forward(pitch: f32, yaw: f32) -> Vec3 {
+    let (sin_pitch, cos_pitch) = pitch.sin_cos();
+    let (sin_yaw, cos_yaw) = yaw.sin_cos();
+    Vec3::new(cos_pitch * cos_yaw, cos_pitch * sin_yaw, -sin_pitch)
+}
+
+fn replace_multiple_params<'a>(
+    old_script: &HLTAS,
+    new_script: &'a HLTAS,
+) -> Option<(usize, usize, &'a [Line])> {
+    if new_script.properties != old_script.properties {
+        return None;
+    }
+
+    // Most manual edits will probably modify, add or delete a few lines close together, leaving
+    // everything before and after the same. We find this region by discarding all consecutive lines
+    // that remained the same at the beginning and at the end of the two scripts.
+
+    let new_len = new_script.lines.len();
+    let old_len = old_script.lines.len();
+
+    // Find the index of the first non-matching line. It is the same between the two scripts.
+    let matching_lines_from_start = zip(&new_script.lines, &old_script.lines)
+        .find_position(|(new, old)| new != old)
+        .map(|(idx, _)| idx)
+        // If we exhaust one of the iterators, return the current index, which is equal to the
+        // length of the shorter iterator.
+        .unwrap_or(min(new_len, old_len));
+    let first_line_idx = matching_lines_from_start;
+
+    // Operate on the non-matching part to avoid the next search from stepping on the same lines.
+    //
+    // For example, consider a test case like this:
+    //
+    // ```
+    // version 1
+    // frames
+    // ----------|------|------|0.001|-|-|1
+    // ----------|------|------|0.001|-|-|1
+    // ```
+    //
+    // ```
+    // version 1
+    // frames
+    // ----------|------|------|0.001|-|-|1
+    // ```
+    //
+    // When doing the reverse search below using the original (non-sliced) arrays, it will count the
+    // single matching line from both the start and the end, causing an overlap and a logic error.
+    // By slicing here we're enforcing that all lines that were counted during the forward search
+    // are not counted during the reverse search.
+    let new_script_non_matching = &new_script.lines[first_line_idx..];
+    let old_script_non_matching = &old_script.lines[first_line_idx..];
+    let new_len_non_matching = new_len - matching_lines_from_start;
+    let old_len_non_matching = old_len - matching_lines_from_start;
+
+    // Find the index of the first non-matching line from the end. It is the same between the two
+    // scripts.
+    let matching_lines_from_end = zip(
+        new_script_non_matching.iter().rev(),
+        old_script_non_matching.iter().rev(),
+    )
+    .find_position(|(new, old)| new != old)
+    .map(|(idx, _)| idx)
+    // If we exhaust one of the iterators, return the current index, which is equal to the
+    // length of the shorter iterator.
+    .unwrap_or(min(new_len_non_matching, old_len_non_matching));
+
+    let count = old_len - matching_lines_from_end - matching_lines_from_start;
+
+    let new_one_past_last_line_idx = new_len - matching_lines_from_end;
+    let to = &new_script.lines[first_line_idx..new_one_past_last_line_idx];
+
+    Some((first_line_idx, count, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use bxt_strafe::{DummyTracer, Parameters, State, TraceResult};
+    use expect_test::expect;
+    use hltas::types::FrameBulk;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::hooks::sdl::MouseButtons;
+
+    /// Pushes a default frame to the current branch for every item, letting `set_up` change it.
+    fn push_frames<I>(
+        editor: &mut Editor,
+        items: impl IntoIterator<Item = I>,
+        mut set_up: impl FnMut(&mut Frame, I),
+    ) {
+        for item in items {
+            let mut frame = Frame {
+                parameters: Parameters::default(),
+                state: State::default(),
+            };
+            set_up(&mut frame, item);
+            editor.branch_mut().frames.push(frame);
+        }
+    }
+
+    /// Pushes `count` default frames to the current branch.
+    fn push_default_frames(editor: &mut Editor, count: usize) {
+        push_frames(editor, 0..count, |_, _| ());
+    }
+
+    #[test]
+    fn undo_redo() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|6").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        // Undo with no changes should return an error saying there are no actions to undo.
+        assert!(matches!(editor.undo(), Err(ManualOpError::UserError(_))));
+
+        let before_op = editor.branch().branch.script.clone();
+        editor
+            .apply_operation(Operation::SetFrameCount {
+                bulk_idx: 0,
+                from: 6,
+                to: 10,
+            })
+            .unwrap();
+        let after_op = editor.branch().branch.script.clone();
+        assert_ne!(before_op, after_op, "operation should affect the HLTAS");
+
+        editor.undo().unwrap();
+        assert_eq!(
+            before_op,
+            editor.branch().branch.script,
+            "undo produced wrong result"
+        );
+
+        editor.redo().unwrap();
+        assert_eq!(
+            after_op,
+            editor.branch().branch.script,
+            "redo produced wrong result"
+        );
+
+        // Redo with no changes should return an error saying there are no actions to redo.
+        assert!(matches!(editor.redo(), Err(ManualOpError::UserError(_))));
+    }
+
+    #[test]
+    fn toggle_dbc_undo_redo() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|6").unwrap();
+        let dbc_script =
+            HLTAS::from_str("version 1\nframes\n-------c--|------|------|0.004|10|-|6").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+        editor.selected_bulk_idx = Some(0);
+
+        editor
+            .toggle_auto_action(ToggleAutoActionTarget::DuckBeforeCollision)
+            .unwrap();
+        assert_eq!(editor.branch().branch.script, dbc_script);
+        assert_eq!(editor.undo_log_len(), 1);
+
+        editor
+            .toggle_auto_action(ToggleAutoActionTarget::DuckBeforeCollision)
+            .unwrap();
+        assert_eq!(editor.branch().branch.script, script);
+        assert_eq!(editor.undo_log_len(), 2);
+
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, dbc_script);
+        assert_eq!(editor.undo_log_len(), 1);
+
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
+        assert_eq!(editor.undo_log_len(), 0);
+
+        editor.redo().unwrap();
+        assert_eq!(editor.branch().branch.script, dbc_script);
+        assert_eq!(editor.undo_log_len(), 1);
+
+        editor.redo().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
+        assert_eq!(editor.undo_log_len(), 2);
+
+        assert!(matches!(editor.redo(), Err(ManualOpError::UserError(_))));
+    }
+
+    #[test]
+    fn undo_redo_all() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|6").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        assert!(matches!(
+            editor.undo_all(),
+            Err(ManualOpError::UserError(_))
+        ));
+
+        let before_ops = editor.branch().branch.script.clone();
+        for (from, to) in [(6, 7), (7, 8), (8, 9)] {
+            editor
+                .apply_operation(Operation::SetFrameCount {
+                    bulk_idx: 0,
+                    from,
+                    to,
+                })
+                .unwrap();
+        }
+        let after_ops = editor.branch().branch.script.clone();
+
+        // The database matches the editor.
+        let check_db = |editor: &mut Editor, script: &HLTAS, undo_len: usize| {
+            let branch_id = editor.branch().branch.branch_id;
+            assert_eq!(editor.db.branch(branch_id).unwrap().script, *script);
+            let (undo_log, redo_log) = editor.db.undo_redo().unwrap();
+            assert_eq!((undo_log.len(), redo_log.len()), (undo_len, 3 - undo_len));
+        };
+
+        editor.undo_all().unwrap();
+        assert_eq!(before_ops, editor.branch().branch.script);
+        assert_eq!(editor.undo_log_len(), 0);
+        check_db(&mut editor, &before_ops, 0);
+
+        editor.redo_all().unwrap();
+        assert_eq!(after_ops, editor.branch().branch.script);
+        assert_eq!(editor.undo_log_len(), 3);
+        check_db(&mut editor, &after_ops, 3);
+
+        assert!(matches!(
+            editor.redo_all(),
+            Err(ManualOpError::UserError(_))
+        ));
+    }
+
+    #[test]
+    fn replace_multiple_optimization_bug_1() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                ----------|------|------|0.004|10|-|6",
+        )
+        .unwrap();
+
+        let new_script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6",
+        )
+        .unwrap();
+
+        let (first_line_idx, count, to) = replace_multiple_params(&script, &new_script).unwrap();
+        assert_eq!(first_line_idx, 1);
+        assert_eq!(count, 1);
+        assert_eq!(to, []);
+    }
+
+    #[test]
+    fn replace_multiple_optimization_bug_2() {
+        let script = HLTAS::from_str("version 1\nframes\nstrafing vectorial").unwrap();
+        let new_script = HLTAS::from_str("version 1\nframes\nstrafing vectorial").unwrap();
+
+        let (first_line_idx, count, to) = replace_multiple_params(&script, &new_script).unwrap();
+        assert_eq!(first_line_idx, 1);
+        assert_eq!(count, 0);
+        assert_eq!(to, []);
+    }
+
+    #[test]
+    fn selected_bulk_idx_is_not_invalid_after_rewrite() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                ----------|------|------|0.004|20|-|6",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        editor.selected_bulk_idx = Some(1);
+
+        let new_script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6",
+        )
+        .unwrap();
+        editor.rewrite(new_script).unwrap();
+
+        assert_eq!(editor.selected_bulk_idx, None);
+    }
+
+    #[test]
+    fn suggest_yaw_to_avoid_collision() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|2").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_frames(&mut editor, [0., 1.], |frame, x| {
+            frame.state.player.pos = Vec3::new(x, x, 0.);
+        });
+
+        assert_eq!(editor.suggest_yaw_to_avoid_collision(0), None);
+        assert_eq!(editor.suggest_yaw_to_avoid_collision(1), None);
+
+        // Moving diagonally into a wall facing -X.
+        editor.branch_mut().frames[1]
+            .state
+            .move_traces
+            .push(TraceResult {
+                all_solid: false,
+                start_solid: false,
+                fraction: 0.5,
+                end_pos: Vec3::ZERO,
+                plane_normal: Vec3::NEG_X,
+                entity: 0,
+            });
+
+        assert_eq!(editor.suggest_yaw_to_avoid_collision(1), Some(90.));
+    }
+
+    #[test]
+    fn select_collided() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|2\n\
+                ----------|------|------|0.004|10|-|2\n\
+                ----------|------|------|0.004|10|-|2",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_frames(&mut editor, 0..7, |frame, i| {
+            frame.state.player.pos = Vec3::new(i as f32 * 10., 0., 0.);
+        });
+
+        let world_to_screen = |pos: Vec3| Some(pos.truncate());
+        assert!(matches!(
+            editor.select_collided(world_to_screen, Vec2::ZERO),
+            Err(ManualOpError::UserError(_))
+        ));
+        assert_eq!(editor.selected_bulk_idx, None);
+
+        let collide = |editor: &mut Editor, frame_idx: usize| {
+            editor.branch_mut().frames[frame_idx]
+                .state
+                .move_traces
+                .push(TraceResult {
+                    all_solid: false,
+                    start_solid: false,
+                    fraction: 0.5,
+                    end_pos: Vec3::ZERO,
+                    plane_normal: Vec3::NEG_X,
+                    entity: 0,
+                });
+        };
+
+        // Frames 3 and 4 are simulated by the second frame bulk.
+        collide(&mut editor, 4);
+        editor
+            .select_collided(world_to_screen, Vec2::new(60., 0.))
+            .unwrap();
+        assert_eq!(editor.selected_bulk_idx, Some(1));
+
+        // The collision closest to the point is selected.
+        collide(&mut editor, 6);
+        editor
+            .select_collided(world_to_screen, Vec2::new(60., 0.))
+            .unwrap();
+        assert_eq!(editor.selected_bulk_idx, Some(2));
+        editor.select_collided(world_to_screen, Vec2::ZERO).unwrap();
+        assert_eq!(editor.selected_bulk_idx, Some(1));
+    }
+
+    #[test]
+    fn set_smooth_window_from_hovered_segment() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.01|10|-|5").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_frames(&mut editor, 0..6, |frame, _| {
+            frame.parameters.frame_time = 0.01;
+        });
+
+        assert!(matches!(
+            editor.set_smooth_window_from_hovered_segment(),
+            Err(ManualOpError::CannotDoInMovementEditor)
+        ));
+
+        editor.set_in_camera_editor(true);
+        assert!(matches!(
+            editor.set_smooth_window_from_hovered_segment(),
+            Err(ManualOpError::NoHoveredFrame)
+        ));
+
+        editor.hovered_frame_idx = Some(2);
+        editor.branch_mut().extra_cam[2].smoothing_input_region = Some((1, 4));
+        let window_s = editor.set_smooth_window_from_hovered_segment().unwrap();
+        assert!((window_s - 0.04).abs() < 1e-6, "{window_s}");
+        assert_eq!(editor.smooth_window_s, window_s);
+    }
+
+    #[test]
+    fn first_frame_leaving_box() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|3").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_frames(&mut editor, [0., 1., 2., 3.], |frame, x| {
+            frame.state.player.pos = Vec3::new(x, 0., 0.);
+        });
+
+        assert_eq!(
+            editor.first_frame_leaving_box(Vec3::splat(-1.), Vec3::new(1., 1., 1.)),
+            Some(2)
+        );
+        // Corners in the opposite order.
+        assert_eq!(
+            editor.first_frame_leaving_box(Vec3::new(2., 1., 1.), Vec3::splat(-1.)),
+            Some(3)
+        );
+        assert_eq!(
+            editor.first_frame_leaving_box(Vec3::splat(-10.), Vec3::splat(10.)),
+            None
+        );
+        assert_eq!(
+            editor.first_frame_leaving_box(Vec3::new(1., -1., -1.), Vec3::splat(10.)),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn recentered_view_origin() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|1").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_frames(&mut editor, [0., 100.], |frame, x| {
+            frame.state.player.pos = Vec3::new(x, 0., 0.);
+        });
+
+        // Looking along +Y.
+        let origin = editor
+            .recentered_view_origin(1, [0., 90., 0.], 200.)
+            .unwrap();
+        assert!(
+            origin.abs_diff_eq(Vec3::new(100., -200., 0.), 1e-3),
+            "{origin}"
+        );
+
+        // Looking straight down.
+        let origin = editor
+            .recentered_view_origin(0, [90., 0., 0.], 200.)
+            .unwrap();
+        assert!(
+            origin.abs_diff_eq(Vec3::new(0., 0., 200.), 1e-3),
+            "{origin}"
+        );
+
+        assert_eq!(editor.recentered_view_origin(2, [0., 0., 0.], 200.), None);
+    }
+
+    #[test]
+    fn path_obj() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|2").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_frames(&mut editor, [0., 1., 2.], |frame, x| {
+            frame.state.player.pos = Vec3::new(x, 0., 10.);
+        });
+
+        expect![[r#"
+            o branch_0
+            v 0 0 10
+            v 1 0 10
+            v 2 0 10
+            l 1 2 3
+        "#]]
+        .assert_eq(&editor.path_obj(true));
+    }
+
+    #[test]
+    fn select_after_delete() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                ----------|------|------|0.004|20|-|6\n\
+                ----------|------|------|0.004|30|-|6",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        editor.selected_bulk_idx = Some(0);
+        editor.delete_selected().unwrap();
+        assert_eq!(editor.selected_bulk_idx, Some(0));
+
+        editor.set_select_after_delete(false);
+        editor.delete_selected().unwrap();
+        assert_eq!(editor.selected_bulk_idx, None);
+    }
+
+    #[test]
+    fn select_range() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                ----------|------|------|0.004|20|-|6\n\
+                ----------|------|------|0.004|30|-|6",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        editor.select_bulk(1).unwrap();
+        assert_eq!(editor.selected_bulk_range(), Some((1, 1)));
+
+        editor.prev_keyboard_state.extend_selection = true;
+        editor.select_next().unwrap();
+        assert_eq!(editor.selected_bulk_range(), Some((1, 2)));
+        editor.select_prev().unwrap();
+        editor.select_prev().unwrap();
+        assert_eq!(editor.selected_bulk_range(), Some((0, 1)));
+
+        editor.prev_keyboard_state.extend_selection = false;
+        editor.select_next().unwrap();
+        assert_eq!(editor.selected_bulk_range(), Some((1, 1)));
+    }
+
+    #[test]
+    fn delete_selected_range_undo_redo() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                ----------|------|------|0.004|20|-|6\n\
+                ----------|------|------|0.004|30|-|6",
+        )
+        .unwrap();
+        let deleted = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|30|-|6",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        editor.select_bulk(0).unwrap();
+        editor.prev_keyboard_state.extend_selection = true;
+        editor.select_next().unwrap();
+        assert_eq!(editor.selected_bulk_range(), Some((0, 1)));
+
+        editor.delete_selected_range().unwrap();
+        assert_eq!(editor.branch().branch.script, deleted);
+        assert_eq!(editor.undo_log_len(), 1);
+        assert_eq!(editor.selected_bulk_range(), Some((0, 0)));
+
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
+        assert_eq!(editor.undo_log_len(), 0);
+
+        editor.redo().unwrap();
+        assert_eq!(editor.branch().branch.script, deleted);
+    }
+
+    #[test]
+    fn duplicate_selected_bulk() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                s03lj-----|------|------|0.001|15|10|2",
+        )
+        .unwrap();
+        let duplicated = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                s03lj-----|------|------|0.001|15|10|2\n\
+                s03lj-----|------|------|0.001|15|10|2",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        assert!(matches!(
+            editor.duplicate_selected_bulk(),
+            Err(ManualOpError::NoSelectedBulk)
+        ));
+
+        editor.select_bulk(1).unwrap();
+        editor.duplicate_selected_bulk().unwrap();
+        assert_eq!(editor.branch().branch.script, duplicated);
+        assert_eq!(editor.selected_bulk_idx(), Some(1));
+        assert_eq!(editor.undo_log_len(), 1);
+
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
+        assert_eq!(editor.undo_log_len(), 0);
+    }
+
+    #[test]
+    fn branch_clone_color() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|6").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        editor.branch_clone().unwrap();
+        editor.branch_clone().unwrap();
+
+        let first = &editor.branches[1].branch;
+        let second = &editor.branches[2].branch;
+        assert!(first.color.is_some());
+        assert!(second.color.is_some());
+        assert_ne!(first.color, second.color);
+        assert_ne!(editor.branches[1].color(), editor.branches[2].color());
+
+        // The colors are persisted.
+        assert_eq!(
+            editor.db.branch(first.branch_id).unwrap().color,
+            first.color
+        );
+        assert_eq!(
+            editor.db.branch(second.branch_id).unwrap().color,
+            second.color
+        );
+    }
+
+    #[test]
+    fn rename_branch() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|6").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        editor.rename_branch(0, "Skip".to_owned()).unwrap();
+        assert_eq!(editor.branch().name(), "Skip");
+
+        let branch_id = editor.branch().branch.branch_id;
+        assert_eq!(editor.db.branch(branch_id).unwrap().name, "Skip");
+
+        assert!(matches!(
+            editor.rename_branch(1, "Other".to_owned()),
+            Err(ManualOpError::BranchDoesNotExist)
+        ));
+    }
+
+    #[test]
+    fn branch_focus_by_name() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|6").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        editor.rename_branch(0, "Skip".to_owned()).unwrap();
+        editor.branch_clone().unwrap();
+        assert_eq!(editor.branch_idx(), 1);
+        editor.rename_branch(1, "Safe".to_owned()).unwrap();
+
+        editor.branch_focus_by_name("Skip").unwrap();
+        assert_eq!(editor.branch_idx(), 0);
+
+        assert!(matches!(
+            editor.branch_focus_by_name("Other"),
+            Err(ManualOpError::UserError(_))
+        ));
+
+        // Hidden branches are not considered.
+        editor.branch_hide(1).unwrap();
+        assert!(matches!(
+            editor.branch_focus_by_name("Safe"),
+            Err(ManualOpError::UserError(_))
+        ));
+        assert_eq!(editor.branch_idx(), 0);
+    }
+
+    #[test]
+    fn mirror_selected() {
+        let mut script = HLTAS::from_str(&format!(
+            "version 1\nframes\n{}",
+            ["s03-------|------|------|0.004|10|-|6"; 10].join("\n")
+        ))
+        .unwrap();
+
+        let strafe = |dir| {
+            Some(AutoMovement::Strafe(StrafeSettings {
+                type_: StrafeType::MaxAccel,
+                dir,
+            }))
+        };
+        let count = NonZeroU32::new(2).unwrap();
+        let cases = [
+            (strafe(StrafeDir::Left), strafe(StrafeDir::Right)),
+            (strafe(StrafeDir::Right), strafe(StrafeDir::Left)),
+            (strafe(StrafeDir::Best), strafe(StrafeDir::Best)),
+            (strafe(StrafeDir::Yaw(30.)), strafe(StrafeDir::Yaw(-30.))),
+            (
+                strafe(StrafeDir::Point { x: 10., y: 20. }),
+                strafe(StrafeDir::Point { x: 10., y: 20. }),
+            ),
+            (
+                strafe(StrafeDir::Line { yaw: 30. }),
+                strafe(StrafeDir::Line { yaw: -30. }),
+            ),
+            (
+                strafe(StrafeDir::LeftRight(count)),
+                strafe(StrafeDir::RightLeft(count)),
+            ),
+            (
+                strafe(StrafeDir::RightLeft(count)),
+                strafe(StrafeDir::LeftRight(count)),
+            ),
+            (
+                Some(AutoMovement::SetYaw(45.)),
+                Some(AutoMovement::SetYaw(-45.)),
+            ),
+            (None, None),
+        ];
+        for (bulk, (from, _)) in zip(script.frame_bulks_mut(), &cases) {
+            bulk.auto_actions.movement = *from;
+        }
+        script.frame_bulks_mut().next().unwrap().movement_keys.left = true;
+
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+        editor.select_bulk(0).unwrap();
+        editor.selection_anchor_bulk_idx = Some(cases.len() - 1);
+
+        editor.mirror_selected().unwrap();
+        let mirrored = editor.branch().branch.script.clone();
+        for (bulk, (_, to)) in zip(mirrored.frame_bulks(), &cases) {
+            assert_eq!(bulk.auto_actions.movement, *to);
+        }
+        let first_bulk = mirrored.frame_bulks().next().unwrap();
+        assert!(!first_bulk.movement_keys.left);
+        assert!(first_bulk.movement_keys.right);
+
+        // Mirroring twice is the identity.
+        assert_eq!(editor.selected_bulk_range(), Some((0, cases.len() - 1)));
+        editor.mirror_selected().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
+
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, mirrored);
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
+    }
+
+    #[test]
+    fn reverse_selected() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                s03-------|------|------|0.004|10|-|5\n\
+                strafing vectorial\n\
+                s06-------|------|------|0.004|10|-|4\n\
+                s07-------|------|------|0.004|10|-|3",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        editor.select_bulk(1).unwrap();
+        editor.selection_anchor_bulk_idx = Some(3);
+        editor.reverse_selected().unwrap();
+
+        let lines = &editor.branch().branch.script.lines;
+        let frame_counts: Vec<_> = editor
+            .script()
+            .frame_bulks()
+            .map(|bulk| bulk.frame_count.get())
+            .collect();
+        assert_eq!(frame_counts, [6, 3, 4, 5]);
+        // The strafing line stays where it was.
+        assert!(matches!(lines[2], Line::VectorialStrafing(true)));
+        assert_eq!(editor.selected_bulk_range(), Some((1, 3)));
+
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
+    }
+
+    #[test]
+    fn prediction_progress() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                strafing vectorial\n\
+                ----------|------|------|0.004|10|-|4",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+        assert_eq!(editor.prediction_progress(), (0, 10));
+
+        push_default_frames(&mut editor, 4);
+        // The first frame is the initial frame.
+        assert_eq!(editor.prediction_progress(), (3, 10));
+    }
+
+    #[test]
+    fn frame_at_time() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|4").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+        assert_eq!(editor.frame_at_time(0.), None);
+
+        // Frames end at 0, 0.01, 0.03, 0.035 and 0.045 seconds.
+        push_frames(
+            &mut editor,
+            [0.001, 0.01, 0.02, 0.005, 0.01],
+            |frame, frame_time| frame.parameters.frame_time = frame_time,
+        );
+
+        assert_eq!(editor.frame_at_time(-1.), Some(0));
+        assert_eq!(editor.frame_at_time(0.), Some(0));
+        assert_eq!(editor.frame_at_time(0.004), Some(0));
+        assert_eq!(editor.frame_at_time(0.006), Some(1));
+        assert_eq!(editor.frame_at_time(0.026), Some(2));
+        assert_eq!(editor.frame_at_time(0.034), Some(3));
+        assert_eq!(editor.frame_at_time(0.041), Some(4));
+        assert_eq!(editor.frame_at_time(1.), Some(4));
+    }
+
+    #[test]
+    fn nudge_stop_frame() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                ----------|------|------|0.004|10|-|4",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        editor.nudge_stop_frame(3).unwrap();
+        assert_eq!(editor.stop_frame(), 3);
+        editor.nudge_stop_frame(-1).unwrap();
+        assert_eq!(editor.stop_frame(), 2);
+
+        editor.nudge_stop_frame(-10).unwrap();
+        assert_eq!(editor.stop_frame(), 0);
+
+        editor.nudge_stop_frame(100).unwrap();
+        assert_eq!(editor.stop_frame(), 10);
+        editor.nudge_stop_frame(i64::MAX).unwrap();
+        assert_eq!(editor.stop_frame(), 10);
+    }
+
+    #[test]
+    fn branch_set_color() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|6").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        let branch_id = editor.branch().branch.branch_id;
+        editor.branch_set_color(0, Some([255, 128, 0])).unwrap();
+        assert_eq!(
+            editor.db.branch(branch_id).unwrap().color,
+            Some([255, 128, 0])
+        );
+
+        editor.branch_set_color(0, None).unwrap();
+        assert_eq!(editor.db.branch(branch_id).unwrap().color, None);
+
+        assert!(matches!(
+            editor.branch_set_color(1, None),
+            Err(ManualOpError::BranchDoesNotExist)
+        ));
+    }
+
+    #[test]
+    fn open_db_restores_ui_state() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                ----------|------|------|0.004|10|-|6",
+        )
+        .unwrap();
+
+        let db = Db::create_in_memory(&script).unwrap();
+        db.update_ui_state(&UiState {
+            selected_bulk_idx: Some(1),
+            first_shown_frame_idx: 4,
+            in_camera_editor: true,
+        })
+        .unwrap();
+        let editor = Editor::open_db(db).unwrap();
+        assert_eq!(editor.selected_bulk_idx(), Some(1));
+        assert_eq!(editor.first_shown_frame_idx, 4);
+        assert!(editor.in_camera_editor());
+        assert!(!editor.is_any_adjustment_active());
+
+        // Out-of-range values are dropped.
+        let db = Db::create_in_memory(&script).unwrap();
+        db.update_ui_state(&UiState {
+            selected_bulk_idx: Some(2),
+            first_shown_frame_idx: 20,
+            in_camera_editor: false,
+        })
+        .unwrap();
+        let editor = Editor::open_db(db).unwrap();
+        assert_eq!(editor.selected_bulk_idx(), None);
+        assert_eq!(editor.first_shown_frame_idx, 0);
+    }
+
+    #[test]
+    fn branch_inputs() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|2").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+        assert!(editor.branch_inputs(0).is_empty());
+
+        push_frames(&mut editor, [0., 1., 2.], |frame, yaw| {
+            frame.state.prev_frame_input.yaw = yaw;
+        });
+
+        let yaws: Vec<f32> = editor
+            .branch_inputs(0)
+            .into_iter()
+            .map(|input| input.yaw)
+            .collect();
+        assert_eq!(yaws, [1., 2.]);
+    }
+
+    #[test]
+    fn norefresh_until_last_frames_for_hovered() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|4").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_default_frames(&mut editor, 5);
+
+        assert!(matches!(
+            editor.norefresh_until_last_frames_for_hovered(),
+            Err(ManualOpError::NoHoveredFrame)
+        ));
+
+        editor.hovered_frame_idx = Some(1);
+        assert_eq!(editor.norefresh_until_last_frames_for_hovered().unwrap(), 3);
+
+        editor.set_stop_frame(2).unwrap();
+        assert_eq!(editor.norefresh_until_last_frames_for_hovered().unwrap(), 1);
+
+        editor.hovered_frame_idx = Some(3);
+        assert!(matches!(
+            editor.norefresh_until_last_frames_for_hovered(),
+            Err(ManualOpError::UserError(_))
+        ));
+    }
+
+    #[test]
+    fn add_yaw_offset_change() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|4").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_default_frames(&mut editor, 5);
+
+        editor.hovered_frame_idx = Some(2);
+        assert!(matches!(
+            editor.add_yaw_offset_change(10., 0.5),
+            Err(ManualOpError::CannotDoInMovementEditor)
+        ));
+
+        editor.set_in_camera_editor(true);
+        editor.add_yaw_offset_change(10., 0.5).unwrap();
+
+        let lines = &editor.script().lines;
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].frame_bulk().unwrap().frame_count.get(), 2);
+        assert_eq!(
+            lines[1],
+            Line::Change(Change {
+                target: ChangeTarget::VectorialStrafingYawOffset,
+                final_value: 10.,
+                over: 0.5,
+            })
+        );
+        assert_eq!(lines[2].frame_bulk().unwrap().frame_count.get(), 2);
+
+        // On a frame bulk edge, nothing is split.
+        editor.add_yaw_offset_change(-10., 0.).unwrap();
+        let lines = &editor.script().lines;
+        assert_eq!(lines.len(), 4);
+        assert!(matches!(lines[1], Line::Change(_)));
+        assert!(matches!(lines[2], Line::Change(_)));
+    }
+
+    #[test]
+    fn insert_command() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|4\n\
+                ----------|------|------|0.004|10|-|2|stop",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        // In the middle of a frame bulk, it gets split.
+        editor.hovered_frame_idx = Some(3);
+        editor.insert_command("echo a".to_owned()).unwrap();
+
+        let bulks: Vec<_> = editor.script().frame_bulks().collect();
+        assert_eq!(bulks.len(), 3);
+        assert_eq!(bulks[0].frame_count.get(), 3);
+        assert_eq!(bulks[0].console_command, None);
+        assert_eq!(bulks[1].frame_count.get(), 1);
+        assert_eq!(bulks[1].console_command.as_deref(), Some("echo a"));
+
+        // On a frame bulk edge, the command is appended to the existing ones.
+        editor.hovered_frame_idx = Some(4);
+        editor.insert_command("echo b".to_owned()).unwrap();
+
+        let bulks: Vec<_> = editor.script().frame_bulks().collect();
+        assert_eq!(bulks.len(), 3);
+        assert_eq!(bulks[2].console_command.as_deref(), Some("stop;echo b"));
+
+        // Undo restores the original frame bulk in one go.
+        editor.undo().unwrap();
+        editor.undo().unwrap();
+        assert_eq!(*editor.script(), script);
+    }
+
+    #[test]
+    fn hide_frames_before() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|4\n\
+                ----------|------|------|0.004|20|-|4",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        editor.selected_bulk_idx = Some(0);
+        editor.hide_frames_before(5).unwrap();
+        assert_eq!(editor.first_shown_frame_idx, 5);
+        assert_eq!(editor.selected_bulk_idx, None);
+
+        // Clamped to the last frame.
+        editor.hide_frames_before(100).unwrap();
+        assert_eq!(editor.first_shown_frame_idx, 7);
+
+        editor.hide_frames_before(0).unwrap();
+        assert_eq!(editor.first_shown_frame_idx, 0);
+
+        // A script without frame bulks has nothing to hide.
+        let script = HLTAS::from_str("version 1\nframes\n").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+        editor.hide_frames_before(5).unwrap();
+        assert_eq!(editor.first_shown_frame_idx, 0);
+    }
+
+    #[test]
+    fn extract_branch() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|4\n\
+                buttons 1 0 2 3\n\
+                ----------|------|------|0.004|20|-|4\n\
+                target_yaw velocity_lock\n\
+                ----------|------|------|0.004|30|-|4",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        assert!(matches!(
+            editor.extract_branch(3, 3),
+            Err(ManualOpError::UserError(_))
+        ));
+        assert!(matches!(
+            editor.extract_branch(3, 13),
+            Err(ManualOpError::UserError(_))
+        ));
+
+        editor.extract_branch(6, 10).unwrap();
+        assert_eq!(editor.branch_idx(), 1);
+
+        let lines = &editor.script().lines;
+        assert_eq!(lines.len(), 5);
+        assert!(matches!(lines[0], Line::Comment(_)));
+        assert!(matches!(lines[1], Line::Buttons(_)));
+        assert_eq!(lines[2].frame_bulk().unwrap().frame_count.get(), 2);
+        assert!(matches!(lines[3], Line::VectorialStrafingConstraints(_)));
+        let bulk = lines[4].frame_bulk().unwrap();
+        assert_eq!(bulk.frame_count.get(), 2);
+        assert_eq!(bulk.yaw(), Some(&30.));
+
+        // The original branch is unchanged.
+        assert_eq!(editor.branches[0].branch.script, script);
+    }
+
+    #[test]
+    fn view_angles_deg() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|1").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_frames(&mut editor, [std::f32::consts::FRAC_PI_2], |frame, yaw| {
+            frame.state.prev_frame_input.yaw = yaw;
+            frame.state.prev_frame_input.pitch = -std::f32::consts::FRAC_PI_4;
+        });
+
+        let (yaw, pitch) = editor.view_angles_deg(0).unwrap();
+        assert!((yaw - 90.).abs() < 1e-4, "{yaw}");
+        assert!((pitch + 45.).abs() < 1e-4, "{pitch}");
+        assert_eq!(editor.view_angles_deg(1), None);
+    }
+
+    #[test]
+    fn tick_projects_each_frame_once() {
+        if std::env::var_os("RUN_SLOW_TESTS").is_none() {
+            eprintln!("ignoring slow test");
+            return;
+        }
+
+        let script = HLTAS::from_str(&format!(
+            "version 1\nframes\n{}",
+            ["----------|------|------|0.004|10|-|10"; 1000].join("\n")
+        ))
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_default_frames(&mut editor, 10_001);
+        editor.branch_mut().first_predicted_frame = 10_001;
+
+        let calls = std::cell::Cell::new(0);
+        let world_to_screen = |pos: Vec3| {
+            calls.set(calls.get() + 1);
+            Some(pos.truncate())
+        };
+        let callbacks = Callbacks {
+            enable_mouse_look: &|| (),
+            disable_mouse_look: &|| (),
+            get_viewangles: &|| [0.; 3],
+            change_view_origin: &|_| (),
+        };
+
+        editor
+            .tick(
+                &DummyTracer,
+                world_to_screen,
+                MouseState::default(),
+                KeyboardState::default(),
+                Instant::now(),
+                callbacks,
+            )
+            .unwrap();
+
+        // The hovered frame bulk and the hovered frame share the projected positions.
+        assert_eq!(calls.get(), 10_001);
+    }
+
+    #[test]
+    fn camera_frame_range_limits_hovering() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|2\n\
+                target_yaw velocity_lock\n\
+                ----------|------|------|0.004|10|-|2\n\
+                target_yaw velocity_lock\n\
+                ----------|------|------|0.004|10|-|2",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_frames(&mut editor, 0..7, |frame, i| {
+            frame.state.player.pos = Vec3::new(i as f32 * 10., 0., 0.);
+        });
+        editor.branch_mut().first_predicted_frame = 7;
+        editor.set_in_camera_editor(true);
+
+        assert!(matches!(
+            editor.set_camera_frame_range(Some((3, 2))),
+            Err(ManualOpError::UserError(_))
+        ));
+
+        // Hover the frame right before the second camera line.
+        let hover = |editor: &mut Editor| {
+            let callbacks = Callbacks {
+                enable_mouse_look: &|| (),
+                disable_mouse_look: &|| (),
+                get_viewangles: &|| [0.; 3],
+                change_view_origin: &|_| (),
+            };
+            editor
+                .tick(
+                    &DummyTracer,
+                    |pos: Vec3| Some(pos.truncate()),
+                    MouseState {
+                        pos: IVec2::new(40, 0),
+                        ..MouseState::default()
+                    },
+                    KeyboardState::default(),
+                    Instant::now(),
+                    callbacks,
+                )
+                .unwrap();
+            editor.hovered_line_idx
+        };
+        assert_eq!(hover(&mut editor), Some(3));
+
+        // Only the first camera line is in the range.
+        editor.set_camera_frame_range(Some((0, 3))).unwrap();
+        assert_eq!(hover(&mut editor), Some(1));
+
+        editor.set_camera_frame_range(None).unwrap();
+        assert_eq!(hover(&mut editor), Some(3));
+    }
+
+    #[test]
+    fn camera_view_adjustment_look_at_cycle() {
+        let mut script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|4").unwrap();
+        script.lines.insert(
+            0,
+            Line::VectorialStrafingConstraints(VectorialStrafingConstraints::Yaw {
+                yaw: 10.,
+                tolerance: 0.,
+            }),
+        );
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_frames(&mut editor, 0..5, |frame, i| {
+            frame.state.player.pos = Vec3::new(i as f32, 2., 3.);
+        });
+        editor.hovered_frame_idx = Some(2);
+
+        let callbacks = Callbacks {
+            enable_mouse_look: &|| (),
+            disable_mouse_look: &|| (),
+            get_viewangles: &|| [0., 30., 0.],
+            change_view_origin: &|_| (),
+        };
+        let adjust = |editor: &mut Editor, mode| {
+            editor.camera_view_adjustment = Some(CameraViewAdjustment {
+                mode,
+                camera_line_idx: 0,
+            });
+            editor
+                .tick_camera_view_adjustment(
+                    MouseState::default(),
+                    MouseState::default(),
+                    callbacks,
+                )
+                .unwrap();
+            assert!(editor.camera_view_adjustment.is_none());
+
+            editor.script().lines[0].clone()
+        };
+
+        assert!(matches!(
+            adjust(&mut editor, CameraViewAdjustmentMode::Alt),
+            Line::VectorialStrafingConstraints(
+                VectorialStrafingConstraints::VelocityYawLocking { .. }
+            )
+        ));
+        assert!(matches!(
+            adjust(&mut editor, CameraViewAdjustmentMode::Alt),
+            Line::VectorialStrafingConstraints(VectorialStrafingConstraints::LookAt {
+                entity: None,
+                x,
+                y,
+                z,
+            }) if Vec3::new(x, y, z) == Vec3::new(2., 2., 3.)
+        ));
+        assert!(matches!(
+            adjust(&mut editor, CameraViewAdjustmentMode::Alt),
+            Line::VectorialStrafingConstraints(VectorialStrafingConstraints::Yaw { yaw, .. })
+                if yaw == 30.
+        ));
+
+        // Undo goes back through the cycle.
+        editor.undo().unwrap();
+        assert!(matches!(
+            editor.script().lines[0],
+            Line::VectorialStrafingConstraints(VectorialStrafingConstraints::LookAt { .. })
+        ));
+        editor.undo().unwrap();
+        editor.undo().unwrap();
+        assert_eq!(*editor.script(), script);
+    }
+
+    #[test]
+    fn adjacent_frame_time_adjustment() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|10\n\
+                ----------|------|------|0.004|10|-|10",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+        editor.select_bulk(0).unwrap();
+
+        let start_adjustment = |editor: &mut Editor| {
+            editor.adjacent_frame_time_adjustment = Some(AdjacentFrameTimeAdjustment {
+                mouse_adjustment: MouseAdjustment::new(0.004, Vec2::ZERO, Vec2::X),
+                original_frame_time: "0.004".to_owned(),
+                original_next_frame_count: 10,
+                next_frame_time: 0.004,
+            });
+        };
+        let middle_down_at = |x| MouseState {
+            pos: IVec2::new(x, 0),
+            buttons: MouseButtons::Middle,
+        };
+
+        start_adjustment(&mut editor);
+        editor
+            .tick_adjacent_frame_time_adjustment(middle_down_at(200), KeyboardState::default())
+            .unwrap();
+
+        // The total duration of 0.08 s is kept.
+        let bulks: Vec<_> = editor.script().frame_bulks().collect();
+        assert_eq!(bulks[0].frame_time, "0.006");
+        assert_eq!(bulks[0].frame_count.get(), 10);
+        assert_eq!(bulks[1].frame_time, "0.004");
+        assert_eq!(bulks[1].frame_count.get(), 5);
+        let adjusted = editor.script().clone();
+
+        editor
+            .tick_adjacent_frame_time_adjustment(MouseState::default(), KeyboardState::default())
+            .unwrap();
+        assert!(!editor.is_any_adjustment_active());
+        assert_eq!(editor.undo_log_len(), 1);
+
+        editor.undo().unwrap();
+        assert_eq!(*editor.script(), script);
+        editor.redo().unwrap();
+        assert_eq!(*editor.script(), adjusted);
+        editor.undo().unwrap();
+
+        // Cancelling restores the original values.
+        editor.select_bulk(0).unwrap();
+        start_adjustment(&mut editor);
+        editor
+            .tick_adjacent_frame_time_adjustment(middle_down_at(-50), KeyboardState::default())
+            .unwrap();
+        let bulks: Vec<_> = editor.script().frame_bulks().collect();
+        assert_eq!(bulks[0].frame_time, "0.0035");
+        assert_eq!(bulks[1].frame_count.get(), 11);
+
+        editor.cancel_ongoing_adjustments();
+        assert!(!editor.is_any_adjustment_active());
+        assert_eq!(*editor.script(), script);
+        assert_eq!(editor.undo_log_len(), 0);
+    }
+
+    #[test]
+    fn update_drawn_frames() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|1000").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-                draw_aa_cuboid(pos - offset, pos + offset, color);
-            }
+        editor.update_drawn_frames();
+        assert_eq!(editor.drawn_frames, 0..usize::MAX);
 
-            // If this is the stop frame, draw an indicator.
-            if is_stop_frame {
-                let perp = perpendicular(prev_pos, pos) * 2.;
+        editor.set_max_drawn_frames(Some(100));
+        editor.update_drawn_frames();
+        assert_eq!(editor.drawn_frames, 0..100);
 
-                draw(DrawLine {
-                    start: pos - perp,
-                    end: pos + perp,
-                    color: Vec3::new(1., 1., 0.5),
-                });
-            }
+        // Follows the hovered frame.
+        editor.hovered_frame_idx = Some(90);
+        editor.update_drawn_frames();
+        assert_eq!(editor.drawn_frames, 40..140);
 
-            // If bxt_tas_studio_norefresh_until_stop_frame is set, draw another indicator.
-            if is_norefresh_until_stop_frame {
-                let perp = perpendicular(prev_pos, pos) * 2.;
-                let diff = (pos - prev_pos).normalize_or_zero() * 2.;
+        // Stays in place without a hovered frame.
+        editor.hovered_frame_idx = None;
+        editor.update_drawn_frames();
+        assert_eq!(editor.drawn_frames, 40..140);
 
-                // Draw the arrow.
-                draw(DrawLine {
-                    start: pos - perp + diff,
-                    end: pos,
-                    color: Vec3::new(1., 1., 0.5),
-                });
-                draw(DrawLine {
-                    start: pos,
-                    end: pos + perp + diff,
-                    color: Vec3::new(1., 1., 0.5),
-                });
-            }
+        // Doesn't start before the first shown frame.
+        editor.first_shown_frame_idx = 500;
+        editor.update_drawn_frames();
+        assert_eq!(editor.drawn_frames, 500..600);
+    }
 
-            if is_last_in_bulk {
-                // Reset flag for next iteration.
-                collided_this_bulk = false;
-            }
-        }
+    #[test]
+    fn apply_accurate_frame_stores_rendered_viewangles() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|2").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        push_default_frames(&mut editor, 3);
+        editor.branch_mut().first_predicted_frame = 1;
+
+        let mut frame = editor.branch().frames[1].clone();
+        frame.state.rendered_viewangles = Some(Vec3::new(10., 20., 0.));
+        editor.apply_accurate_frame(
+            AccurateFrame {
+                frame_idx: 1,
+                frame,
+                generation: editor.generation(),
+                branch_idx: 0,
+                is_smoothed: false,
+            },
+            true,
+        );
+
+        // The predicted frames are still valid.
+        assert_eq!(editor.branch().frames.len(), 3);
+        assert_eq!(editor.branch().first_predicted_frame, 2);
+        assert_eq!(
+            editor.branch().frames[1].state.rendered_viewangles,
+            Some(Vec3::new(10., 20., 0.))
+        );
     }
 
-    fn draw_auto_smoothing(&self, mut draw: impl FnMut(DrawLine)) {
-        if !self.auto_smoothing {
-            return;
-        }
+    #[test]
+    fn apply_smoothing_to_range() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|10").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-        for (prev_idx, (prev, frame)) in self
-            .branch()
-            .auto_smoothing
-            .frames
-            .iter()
-            .tuple_windows()
-            .enumerate()
-        {
-            let idx = prev_idx + 1;
+        push_frames(&mut editor, 0..11, |frame, i| {
+            frame.parameters.frame_time = 0.004;
+            frame.state.prev_frame_input.yaw = (i as f32 * 10.).to_radians();
+        });
 
-            // How many frames until the visible part, clamped in a way to allow for smooth dimming.
-            let frames_until_hidden = self.first_shown_frame_idx.saturating_sub(idx - 1).min(20);
-            // Hidden frames become invisible, smoothly transition into visible.
-            let dim = if frames_until_hidden == 0 {
-                1.
-            } else {
-                (20 - frames_until_hidden) as f32 / 40.
-            };
+        editor.branch_mut().first_predicted_frame = 5;
+        assert!(matches!(
+            editor.apply_smoothing_to_range(3, 6),
+            Err(ManualOpError::UserError(_))
+        ));
 
-            let prev_pos = prev.state.player.pos;
-            let pos = frame.state.player.pos;
+        editor.branch_mut().first_predicted_frame = 11;
+        editor.apply_smoothing_to_range(3, 6).unwrap();
 
-            // Line from previous to this frame position.
-            draw(DrawLine {
-                start: prev_pos,
-                end: pos,
-                color: Vec3::new(1., 0.75, 0.5) * dim,
-            });
-        }
+        let lines = &editor.branch().branch.script.lines;
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].frame_bulk().unwrap().frame_count.get(), 2);
+        let Line::TargetYawOverride(yaws) = &lines[1] else {
+            panic!("expected a target_yaw_override line, got {:?}", lines[1]);
+        };
+        assert_eq!(yaws.len(), 4);
+        assert_eq!(lines[2].frame_bulk().unwrap().frame_count.get(), 8);
+
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
     }
 
-    fn draw_other_branches(&self, mut draw: impl FnMut(DrawLine)) {
-        let total_time = self
-            .branch()
-            .frames
-            .iter()
-            .skip(1)
-            .map(|f| f.parameters.frame_time)
-            .sum::<f32>();
+    #[test]
+    fn apply_global_smoothing_undo_redo() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|10").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-        for (idx, branch) in self.branches.iter().enumerate() {
-            if idx == self.branch_idx {
-                continue;
-            }
+        push_frames(&mut editor, 0..11, |frame, i| {
+            frame.parameters.frame_time = 0.004;
+            frame.state.prev_frame_input.yaw = (i as f32 * 10.).to_radians();
+        });
+        editor.branch_mut().first_predicted_frame = 11;
 
-            if branch.branch.is_hidden {
-                // Skip hidden branches.
-                continue;
-            }
+        editor.apply_global_smoothing().unwrap();
+        let smoothed = editor.branch().branch.script.clone();
+        assert_eq!(smoothed.lines.len(), 3);
+        assert!(matches!(smoothed.lines[0], Line::TargetYawOverride(_)));
+        assert!(matches!(smoothed.lines[1], Line::PitchOverride(_)));
+        assert_eq!(editor.undo_log_len(), 1);
 
-            let mut time = 0.;
-            for (prev_idx, (prev, frame)) in branch.frames.iter().tuple_windows().enumerate() {
-                let idx = prev_idx + 1;
+        // A single undo reverts both inserted lines.
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
+        assert_eq!(editor.undo_log_len(), 0);
 
-                // How many frames until the visible part, clamped in a way to allow for smooth
-                // dimming.
-                let frames_until_hidden =
-                    self.first_shown_frame_idx.saturating_sub(idx - 1).min(20);
-                // Hidden frames become invisible, smoothly transition into visible.
-                let dim = if frames_until_hidden == 0 {
-                    1.
-                } else {
-                    (20 - frames_until_hidden) as f32 / 40.
-                };
+        editor.redo().unwrap();
+        assert_eq!(editor.branch().branch.script, smoothed);
+        assert_eq!(editor.undo_log_len(), 1);
+    }
 
-                let prev_pos = prev.state.player.pos;
-                let pos = frame.state.player.pos;
+    #[test]
+    fn set_frame_time_preserving_duration() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                ----------|------|------|0.004|10|-|5",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-                // Line from previous to this frame position.
-                draw(DrawLine {
-                    start: prev_pos,
-                    end: pos,
-                    color: Vec3::ONE * 0.5 * dim,
-                });
+        editor.select_bulk(0).unwrap();
+        editor
+            .set_frame_time_preserving_duration("0.002".to_owned())
+            .unwrap();
+        let bulk = editor.script().frame_bulks().next().unwrap();
+        assert_eq!(bulk.frame_time, "0.002");
+        assert_eq!(bulk.frame_count.get(), 12);
+        assert_eq!(editor.undo_log_len(), 1);
 
-                time += frame.parameters.frame_time;
-                let next_frame = branch.frames.get(idx + 1).unwrap_or(frame);
-                let next_time = time + next_frame.parameters.frame_time;
-                if (time - total_time).abs() < (next_time - total_time).abs() {
-                    // Draw other branches up to the length of the current branch.
-                    break;
-                }
-            }
-        }
-    }
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
 
-    fn draw_inner(&self, mut draw: impl FnMut(DrawLine)) {
-        // At least on my machine, things that are drawn later visually appear over things that are
-        // drawn earlier. Therefore, the drawing order should be from the least to the most
-        // important.
-        self.draw_other_branches(&mut draw);
-        self.draw_auto_smoothing(&mut draw);
-        self.draw_current_branch(&mut draw);
+        // The frame count is rounded.
+        editor.select_bulk(1).unwrap();
+        editor
+            .set_frame_time_preserving_duration("0.003".to_owned())
+            .unwrap();
+        let bulk = editor.script().frame_bulks().nth(1).unwrap();
+        assert_eq!(bulk.frame_count.get(), 7);
+
+        // Unparseable frame times leave the frame count alone.
+        editor
+            .set_frame_time_preserving_duration("abc".to_owned())
+            .unwrap();
+        let bulk = editor.script().frame_bulks().nth(1).unwrap();
+        assert_eq!(bulk.frame_time, "abc");
+        assert_eq!(bulk.frame_count.get(), 7);
     }
 
-    /// Draws the editor UI.
-    #[instrument("Editor::draw", skip_all)]
-    pub fn draw(&self, tri: &TriangleApi) {
-        tri.render_mode(RenderMode::TransColor);
+    #[test]
+    fn snap_selected_yaw_to_vct() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|37.3|-|6\n\
+                ----------|------|------|0.004|-|-|5",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-        tri.begin(Primitive::Lines);
+        editor.select_bulk(0).unwrap();
+        editor.snap_selected_yaw_to_vct().unwrap();
 
-        self.draw_inner(|DrawLine { start, end, color }| {
-            tri.color(color.x, color.y, color.z, 1.);
-            tri.vertex(start);
-            tri.vertex(end);
-        });
+        let expected = Vct::get()
+            .find_best(37.3f32.to_radians())
+            .angle_radians()
+            .to_degrees();
+        let bulk = editor.script().frame_bulks().next().unwrap();
+        assert_eq!(bulk.yaw(), Some(&expected));
+        assert_eq!(editor.undo_log_len(), 1);
 
-        tri.end();
+        editor.undo().unwrap();
+        assert_eq!(editor.branch().branch.script, script);
+
+        // Bulks without a yaw can't be snapped.
+        editor.select_bulk(1).unwrap();
+        assert!(matches!(
+            editor.snap_selected_yaw_to_vct(),
+            Err(ManualOpError::UserError(_))
+        ));
     }
-}
 
-fn perpendicular(prev: Vec3, next: Vec3) -> Vec3 {
-    let line = (next - prev).normalize_or_zero();
+    #[test]
+    fn export_hltas_to_path() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                s03lj-----|------|------|0.001|15|10|2",
+        )
+        .unwrap();
+        let editor = Editor::create_in_memory(&script).unwrap();
 
-    let rv = if line.x == 0. {
-        Vec3::X
-    } else if line.y == 0. {
-        Vec3::Y
-    } else {
-        Vec3::new(1., -line.x / line.y, 0.).normalize()
-    };
+        let path = std::env::temp_dir().join(format!(
+            "bxt-rs-export-hltas-to-path-{}.hltas",
+            std::process::id()
+        ));
 
-    // Make sure it's oriented in a particular way: this makes right-drag to change yaw behave as
-    // expected (the yaw will change in the direction where you move the mouse).
-    if rv.x * line.y - rv.y * line.x > 0. {
-        -rv
-    } else {
-        rv
+        // There's no smoothed script without auto-smoothing.
+        assert!(editor.export_hltas_to_path(&path, true).is_err());
+
+        editor.export_hltas_to_path(&path, false).unwrap();
+        let exported = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(HLTAS::from_str(&exported).unwrap(), script);
     }
-}
 
-fn forward(pitch: f32, yaw: f32) -> Vec3 {
-    let (sin_pitch, cos_pitch) = pitch.sin_cos();
-    let (sin_yaw, cos_yaw) = yaw.sin_cos();
-    Vec3::new(cos_pitch * cos_yaw, cos_pitch * sin_yaw, -sin_pitch)
-}
+    #[test]
+    fn branches_info() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|6").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-fn unwrap_angles(xs: impl Iterator<Item = f32>) -> impl Iterator<Item = f32> {
-    use std::f32::consts::PI;
+        let info = editor.branches_info();
+        assert_eq!(info.len(), 1);
+        assert_eq!(info[0].frame_count, 6);
+        assert!(info[0].is_focused);
+        assert!(!info[0].is_hidden);
+
+        editor.branch_clone().unwrap();
+        let other_idx = 1 - editor.branch_idx();
+        editor.branch_hide(other_idx).unwrap();
+
+        let info = editor.branches_info();
+        assert_eq!(info.len(), 2);
+        assert_ne!(info[0].branch_id, info[1].branch_id);
+        assert!(info[editor.branch_idx()].is_focused);
+        assert!(!info[other_idx].is_focused);
+        assert!(info[other_idx].is_hidden);
+    }
 
-    xs.scan((0., 0.), |(prev, offset), curr| {
-        let mut diff = curr - *prev + *offset;
-        while diff >= PI {
-            diff -= 2. * PI;
-            *offset -= 2. * PI;
-        }
-        while diff <= -PI {
-            diff += 2. * PI;
-            *offset += 2. * PI;
-        }
+    #[test]
+    fn smoothed_scratch_set_yaw() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|2\n\
+                ----------|------|------|0.004|20|-|2",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-        *prev += diff;
-        Some(*prev)
-    })
-}
+        assert!(editor.smoothed_scratch_set_yaw(1, 30.).is_err());
 
-fn smoothed_views(
-    window_size: f32,
-    small_window_size: f32,
-    small_window_multiplier: f32,
-    frames: &[Frame],
-    views: impl Iterator<Item = f32>,
-) -> Vec<f32> {
-    if frames.is_empty() {
-        return vec![];
-    }
-
-    let unwrapped: Vec<f32> = unwrap_angles(views).collect();
-    let mut rv = Vec::with_capacity(unwrapped.len());
-
-    fn frame_time(frame: &Frame) -> f32 {
-        frame.parameters.frame_time
-    }
-
-    let repeat_first = iter::repeat((frame_time(&frames[0]), unwrapped[0]));
-    let repeat_last = iter::repeat((
-        frame_time(frames.last().unwrap()),
-        *unwrapped.last().unwrap(),
-    ));
-
-    // The smoothing window is centered at the center of each yaw.
-    // For pitch smoothing, every frame has both pitch and yaw so iterate over this is ok.
-    for i in 0..unwrapped.len() {
-        let mut total_view = 0.;
-        let mut total_weight = 0.;
-
-        let mut process_frame =
-            |(mut rem_win_size, mut rem_small_win_size), (mut frame_time, view): (f32, f32)| {
-                // If there's any small window zone left to cover, do so.
-                if rem_small_win_size > 0. {
-                    let dt = frame_time.min(rem_small_win_size);
-                    let weight = dt * small_window_multiplier;
-
-                    total_view += view * weight;
-                    total_weight += weight;
-
-                    rem_win_size -= dt;
-                    rem_small_win_size -= dt;
-                    frame_time -= dt;
-                }
+        editor.set_auto_smoothing(true);
+        let smoothing = &mut editor.branch_mut().auto_smoothing;
+        smoothing.script = Some(script.clone());
+        for _ in 0..5 {
+            smoothing.frames.push(Frame {
+                parameters: Parameters::default(),
+                state: State::default(),
+            });
+        }
 
-                if frame_time <= 0. {
-                    // Ran out of frame time in the branch above (entire frame was covered by the
-                    // small window).
-                    return ControlFlow::Continue((rem_win_size, rem_small_win_size));
-                }
+        editor.smoothed_scratch_set_yaw(1, 30.).unwrap();
+        let yaws: Vec<_> = editor
+            .smoothed_scratch_script()
+            .unwrap()
+            .frame_bulks()
+            .map(|bulk| bulk.yaw().copied())
+            .collect();
+        assert_eq!(yaws, [Some(10.), Some(30.)]);
 
-                if rem_win_size <= 0. {
-                    // Ran out of smoothing window, break.
-                    return ControlFlow::Break(());
-                }
+        // The frames starting from the edited frame bulk will be predicted again.
+        let scratch = editor.branch().auto_smoothing.scratch.as_ref().unwrap();
+        assert_eq!(scratch.frames.len(), 3);
+
+        // The smoothed script itself is unchanged.
+        assert_eq!(editor.smoothed_script(), Some(&script));
 
-                // If there's any regular window zone left to cover, do so.
-                let dt = frame_time.min(rem_win_size);
-                let weight = dt;
+        editor.smoothed_scratch_clear();
+        assert_eq!(editor.smoothed_scratch_script(), None);
+    }
 
-                total_view += view * weight;
-                total_weight += weight;
+    #[test]
+    fn set_frame_count_limits() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|4294967295\n\
+                ----------|------|------|0.004|10|-|4294967295",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+        editor.selected_bulk_idx = Some(0);
 
-                rem_win_size -= dt;
-                // No need to decrease rem_small_win_size as it is already == 0 here.
+        // Already above the default maximum.
+        assert!(matches!(
+            editor.set_frame_count(u32::MAX - 1),
+            Err(ManualOpError::UserError(_))
+        ));
+        assert_eq!(*editor.script(), script);
 
-                if rem_win_size <= 0. {
-                    // Ran out of smoothing window, break.
-                    ControlFlow::Break(())
-                } else {
-                    // Still have smoothing window remaining, continue.
-                    ControlFlow::Continue((rem_win_size, rem_small_win_size))
-                }
-            };
+        editor.set_max_frame_count(usize::MAX);
+        editor.set_frame_count(u32::MAX - 1).unwrap();
+        assert_eq!(
+            editor
+                .script()
+                .frame_bulks()
+                .next()
+                .unwrap()
+                .frame_count
+                .get(),
+            u32::MAX - 1
+        );
 
-        let rem_win_size = window_size / 2.;
-        let rem_small_win_size = small_window_size / 2.;
+        editor.set_max_frame_count(u32::MAX as usize + 10);
+        assert_eq!(editor.max_bulk_frame_count(0), 10);
+        assert!(editor.set_frame_count(11).is_err());
+        editor.set_frame_count(10).unwrap();
 
-        // Start from the middle frame.
-        let middle_frame_half = iter::once((frames[i].parameters.frame_time / 2., unwrapped[i]));
+        assert!(matches!(
+            editor.set_frame_count(0),
+            Err(ManualOpError::UserError(_))
+        ));
+    }
 
-        // Walk back half an interval.
-        middle_frame_half
-            .clone()
-            .chain(
-                zip(
-                    frames[..i].iter().map(frame_time),
-                    unwrapped[..i].iter().copied(),
-                )
-                .rev(),
-            )
-            .chain(repeat_first.clone())
-            .try_fold((rem_win_size, rem_small_win_size), &mut process_frame);
+    #[test]
+    fn parameter_overrides_move_with_frames() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|4\n\
+                ----------|------|------|0.004|10|-|4",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-        // Walk forward half an interval.
-        middle_frame_half
-            .chain(zip(
-                frames[i + 1..].iter().map(frame_time),
-                unwrapped[i + 1..].iter().copied(),
-            ))
-            .chain(repeat_last.clone())
-            .try_fold((rem_win_size, rem_small_win_size), &mut process_frame);
+        editor
+            .set_parameter_override(2, "friction".to_owned(), 1.)
+            .unwrap();
+        editor
+            .set_parameter_override(6, "friction".to_owned(), 2.)
+            .unwrap();
 
-        rv.push(total_view / total_weight);
-    }
+        // Making the first frame bulk longer moves the overrides after it.
+        editor.select_bulk(0).unwrap();
+        editor.set_frame_count(6).unwrap();
+        let keys: Vec<_> = editor
+            .branch()
+            .parameter_overrides
+            .keys()
+            .copied()
+            .collect();
+        assert_eq!(keys, [2, 8]);
 
-    rv
-}
+        editor.undo().unwrap();
+        let keys: Vec<_> = editor
+            .branch()
+            .parameter_overrides
+            .keys()
+            .copied()
+            .collect();
+        assert_eq!(keys, [2, 6]);
 
-fn replace_multiple_params<'a>(
-    old_script: &HLTAS,
-    new_script: &'a HLTAS,
-) -> Option<(usize, usize, &'a [Line])> {
-    if new_script.properties != old_script.properties {
-        return None;
+        // Changes that keep the frame count keep the overrides in place.
+        editor.set_yaw(Some(20.)).unwrap();
+        let keys: Vec<_> = editor
+            .branch()
+            .parameter_overrides
+            .keys()
+            .copied()
+            .collect();
+        assert_eq!(keys, [2, 6]);
+
+        // The overrides are per branch, and changing them only invalidates the current branch.
+        push_default_frames(&mut editor, 9);
+        editor.branch_clone().unwrap();
+        editor.clear_parameter_overrides(None).unwrap();
+        assert!(editor.branch().parameter_overrides.is_empty());
+        assert_eq!(editor.branch().frames.len(), 2);
+
+        let keys: Vec<_> = editor.branches[0]
+            .parameter_overrides
+            .keys()
+            .copied()
+            .collect();
+        assert_eq!(keys, [2, 6]);
+        assert_eq!(editor.branches[0].frames.len(), 9);
     }
 
-    // Most manual edits will probably modify, add or delete a few lines close together, leaving
-    // everything before and after the same. We find this region by discarding all consecutive lines
-    // that remained the same at the beginning and at the end of the two scripts.
+    #[test]
+    fn set_max_trace_distance_invalidates_predicted_frames() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|4").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-    let new_len = new_script.lines.len();
-    let old_len = old_script.lines.len();
+        push_default_frames(&mut editor, 5);
 
-    // Find the index of the first non-matching line. It is the same between the two scripts.
-    let matching_lines_from_start = zip(&new_script.lines, &old_script.lines)
-        .find_position(|(new, old)| new != old)
-        .map(|(idx, _)| idx)
-        // If we exhaust one of the iterators, return the current index, which is equal to the
-        // length of the shorter iterator.
-        .unwrap_or(min(new_len, old_len));
-    let first_line_idx = matching_lines_from_start;
+        // Setting the same value keeps the frames.
+        editor.set_max_trace_distance(None);
+        assert_eq!(editor.branch().frames.len(), 5);
 
-    // Operate on the non-matching part to avoid the next search from stepping on the same lines.
-    //
-    // For example, consider a test case like this:
-    //
-    // ```
-    // version 1
-    // frames
-    // ----------|------|------|0.001|-|-|1
-    // ----------|------|------|0.001|-|-|1
-    // ```
-    //
-    // ```
-    // version 1
-    // frames
-    // ----------|------|------|0.001|-|-|1
-    // ```
-    //
-    // When doing the reverse search below using the original (non-sliced) arrays, it will count the
-    // single matching line from both the start and the end, causing an overlap and a logic error.
-    // By slicing here we're enforcing that all lines that were counted during the forward search
-    // are not counted during the reverse search.
-    let new_script_non_matching = &new_script.lines[first_line_idx..];
-    let old_script_non_matching = &old_script.lines[first_line_idx..];
-    let new_len_non_matching = new_len - matching_lines_from_start;
-    let old_len_non_matching = old_len - matching_lines_from_start;
+        editor.set_max_trace_distance(Some(16.));
+        assert_eq!(editor.branch().frames.len(), 1);
+    }
 
-    // Find the index of the first non-matching line from the end. It is the same between the two
-    // scripts.
-    let matching_lines_from_end = zip(
-        new_script_non_matching.iter().rev(),
-        old_script_non_matching.iter().rev(),
-    )
-    .find_position(|(new, old)| new != old)
-    .map(|(idx, _)| idx)
-    // If we exhaust one of the iterators, return the current index, which is equal to the
-    // length of the shorter iterator.
-    .unwrap_or(min(new_len_non_matching, old_len_non_matching));
+    #[test]
+    fn frame_count_limit_when_already_over() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|10\n\
+                ----------|------|------|0.004|10|-|20",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+        editor.set_max_frame_count(15);
+        editor.select_bulk(1).unwrap();
 
-    let count = old_len - matching_lines_from_end - matching_lines_from_start;
+        let left_down_at = |x| MouseState {
+            pos: IVec2::new(x, 0),
+            buttons: MouseButtons::Left,
+        };
 
-    let new_one_past_last_line_idx = new_len - matching_lines_from_end;
-    let to = &new_script.lines[first_line_idx..new_one_past_last_line_idx];
+        // Starting a drag doesn't cut the frame bulk down to the limit.
+        editor.frame_count_adjustment = Some(MouseAdjustment::new(20, Vec2::ZERO, Vec2::X));
+        editor
+            .tick_frame_count_adjustment(left_down_at(0), KeyboardState::default())
+            .unwrap();
+        editor
+            .tick_frame_count_adjustment(left_down_at(10), KeyboardState::default())
+            .unwrap();
+        assert_eq!(*editor.script(), script);
 
-    Some((first_line_idx, count, to))
-}
+        // Making it shorter still works.
+        editor
+            .tick_frame_count_adjustment(left_down_at(-30), KeyboardState::default())
+            .unwrap();
+        let bulk = editor.script().frame_bulks().nth(1).unwrap();
+        assert_eq!(bulk.frame_count.get(), 17);
+        editor
+            .tick_frame_count_adjustment(MouseState::default(), KeyboardState::default())
+            .unwrap();
 
-#[cfg(test)]
-mod tests {
-    use bxt_strafe::{Input, Parameters, State};
-    use expect_test::{expect, Expect};
-    use proptest::prelude::*;
+        // Duplicating makes the script longer, so it's refused.
+        assert!(matches!(
+            editor.duplicate_selected_bulk(),
+            Err(ManualOpError::UserError(_))
+        ));
+        assert_eq!(editor.script().frame_bulks().count(), 2);
+    }
 
-    use super::*;
+    #[test]
+    fn lines_frame_count_saturates() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|4294967295\n\
+                ----------|------|------|0.004|10|-|4294967295",
+        )
+        .unwrap();
+        assert_eq!(
+            lines_frame_count(&script.lines),
+            (u32::MAX as usize).saturating_mul(2)
+        );
+    }
 
     #[test]
-    fn undo_redo() {
+    fn apply_operation_skips_no_op() {
         let script =
             HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|6").unwrap();
         let mut editor = Editor::create_in_memory(&script).unwrap();
 
-        // Undo with no changes should return an error saying there are no actions to undo.
-        assert!(matches!(editor.undo(), Err(ManualOpError::UserError(_))));
+        editor
+            .apply_operation(Operation::SetYaw {
+                bulk_idx: 0,
+                from: 10.,
+                to: 10.,
+            })
+            .unwrap();
+        assert_eq!(editor.undo_log_len(), 0);
+        assert_eq!(*editor.script(), script);
 
-        let before_op = editor.branch().branch.script.clone();
         editor
-            .apply_operation(Operation::SetFrameCount {
+            .apply_operation(Operation::SetYaw {
                 bulk_idx: 0,
-                from: 6,
-                to: 10,
+                from: 10.,
+                to: 15.,
             })
             .unwrap();
-        let after_op = editor.branch().branch.script.clone();
-        assert_ne!(before_op, after_op, "operation should affect the HLTAS");
+        assert_eq!(editor.undo_log_len(), 1);
+    }
 
-        editor.undo().unwrap();
-        assert_eq!(
-            before_op,
-            editor.branch().branch.script,
-            "undo produced wrong result"
-        );
+    #[test]
+    fn retime_range() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.010000001|-|-|10\n\
+                ----------|------|------|0.004|-|-|5\n\
+                ----------|------|------|0.010000001|-|-|3\n\
+                ----------|------|------|0.004|-|-|1",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-        editor.redo().unwrap();
+        let inexact = editor.retime_range(0, 2, "0.004").unwrap();
+        assert_eq!(inexact, [2]);
+
+        let bulks: Vec<_> = editor
+            .script()
+            .frame_bulks()
+            .map(|bulk| (bulk.frame_time.as_str(), bulk.frame_count.get()))
+            .collect();
         assert_eq!(
-            after_op,
-            editor.branch().branch.script,
-            "redo produced wrong result"
+            bulks,
+            [("0.004", 25), ("0.004", 5), ("0.004", 8), ("0.004", 1)]
         );
 
-        // Redo with no changes should return an error saying there are no actions to redo.
-        assert!(matches!(editor.redo(), Err(ManualOpError::UserError(_))));
+        // Everything is undone at once.
+        editor.undo().unwrap();
+        assert_eq!(*editor.script(), script);
+
+        assert!(editor.retime_range(0, 0, "abc").is_err());
     }
 
     #[test]
-    fn replace_multiple_optimization_bug_1() {
+    fn flatten_yaws_in_range() {
         let script = HLTAS::from_str(
             "version 1\nframes\n\
-                ----------|------|------|0.004|10|-|6\n\
-                ----------|------|------|0.004|10|-|6",
+                s03-------|------|------|0.004|170|-|1\n\
+                ----------|------|------|0.004|-|-|1\n\
+                ----------|------|------|0.004|-170|-|1\n\
+                ----------|------|------|0.004|10|-|1",
         )
         .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-        let new_script = HLTAS::from_str(
-            "version 1\nframes\n\
-                ----------|------|------|0.004|10|-|6",
-        )
-        .unwrap();
+        push_frames(&mut editor, [0., 100., 0., 300.], |frame, speed| {
+            frame.state.player.vel = Vec3::new(speed, 0., 0.);
+        });
 
-        let (first_line_idx, count, to) = replace_multiple_params(&script, &new_script).unwrap();
-        assert_eq!(first_line_idx, 1);
-        assert_eq!(count, 1);
-        assert_eq!(to, []);
+        editor.flatten_yaws_in_range(0, 2).unwrap();
+
+        let yaws: Vec<_> = editor
+            .script()
+            .frame_bulks()
+            .map(|bulk| bulk.yaw().copied())
+            .collect();
+        // (170 * 100 + 190 * 300) / 400 = 185 = -175.
+        assert_eq!(yaws.len(), 4);
+        assert!((yaws[0].unwrap() + 175.).abs() < 1e-3, "{yaws:?}");
+        assert_eq!(yaws[1], None);
+        assert_eq!(yaws[0], yaws[2]);
+        assert_eq!(yaws[3], Some(10.));
+
+        // Everything is undone at once.
+        editor.undo().unwrap();
+        assert_eq!(*editor.script(), script);
     }
 
     #[test]
-    fn replace_multiple_optimization_bug_2() {
-        let script = HLTAS::from_str("version 1\nframes\nstrafing vectorial").unwrap();
-        let new_script = HLTAS::from_str("version 1\nframes\nstrafing vectorial").unwrap();
-
-        let (first_line_idx, count, to) = replace_multiple_params(&script, &new_script).unwrap();
-        assert_eq!(first_line_idx, 1);
-        assert_eq!(count, 0);
-        assert_eq!(to, []);
+    fn player_bbox_half_height_during_ducking() {
+        let mut player = Player::default();
+        assert_eq!(player_bbox_half_height(&player), 36.);
+
+        player.in_duck_animation = true;
+        player.duck_time = 1000;
+        assert_eq!(player_bbox_half_height(&player), 36.);
+        player.duck_time = 800;
+        assert_eq!(player_bbox_half_height(&player), 27.);
+        player.duck_time = 600;
+        assert_eq!(player_bbox_half_height(&player), 18.);
+
+        player.in_duck_animation = false;
+        player.ducking = true;
+        assert_eq!(player_bbox_half_height(&player), 18.);
     }
 
     #[test]
-    fn selected_bulk_idx_is_not_invalid_after_rewrite() {
+    fn copy_paste_bulk() {
         let script = HLTAS::from_str(
             "version 1\nframes\n\
                 ----------|------|------|0.004|10|-|6\n\
-                ----------|------|------|0.004|20|-|6",
+                s03lj-----|------|------|0.001|20|-|3",
         )
         .unwrap();
         let mut editor = Editor::create_in_memory(&script).unwrap();
 
+        assert!(matches!(
+            editor.selected_bulk_text(),
+            Err(ManualOpError::NoSelectedBulk)
+        ));
+
         editor.selected_bulk_idx = Some(1);
+        let text = editor.selected_bulk_text().unwrap();
 
-        let new_script = HLTAS::from_str(
+        editor.selected_bulk_idx = Some(0);
+        editor.paste_bulk(&text).unwrap();
+        assert!(matches!(
+            editor.paste_bulk("not a frame bulk"),
+            Err(ManualOpError::UserError(_))
+        ));
+
+        let bulks: Vec<_> = editor.script().frame_bulks().collect();
+        assert_eq!(bulks[0].frame_count.get(), 6);
+        assert_eq!(
+            FrameBulk {
+                frame_count: bulks[1].frame_count,
+                ..bulks[0].clone()
+            },
+            *bulks[1]
+        );
+    }
+
+    #[test]
+    fn mirror_yaws_in_range() {
+        let script = HLTAS::from_str(
             "version 1\nframes\n\
-                ----------|------|------|0.004|10|-|6",
+                ----------|------|------|0.004|10|-|1\n\
+                s03-------|------|------|0.004|20|-|1\n\
+                s06-------|------|------|0.004|5|-|1\n\
+                ----------|------|------|0.004|40|-|1",
         )
         .unwrap();
-        editor.rewrite(new_script).unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-        assert_eq!(editor.selected_bulk_idx, None);
-    }
+        editor.mirror_yaws_in_range(0, 2, 90.).unwrap();
+        assert_eq!(editor.undo_log.len(), 1);
 
-    fn check_unwrap_angles(input: impl IntoIterator<Item = f32>, expect: Expect) {
-        let radians = input.into_iter().map(|x| x.to_radians());
-        let unwrapped: Vec<f32> = unwrap_angles(radians)
-            .map(|x| x.to_degrees().round())
+        let movements: Vec<_> = editor
+            .script()
+            .frame_bulks()
+            .map(|bulk| bulk.auto_actions.movement)
             .collect();
-        expect.assert_debug_eq(&unwrapped);
-    }
+        assert_eq!(movements[0], Some(AutoMovement::SetYaw(170.)));
+        assert!(matches!(
+            movements[1],
+            Some(AutoMovement::Strafe(StrafeSettings {
+                dir: StrafeDir::Yaw(yaw),
+                ..
+            })) if yaw == 160.
+        ));
+        assert!(matches!(
+            movements[2],
+            Some(AutoMovement::Strafe(StrafeSettings {
+                dir: StrafeDir::LeftRight(count),
+                ..
+            })) if count.get() == 5
+        ));
+        assert_eq!(movements[3], Some(AutoMovement::SetYaw(40.)));
 
-    #[test]
-    fn test_unwrap_angles_idempotent() {
-        check_unwrap_angles(
-            [0., 1., 2., 3.],
-            expect![[r#"
-            [
-                0.0,
-                1.0,
-                2.0,
-                3.0,
-            ]
-        "#]],
-        );
+        editor.undo().unwrap();
+        assert_eq!(*editor.script(), script);
+
+        assert!(matches!(
+            editor.mirror_yaws_in_range(2, 1, 0.),
+            Err(ManualOpError::UserError(_))
+        ));
+        assert!(matches!(
+            editor.mirror_yaws_in_range(0, 4, 0.),
+            Err(ManualOpError::UserError(_))
+        ));
     }
 
     #[test]
-    fn test_unwrap_angles() {
-        check_unwrap_angles(
-            [0., 170., -170., 160., -160., -165.],
-            expect![[r#"
-                [
-                    0.0,
-                    170.0,
-                    190.0,
-                    160.0,
-                    200.0,
-                    195.0,
-                ]
-            "#]],
-        );
+    fn sim_step() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|1").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        assert!(matches!(
+            editor.sim_step(&DummyTracer),
+            Err(ManualOpError::NoHoveredFrame)
+        ));
+
+        push_default_frames(&mut editor, 2);
+
+        editor.hovered_frame_idx = Some(1);
+        assert!(matches!(
+            editor.sim_step(&DummyTracer),
+            Err(ManualOpError::UserError(_))
+        ));
+
+        editor.hovered_frame_idx = Some(0);
+        editor.sim_step(&DummyTracer).unwrap();
+        assert_eq!(editor.branch().frames.len(), 2);
     }
 
     #[test]
-    fn test_unwrap_angles_multiple_revolutions() {
-        check_unwrap_angles(
-            [
-                0., 120., -120., 0., 120., -120., 0., 120., -120., 120., 0., -120., 120., 0., -120.,
-            ],
-            expect![[r#"
-                [
-                    0.0,
-                    120.0,
-                    240.0,
-                    360.0,
-                    480.0,
-                    600.0,
-                    720.0,
-                    840.0,
-                    960.0,
-                    840.0,
-                    720.0,
-                    600.0,
-                    480.0,
-                    360.0,
-                    240.0,
-                ]
-            "#]],
-        );
-    }
+    fn autojump_range() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                ----------|------|------|0.004|10|-|6\n\
+                ----------|------|------|0.004|10|-|6\n\
+                ---lj-----|------|------|0.004|10|-|6\n\
+                ----d-----|------|------|0.004|10|-|6\n\
+                ----------|------|------|0.004|10|-|6",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
 
-    fn check_smoothing(
-        input: impl IntoIterator<Item = (f32, f32)>,
-        small_window_size: f32,
-        expect: Expect,
-    ) {
-        let frames: Vec<Frame> = input
-            .into_iter()
-            .map(|(frame_time, yaw)| Frame {
-                parameters: Parameters {
-                    frame_time,
-                    ..Default::default()
-                },
-                state: State {
-                    prev_frame_input: Input {
-                        yaw,
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                },
+        editor.autojump_range(1, 3).unwrap();
+
+        let has_autojump: Vec<_> = editor
+            .script()
+            .frame_bulks()
+            .map(|bulk| {
+                matches!(
+                    bulk.auto_actions.leave_ground_action,
+                    Some(LeaveGroundAction {
+                        type_: LeaveGroundActionType::Jump,
+                        ..
+                    })
+                )
             })
             .collect();
+        assert_eq!(has_autojump, [false, true, true, true, false]);
 
-        let yaws = frames.iter().map(|f| f.state.prev_frame_input.yaw);
-        let smoothed = smoothed_views(1., small_window_size, 4., &frames, yaws);
-        expect.assert_debug_eq(&smoothed);
-    }
+        // Everything is undone at once.
+        editor.undo().unwrap();
+        assert_eq!(*editor.script(), script);
 
-    #[test]
-    fn test_smoothing_on_small_input() {
-        check_smoothing(
-            [(0.1, 2.), (0.1, 2.), (0.1, 2.)],
-            0.5,
-            expect![[r#"
-            [
-                2.0,
-                2.0,
-                2.0,
-            ]
-        "#]],
-        );
+        assert!(matches!(
+            editor.autojump_range(3, 5),
+            Err(ManualOpError::UserError(_))
+        ));
     }
 
     #[test]
-    fn test_smoothing_no_small_window() {
-        check_smoothing(
-            [
-                (0.25, -1.),
-                (0.25, -1.),
-                (0.5, 1.),
-                (0.25, -1.),
-                (0.25, -1.),
-            ],
-            0.,
-            expect![[r#"
-                [
-                    -0.75,
-                    -0.25,
-                    0.0,
-                    -0.25,
-                    -0.75,
-                ]
-            "#]],
-        );
+    fn normalize_yaw_range() {
+        assert_eq!(normalize_yaw(725.), 5.);
+        assert_eq!(normalize_yaw(-350.), 10.);
+        assert_eq!(normalize_yaw(180.), -180.);
+        assert_eq!(normalize_yaw(-180.), -180.);
+
+        // rem_euclid() rounds this up to 360.
+        assert_eq!(normalize_yaw(-180.00002), -180.);
     }
 
     #[test]
-    fn test_smoothing_only_small_window() {
-        check_smoothing(
-            [
-                (0.25, -1.),
-                (0.25, -1.),
-                (0.5, 1.),
-                (0.25, -1.),
-                (0.25, -1.),
-            ],
-            1.,
-            expect![[r#"
-                [
-                    -0.75,
-                    -0.25,
-                    0.0,
-                    -0.25,
-                    -0.75,
-                ]
-            "#]],
-        );
+    fn normalize_yaws() {
+        let script = HLTAS::from_str(
+            "version 1\nframes\n\
+                s03-------|------|------|0.004|725|-|10\n\
+                ----------|f-----|------|0.004|-350|-|10\n\
+                s03-------|------|------|0.004|5|-|10",
+        )
+        .unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        let initial_frame = Frame {
+            parameters: Parameters {
+                frame_time: 0.004,
+                max_velocity: 2000.,
+                max_speed: 320.,
+                accelerate: 10.,
+                air_accelerate: 10.,
+                ..Parameters::default()
+            },
+            state: State::default(),
+        };
+        let simulate = |script: &HLTAS| -> Vec<Frame> {
+            Simulator::new(&DummyTracer, &[initial_frame.clone()], &script.lines).collect()
+        };
+        let frames_before = simulate(editor.script());
+
+        editor.normalize_yaws().unwrap();
+
+        let yaws: Vec<_> = editor
+            .script()
+            .frame_bulks()
+            .map(|bulk| bulk.yaw().copied())
+            .collect();
+        assert_eq!(yaws, [Some(5.), Some(10.), Some(5.)]);
+
+        let frames_after = simulate(editor.script());
+        assert_eq!(frames_before.len(), frames_after.len());
+        for (before, after) in zip(&frames_before, &frames_after) {
+            let before = before.state.player.pos;
+            let after = after.state.player.pos;
+            assert!(before.distance(after) < 1e-3, "{before} != {after}");
+        }
+
+        // Everything is undone at once.
+        editor.undo().unwrap();
+        assert_eq!(*editor.script(), script);
     }
 
     #[test]
-    fn test_smoothing() {
-        check_smoothing(
-            [
-                (0.25, -1.),
-                (0.25, -1.),
-                (0.5, 1.),
-                (0.25, -1.),
-                (0.25, -1.),
-            ],
-            0.5,
-            expect![[r#"
-                [
-                    -0.9,
-                    -0.4,
-                    0.6,
-                    -0.4,
-                    -0.9,
-                ]
-            "#]],
+    fn param_sensitivity() {
+        let script =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.004|10|-|100").unwrap();
+        let mut editor = Editor::create_in_memory(&script).unwrap();
+
+        assert!(matches!(
+            editor.param_sensitivity(&DummyTracer, "gravity", 100.),
+            Err(ManualOpError::UserError(_))
+        ));
+
+        let initial_frame = Frame {
+            parameters: Parameters {
+                frame_time: 0.004,
+                max_velocity: 2000.,
+                gravity: 800.,
+                ent_gravity: 1.,
+                ..Parameters::default()
+            },
+            state: State::default(),
+        };
+        let frames: Vec<Frame> =
+            Simulator::new(&DummyTracer, &[initial_frame.clone()], &script.lines).collect();
+        editor.branch_mut().frames.push(initial_frame);
+        editor.branch_mut().frames.extend(frames);
+
+        assert!(matches!(
+            editor.param_sensitivity(&DummyTracer, "nonexistent", 100.),
+            Err(ManualOpError::UserError(_))
+        ));
+
+        assert_eq!(
+            editor
+                .param_sensitivity(&DummyTracer, "gravity", 0.)
+                .unwrap(),
+            Vec3::ZERO
         );
-    }
 
-    #[test]
-    fn test_smoothing_even() {
-        check_smoothing(
-            [
-                (0.25, -1.),
-                (0.25, -1.),
-                (0.25, 1.),
-                (0.25, 1.),
-                (0.25, -1.),
-                (0.25, -1.),
-            ],
-            0.5,
-            expect![[r#"
-                [
-                    -0.9,
-                    -0.4,
-                    0.3,
-                    0.3,
-                    -0.4,
-                    -0.9,
-                ]
-            "#]],
+        // Falling in the air for 0.4 seconds with stronger gravity ends up lower.
+        let delta = editor
+            .param_sensitivity(&DummyTracer, "gravity", 100.)
+            .unwrap();
+        assert_eq!(delta.truncate(), Vec2::ZERO);
+        assert!(delta.z < -5., "{delta}");
+
+        // Air-only movement doesn't depend on friction.
+        assert_eq!(
+            editor
+                .param_sensitivity(&DummyTracer, "friction", 1.)
+                .unwrap(),
+            Vec3::ZERO
         );
     }
 
     #[test]
-    fn test_smoothing_partial_small() {
-        check_smoothing(
-            [
-                (0.25, -1.),
-                (0.25, -1.),
-                (0.4, 1.),
-                (0.25, -1.),
-                (0.25, -1.),
-            ],
-            0.5,
-            expect![[r#"
-                [
-                    -0.9,
-                    -0.4,
-                    0.28000003,
-                    -0.4,
-                    -0.9,
-                ]
-            "#]],
-        );
+    fn line_quad_has_constant_screen_width() {
+        // Perspective projection looking along +X from the origin, 100 pixels per unit at 1 unit
+        // of distance.
+        let world_to_screen =
+            |pos: Vec3| (pos.x > 0.).then(|| Vec2::new(pos.y, pos.z) * 100. / pos.x);
+
+        let start = Vec3::new(10., 0., 0.);
+        let end = Vec3::new(20., 0., 10.);
+        let [a, b, c, d] = line_quad(start, end, Vec3::ZERO, 4., world_to_screen).unwrap();
+
+        let width_at = |p: Vec3, q: Vec3| {
+            world_to_screen(p)
+                .unwrap()
+                .distance(world_to_screen(q).unwrap())
+        };
+        assert!((width_at(a, b) - 4.).abs() < 1e-3);
+        assert!((width_at(c, d) - 4.).abs() < 1e-3);
+
+        // A line pointing at the camera has no quad.
+        assert!(line_quad(start, start * 2., Vec3::ZERO, 4., world_to_screen).is_none());
+        // Neither does a line behind the camera.
+        assert!(line_quad(-start, -end, Vec3::ZERO, 4., world_to_screen).is_none());
     }
 
     proptest! {
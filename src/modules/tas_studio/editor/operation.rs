@@ -1,7 +1,7 @@
 use std::cmp::min;
 use std::num::NonZeroU32;
 
-use hltas::types::{FrameBulk, Line};
+use hltas::types::{AutoMovement, FrameBulk, Line, StrafeDir, StrafeSettings};
 use hltas::HLTAS;
 use serde::{Deserialize, Serialize};
 
@@ -124,6 +124,30 @@ pub enum Operation {
         from: (f32, f32),
         to: (f32, f32),
     },
+    /// Mirrors the movement of the frame bulks, turning left into right and vice versa.
+    ///
+    /// Applying it again undoes it.
+    MirrorYaws {
+        first_bulk_idx: usize,
+        bulk_count: usize,
+    },
+    /// Reverses the order of the frame bulks.
+    ///
+    /// Only the frame bulks are moved; other lines, like `strafing` or camera lines, stay where
+    /// they are, so they end up applying to different frame bulks.
+    ReverseBulkOrder {
+        first_bulk_idx: usize,
+        bulk_count: usize,
+    },
+    /// Sets the frame time of the frame bulk and the frame count of the next frame bulk.
+    ///
+    /// The values are the frame time of the frame bulk followed by the frame count of the next
+    /// frame bulk.
+    SetAdjacentFrameTime {
+        bulk_idx: usize,
+        from: (String, u32),
+        to: (String, u32),
+    },
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -491,11 +515,105 @@ impl Operation {
                     return Some(first_frame_idx);
                 }
             }
+            Operation::MirrorYaws {
+                first_bulk_idx,
+                bulk_count,
+            } => {
+                let mut bulks = bulk_and_first_frame_idx_mut(hltas).skip(first_bulk_idx);
+                let (bulk, first_frame_idx) = bulks.next().expect("invalid bulk index");
+                mirror_bulk(bulk);
+
+                for _ in 1..bulk_count {
+                    let bulk = bulks.next().expect("invalid bulk index").0;
+                    mirror_bulk(bulk);
+                }
+
+                return Some(first_frame_idx);
+            }
+            Operation::ReverseBulkOrder {
+                first_bulk_idx,
+                bulk_count,
+            } => {
+                let first_frame_idx = bulk_and_first_frame_idx_mut(hltas)
+                    .nth(first_bulk_idx)
+                    .expect("invalid bulk index")
+                    .1;
+
+                let mut bulks: Vec<_> = hltas
+                    .frame_bulks_mut()
+                    .skip(first_bulk_idx)
+                    .take(bulk_count)
+                    .collect();
+                assert_eq!(bulks.len(), bulk_count, "invalid bulk count");
+
+                for i in 0..bulk_count / 2 {
+                    let (left, right) = bulks.split_at_mut(bulk_count - 1 - i);
+                    std::mem::swap(left[i], right[0]);
+                }
+
+                return Some(first_frame_idx);
+            }
+            Operation::SetAdjacentFrameTime {
+                bulk_idx,
+                ref from,
+                ref to,
+            } => {
+                let mut bulks = bulk_and_first_frame_idx_mut(hltas).skip(bulk_idx);
+                let (bulk, first_frame_idx) = bulks.next().expect("invalid bulk index");
+                let (next_bulk, _) = bulks.next().expect("invalid bulk index");
+                drop(bulks);
+
+                assert_eq!(bulk.frame_time, from.0, "wrong current frame time");
+                assert_eq!(
+                    next_bulk.frame_count.get(),
+                    from.1,
+                    "wrong current frame count"
+                );
+
+                if from != to {
+                    bulk.frame_time = to.0.clone();
+                    next_bulk.frame_count = NonZeroU32::new(to.1).expect("invalid new frame count");
+                    return Some(first_frame_idx);
+                }
+            }
         }
 
         None
     }
 
+    /// Returns `true` if applying the operation certainly leaves the HLTAS unchanged.
+    ///
+    /// Operations whose effect can't be determined without the HLTAS, like [`Operation::Split`],
+    /// are never considered no-ops.
+    pub fn is_no_op(&self) -> bool {
+        match self {
+            Operation::SetFrameCount { from, to, .. }
+            | Operation::SetLeftRightCount { from, to, .. }
+            | Operation::SetAdjacentFrameCount { from, to, .. }
+            | Operation::SetAdjacentLeftRightCount { from, to, .. } => from == to,
+            Operation::SetYaw { from, to, .. }
+            | Operation::SetAdjacentYaw { from, to, .. }
+            | Operation::SetYawspeed { from, to, .. }
+            | Operation::SetAdjacentYawspeed { from, to, .. }
+            | Operation::SetMaxAccelOffsetStart { from, to, .. }
+            | Operation::SetMaxAccelOffsetTarget { from, to, .. }
+            | Operation::SetMaxAccelOffsetAccel { from, to, .. } => from == to,
+            Operation::Replace { from, to, .. }
+            | Operation::Rewrite { from, to }
+            | Operation::ReplaceMultiple { from, to, .. }
+            | Operation::SetFrameTime { from, to, .. } => from == to,
+            Operation::SetAdjacentFrameTime { from, to, .. } => from == to,
+            Operation::SetCommands { from, to, .. } => from == to,
+            Operation::SetMaxAccelOffsetStartAndTarget { from, to, .. } => from == to,
+            Operation::Delete { .. }
+            | Operation::Split { .. }
+            | Operation::ToggleKey { .. }
+            | Operation::Insert { .. }
+            | Operation::MirrorYaws { .. }
+            | Operation::ReverseBulkOrder { .. } => false,
+        }
+    }
+
     /// Undoes operation on HLTAS and returns index of first affected frame.
     ///
     /// Returns `None` if all frames remain valid.
@@ -847,12 +965,65 @@ impl Operation {
                     return Some(first_frame_idx);
                 }
             }
+            // Mirroring and reversing are both their own inverse.
+            Operation::MirrorYaws { .. } | Operation::ReverseBulkOrder { .. } => {
+                return self.apply(hltas)
+            }
+            Operation::SetAdjacentFrameTime {
+                bulk_idx,
+                ref from,
+                ref to,
+            } => {
+                let mut bulks = bulk_and_first_frame_idx_mut(hltas).skip(bulk_idx);
+                let (bulk, first_frame_idx) = bulks.next().expect("invalid bulk index");
+                let (next_bulk, _) = bulks.next().expect("invalid bulk index");
+                drop(bulks);
+
+                assert_eq!(bulk.frame_time, to.0, "wrong current frame time");
+                assert_eq!(
+                    next_bulk.frame_count.get(),
+                    to.1,
+                    "wrong current frame count"
+                );
+
+                if from != to {
+                    bulk.frame_time = from.0.clone();
+                    next_bulk.frame_count =
+                        NonZeroU32::new(from.1).expect("invalid original frame count");
+                    return Some(first_frame_idx);
+                }
+            }
         }
 
         None
     }
 }
 
+/// Mirrors the movement of the frame bulk, turning left into right and vice versa.
+///
+/// Strafing towards a point is kept as is since the point is in world coordinates.
+fn mirror_bulk(bulk: &mut FrameBulk) {
+    match &mut bulk.auto_actions.movement {
+        Some(AutoMovement::SetYaw(yaw)) => *yaw = -*yaw,
+        Some(AutoMovement::Strafe(StrafeSettings { dir, .. })) => {
+            *dir = match *dir {
+                StrafeDir::Left => StrafeDir::Right,
+                StrafeDir::Right => StrafeDir::Left,
+                StrafeDir::Best => StrafeDir::Best,
+                StrafeDir::Yaw(yaw) => StrafeDir::Yaw(-yaw),
+                StrafeDir::Point { x, y } => StrafeDir::Point { x, y },
+                StrafeDir::Line { yaw } => StrafeDir::Line { yaw: -yaw },
+                StrafeDir::LeftRight(count) => StrafeDir::RightLeft(count),
+                StrafeDir::RightLeft(count) => StrafeDir::LeftRight(count),
+            };
+        }
+        None => (),
+    }
+
+    let keys = &mut bulk.movement_keys;
+    (keys.left, keys.right) = (keys.right, keys.left);
+}
+
 impl Key {
     pub fn value_mut(self, bulk: &mut FrameBulk) -> &mut bool {
         match self {
@@ -912,6 +1083,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn op_mirror_yaws() {
+        check_op(
+            "\
+----------|------|------|0.004|10|-|6
+s00-------|-l----|------|0.004|-|-|6
+s06-------|------|------|0.004|10|-|6
+s03-------|------|------|0.004|-10|-|6",
+            Operation::MirrorYaws {
+                first_bulk_idx: 1,
+                bulk_count: 2,
+            },
+            "\
+----------|------|------|0.004|10|-|6
+s01-------|--r---|------|0.004|-|-|6
+s07-------|------|------|0.004|10|-|6
+s03-------|------|------|0.004|-10|-|6",
+        );
+    }
+
+    #[test]
+    fn op_reverse_bulk_order() {
+        check_op(
+            "\
+----------|------|------|0.004|10|-|6
+s03-------|------|------|0.004|10|-|5
+strafing vectorial
+s06-------|------|------|0.004|10|-|4
+s07-------|------|------|0.004|10|-|3",
+            Operation::ReverseBulkOrder {
+                first_bulk_idx: 1,
+                bulk_count: 3,
+            },
+            "\
+----------|------|------|0.004|10|-|6
+s07-------|------|------|0.004|10|-|3
+strafing vectorial
+s06-------|------|------|0.004|10|-|4
+s03-------|------|------|0.004|10|-|5",
+        );
+    }
+
+    #[test]
+    fn op_set_adjacent_frame_time() {
+        check_op(
+            "\
+----------|------|------|0.004|10|-|10
+----------|------|------|0.004|10|-|10",
+            Operation::SetAdjacentFrameTime {
+                bulk_idx: 0,
+                from: ("0.004".to_owned(), 10),
+                to: ("0.006".to_owned(), 5),
+            },
+            "\
+----------|------|------|0.006|10|-|10
+----------|------|------|0.004|10|-|5",
+        );
+    }
+
+    #[test]
+    fn op_is_no_op() {
+        assert!(Operation::SetYaw {
+            bulk_idx: 0,
+            from: 10.,
+            to: 10.,
+        }
+        .is_no_op());
+        assert!(!Operation::SetYaw {
+            bulk_idx: 0,
+            from: 10.,
+            to: 15.,
+        }
+        .is_no_op());
+        assert!(!Operation::Split { frame_idx: 0 }.is_no_op());
+    }
+
     #[test]
     fn op_set_frame_count() {
         check_op(
@@ -957,6 +1204,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ops_keep_comment_after_edited_bulk() {
+        // Comments such as split markers refer to the position between two frame bulks, so editing
+        // the frame bulk right before one must keep the comment after all of that bulk's frames.
+        check_op(
+            "----------|------|------|0.004|10|-|6\n\
+            // bxt-rs-split\n\
+            ----------|------|------|0.004|10|-|2",
+            Operation::Split { frame_idx: 4 },
+            "----------|------|------|0.004|10|-|4\n\
+            ----------|------|------|0.004|10|-|2\n\
+            // bxt-rs-split\n\
+            ----------|------|------|0.004|10|-|2",
+        );
+        check_op(
+            "----------|------|------|0.004|10|-|6\n\
+            // bxt-rs-split\n\
+            ----------|------|------|0.004|10|-|2",
+            Operation::SetFrameCount {
+                bulk_idx: 0,
+                from: 6,
+                to: 10,
+            },
+            "----------|------|------|0.004|10|-|10\n\
+            // bxt-rs-split\n\
+            ----------|------|------|0.004|10|-|2",
+        );
+        check_op(
+            "----------|------|------|0.004|10|-|6\n\
+            // bxt-rs-split\n\
+            ----------|------|------|0.004|10|-|2",
+            Operation::Replace {
+                line_idx: 0,
+                from: "----------|------|------|0.004|10|-|6".to_string(),
+                to: "s03lj-----|------|------|0.001|15|10|2".to_string(),
+            },
+            "s03lj-----|------|------|0.001|15|10|2\n\
+            // bxt-rs-split\n\
+            ----------|------|------|0.004|10|-|2",
+        );
+    }
+
     #[test]
     fn op_delete() {
         check_op(
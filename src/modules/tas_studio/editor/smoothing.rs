@@ -0,0 +1,525 @@
+//! View angle smoothing.
+
+use std::f32::consts::PI;
+use std::iter::{self, zip};
+use std::ops::ControlFlow;
+
+/// Kind of smoothing to apply.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SmoothingKind {
+    /// Average over a window with a boosted small window in the middle, see [`smoothed_views`].
+    #[default]
+    Windowed,
+    /// Gaussian-weighted average, see [`gaussian_smoothed_views`].
+    Gaussian {
+        /// Standard deviation of the Gaussian in seconds.
+        sigma_s: f32,
+    },
+}
+
+impl SmoothingKind {
+    /// Smooths view angles in radians with this kind of smoothing.
+    ///
+    /// The window parameters are used only by [`SmoothingKind::Windowed`].
+    pub fn smoothed_views(
+        self,
+        window_size: f32,
+        small_window_size: f32,
+        small_window_multiplier: f32,
+        frame_times: &[f32],
+        angles: &[f32],
+    ) -> Vec<f32> {
+        match self {
+            SmoothingKind::Windowed => smoothed_views(
+                window_size,
+                small_window_size,
+                small_window_multiplier,
+                frame_times,
+                angles,
+            ),
+            SmoothingKind::Gaussian { sigma_s } => {
+                gaussian_smoothed_views(sigma_s, frame_times, angles)
+            }
+        }
+    }
+}
+
+/// Unwraps angles in radians, removing jumps of more than π between consecutive angles.
+pub fn unwrap_angles(xs: impl Iterator<Item = f32>) -> impl Iterator<Item = f32> {
+    xs.scan((0., 0.), |(prev, offset), curr| {
+        let mut diff = curr - *prev + *offset;
+        while diff >= PI {
+            diff -= 2. * PI;
+            *offset -= 2. * PI;
+        }
+        while diff <= -PI {
+            diff += 2. * PI;
+            *offset += 2. * PI;
+        }
+
+        *prev += diff;
+        Some(*prev)
+    })
+}
+
+/// Smooths view angles in radians over time.
+///
+/// `frame_times[i]` is the duration of the frame with the view angle `angles[i]`. Every angle is
+/// replaced with the weighted average of the angles within `window_size` seconds centered at it.
+/// Angles within the central `small_window_size` seconds have their weight multiplied by
+/// `small_window_multiplier`. The angles are unwrapped before smoothing, so the result may fall
+/// outside of `[-π; π]`.
+///
+/// # Panics
+///
+/// Panics if `frame_times` and `angles` have different lengths.
+pub fn smoothed_views(
+    window_size: f32,
+    small_window_size: f32,
+    small_window_multiplier: f32,
+    frame_times: &[f32],
+    angles: &[f32],
+) -> Vec<f32> {
+    assert_eq!(frame_times.len(), angles.len());
+
+    if frame_times.is_empty() {
+        return vec![];
+    }
+
+    let unwrapped: Vec<f32> = unwrap_angles(angles.iter().copied()).collect();
+    let mut rv = Vec::with_capacity(unwrapped.len());
+
+    let repeat_first = iter::repeat((frame_times[0], unwrapped[0]));
+    let repeat_last = iter::repeat((*frame_times.last().unwrap(), *unwrapped.last().unwrap()));
+
+    // The smoothing window is centered at the center of each yaw.
+    // For pitch smoothing, every frame has both pitch and yaw so iterate over this is ok.
+    for i in 0..unwrapped.len() {
+        let mut total_view = 0.;
+        let mut total_weight = 0.;
+
+        let mut process_frame =
+            |(mut rem_win_size, mut rem_small_win_size), (mut frame_time, view): (f32, f32)| {
+                // If there's any small window zone left to cover, do so.
+                if rem_small_win_size > 0. {
+                    let dt = frame_time.min(rem_small_win_size);
+                    let weight = dt * small_window_multiplier;
+
+                    total_view += view * weight;
+                    total_weight += weight;
+
+                    rem_win_size -= dt;
+                    rem_small_win_size -= dt;
+                    frame_time -= dt;
+                }
+
+                if frame_time <= 0. {
+                    // Ran out of frame time in the branch above (entire frame was covered by the
+                    // small window).
+                    return ControlFlow::Continue((rem_win_size, rem_small_win_size));
+                }
+
+                if rem_win_size <= 0. {
+                    // Ran out of smoothing window, break.
+                    return ControlFlow::Break(());
+                }
+
+                // If there's any regular window zone left to cover, do so.
+                let dt = frame_time.min(rem_win_size);
+                let weight = dt;
+
+                total_view += view * weight;
+                total_weight += weight;
+
+                rem_win_size -= dt;
+                // No need to decrease rem_small_win_size as it is already == 0 here.
+
+                if rem_win_size <= 0. {
+                    // Ran out of smoothing window, break.
+                    ControlFlow::Break(())
+                } else {
+                    // Still have smoothing window remaining, continue.
+                    ControlFlow::Continue((rem_win_size, rem_small_win_size))
+                }
+            };
+
+        let rem_win_size = window_size / 2.;
+        let rem_small_win_size = small_window_size / 2.;
+
+        // Start from the middle frame.
+        let middle_frame_half = iter::once((frame_times[i] / 2., unwrapped[i]));
+
+        // Walk back half an interval.
+        middle_frame_half
+            .clone()
+            .chain(
+                zip(
+                    frame_times[..i].iter().copied(),
+                    unwrapped[..i].iter().copied(),
+                )
+                .rev(),
+            )
+            .chain(repeat_first.clone())
+            .try_fold((rem_win_size, rem_small_win_size), &mut process_frame);
+
+        // Walk forward half an interval.
+        middle_frame_half
+            .chain(zip(
+                frame_times[i + 1..].iter().copied(),
+                unwrapped[i + 1..].iter().copied(),
+            ))
+            .chain(repeat_last.clone())
+            .try_fold((rem_win_size, rem_small_win_size), &mut process_frame);
+
+        rv.push(total_view / total_weight);
+    }
+
+    rv
+}
+
+/// Smooths view angles in radians over time with a Gaussian.
+///
+/// `frame_times[i]` is the duration of the frame with the view angle `angles[i]`. Every angle is
+/// replaced with the average of all angles weighted by their frame time and by a Gaussian with the
+/// standard deviation of `sigma_s` seconds of the time distance to it. Same as in
+/// [`smoothed_views`], the first and the last angles are considered to continue infinitely, and
+/// the angles are unwrapped before smoothing.
+///
+/// # Panics
+///
+/// Panics if `frame_times` and `angles` have different lengths.
+pub fn gaussian_smoothed_views(sigma_s: f32, frame_times: &[f32], angles: &[f32]) -> Vec<f32> {
+    assert_eq!(frame_times.len(), angles.len());
+
+    let unwrapped: Vec<f32> = unwrap_angles(angles.iter().copied()).collect();
+    if unwrapped.is_empty() || sigma_s <= 0. {
+        return unwrapped;
+    }
+
+    // Frames further away than this have a negligible weight.
+    let cutoff = 4. * sigma_s;
+    let weight =
+        |distance: f32, frame_time: f32| (-0.5 * (distance / sigma_s).powi(2)).exp() * frame_time;
+
+    // Frames without duration would never reach the cutoff, so don't repeat them.
+    let repeat = |frame_time: f32, view: f32| {
+        iter::repeat((frame_time, view)).take(if frame_time > 0. { usize::MAX } else { 0 })
+    };
+    let repeat_first = repeat(frame_times[0], unwrapped[0]);
+    let repeat_last = repeat(*frame_times.last().unwrap(), *unwrapped.last().unwrap());
+
+    let mut rv = Vec::with_capacity(unwrapped.len());
+    for i in 0..unwrapped.len() {
+        let center_weight = weight(0., frame_times[i]);
+        let mut total_view = unwrapped[i] * center_weight;
+        let mut total_weight = center_weight;
+
+        // The accumulator is the distance from the center of the middle frame to the start of the
+        // current frame.
+        let mut process_frame = |distance: f32, (frame_time, view): (f32, f32)| {
+            let frame_center = distance + frame_time / 2.;
+            if frame_center > cutoff {
+                return ControlFlow::Break(());
+            }
+
+            let weight = weight(frame_center, frame_time);
+            total_view += view * weight;
+            total_weight += weight;
+
+            ControlFlow::Continue(distance + frame_time)
+        };
+
+        // Walk back.
+        let _ = zip(
+            frame_times[..i].iter().copied(),
+            unwrapped[..i].iter().copied(),
+        )
+        .rev()
+        .chain(repeat_first.clone())
+        .try_fold(frame_times[i] / 2., &mut process_frame);
+
+        // Walk forward.
+        let _ = zip(
+            frame_times[i + 1..].iter().copied(),
+            unwrapped[i + 1..].iter().copied(),
+        )
+        .chain(repeat_last.clone())
+        .try_fold(frame_times[i] / 2., &mut process_frame);
+
+        rv.push(if total_weight > 0. {
+            total_view / total_weight
+        } else {
+            unwrapped[i]
+        });
+    }
+
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use itertools::Itertools;
+
+    use super::*;
+
+    fn check_unwrap_angles(input: impl IntoIterator<Item = f32>, expect: Expect) {
+        let radians = input.into_iter().map(|x| x.to_radians());
+        let unwrapped: Vec<f32> = unwrap_angles(radians)
+            .map(|x| x.to_degrees().round())
+            .collect();
+        expect.assert_debug_eq(&unwrapped);
+    }
+
+    #[test]
+    fn test_unwrap_angles_idempotent() {
+        check_unwrap_angles(
+            [0., 1., 2., 3.],
+            expect![[r#"
+            [
+                0.0,
+                1.0,
+                2.0,
+                3.0,
+            ]
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_unwrap_angles() {
+        check_unwrap_angles(
+            [0., 170., -170., 160., -160., -165.],
+            expect![[r#"
+                [
+                    0.0,
+                    170.0,
+                    190.0,
+                    160.0,
+                    200.0,
+                    195.0,
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_unwrap_angles_multiple_revolutions() {
+        check_unwrap_angles(
+            [
+                0., 120., -120., 0., 120., -120., 0., 120., -120., 120., 0., -120., 120., 0., -120.,
+            ],
+            expect![[r#"
+                [
+                    0.0,
+                    120.0,
+                    240.0,
+                    360.0,
+                    480.0,
+                    600.0,
+                    720.0,
+                    840.0,
+                    960.0,
+                    840.0,
+                    720.0,
+                    600.0,
+                    480.0,
+                    360.0,
+                    240.0,
+                ]
+            "#]],
+        );
+    }
+
+    fn check_smoothing(
+        input: impl IntoIterator<Item = (f32, f32)>,
+        small_window_size: f32,
+        expect: Expect,
+    ) {
+        let (frame_times, yaws): (Vec<f32>, Vec<f32>) = input.into_iter().unzip();
+        let smoothed = smoothed_views(1., small_window_size, 4., &frame_times, &yaws);
+        expect.assert_debug_eq(&smoothed);
+    }
+
+    #[test]
+    fn test_smoothing_on_small_input() {
+        check_smoothing(
+            [(0.1, 2.), (0.1, 2.), (0.1, 2.)],
+            0.5,
+            expect![[r#"
+            [
+                2.0,
+                2.0,
+                2.0,
+            ]
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_smoothing_no_small_window() {
+        check_smoothing(
+            [
+                (0.25, -1.),
+                (0.25, -1.),
+                (0.5, 1.),
+                (0.25, -1.),
+                (0.25, -1.),
+            ],
+            0.,
+            expect![[r#"
+                [
+                    -0.75,
+                    -0.25,
+                    0.0,
+                    -0.25,
+                    -0.75,
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_smoothing_only_small_window() {
+        check_smoothing(
+            [
+                (0.25, -1.),
+                (0.25, -1.),
+                (0.5, 1.),
+                (0.25, -1.),
+                (0.25, -1.),
+            ],
+            1.,
+            expect![[r#"
+                [
+                    -0.75,
+                    -0.25,
+                    0.0,
+                    -0.25,
+                    -0.75,
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_smoothing() {
+        check_smoothing(
+            [
+                (0.25, -1.),
+                (0.25, -1.),
+                (0.5, 1.),
+                (0.25, -1.),
+                (0.25, -1.),
+            ],
+            0.5,
+            expect![[r#"
+                [
+                    -0.9,
+                    -0.4,
+                    0.6,
+                    -0.4,
+                    -0.9,
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_smoothing_even() {
+        check_smoothing(
+            [
+                (0.25, -1.),
+                (0.25, -1.),
+                (0.25, 1.),
+                (0.25, 1.),
+                (0.25, -1.),
+                (0.25, -1.),
+            ],
+            0.5,
+            expect![[r#"
+                [
+                    -0.9,
+                    -0.4,
+                    0.3,
+                    0.3,
+                    -0.4,
+                    -0.9,
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_smoothing_partial_small() {
+        check_smoothing(
+            [
+                (0.25, -1.),
+                (0.25, -1.),
+                (0.4, 1.),
+                (0.25, -1.),
+                (0.25, -1.),
+            ],
+            0.5,
+            expect![[r#"
+                [
+                    -0.9,
+                    -0.4,
+                    0.28000003,
+                    -0.4,
+                    -0.9,
+                ]
+            "#]],
+        );
+    }
+
+    /// Returns frame times and angles of a step from 0 to 1 in the middle.
+    fn step_input() -> (Vec<f32>, Vec<f32>) {
+        let frame_times = vec![0.01; 20];
+        let angles = (0..20).map(|i| if i < 10 { 0. } else { 1. }).collect();
+        (frame_times, angles)
+    }
+
+    #[test]
+    fn test_gaussian_smoothing_on_small_input() {
+        let smoothed = gaussian_smoothed_views(0.5, &[0.1, 0.1, 0.1], &[2., 2., 2.]);
+        for angle in smoothed {
+            assert!((angle - 2.).abs() < 1e-5, "{angle}");
+        }
+    }
+
+    #[test]
+    fn test_gaussian_smoothing_step_is_monotonic() {
+        let (frame_times, angles) = step_input();
+        let smoothed = gaussian_smoothed_views(0.03, &frame_times, &angles);
+
+        for (a, b) in smoothed.iter().tuple_windows() {
+            assert!(a < b, "{smoothed:?}");
+        }
+
+        // The step is in the middle, so the result is symmetric.
+        for (a, b) in zip(&smoothed, smoothed.iter().rev()) {
+            assert!((a + b - 1.).abs() < 1e-5, "{smoothed:?}");
+        }
+    }
+
+    #[test]
+    fn test_gaussian_smoothing_vs_windowed_on_step() {
+        let (frame_times, angles) = step_input();
+        let windowed = smoothed_views(0.15, 0., 1., &frame_times, &angles);
+        let gaussian = gaussian_smoothed_views(0.03, &frame_times, &angles);
+
+        // The windowed smoothing has a corner where the window reaches the step: frames further
+        // than half a window from the step are left as is.
+        assert_eq!(windowed[1], 0.);
+        assert_eq!(windowed[18], 1.);
+
+        // The Gaussian smoothing transitions smoothly instead.
+        assert!(gaussian[1] > 0.);
+        assert!(gaussian[18] < 1.);
+
+        // Both agree in the middle of the transition.
+        assert!((windowed[9] + windowed[10] - 1.).abs() < 1e-5);
+        assert!((gaussian[9] + gaussian[10] - 1.).abs() < 1e-5);
+    }
+}
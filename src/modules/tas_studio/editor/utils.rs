@@ -206,6 +206,30 @@ pub fn bulk_idx_and_is_last(
     })
 }
 
+/// Normalizes the yaw in degrees into the [-180, 180) range.
+pub fn normalize_yaw(yaw: f32) -> f32 {
+    let yaw = (yaw + 180.).rem_euclid(360.) - 180.;
+
+    // rem_euclid() rounds up to 360 for tiny negative values.
+    if yaw == 180. {
+        -180.
+    } else {
+        yaw
+    }
+}
+
+/// Returns the total number of frames in the lines.
+///
+/// The result saturates at `usize::MAX` rather than overflowing.
+pub fn lines_frame_count(lines: &[Line]) -> usize {
+    lines
+        .iter()
+        .filter_map(Line::frame_bulk)
+        .fold(0usize, |count, bulk| {
+            count.saturating_add(bulk.frame_count.get() as usize)
+        })
+}
+
 /// Returns reference to frame bulk and index of first frame simulated by it.
 ///
 /// The index starts at `1` because the very first frame is always the initial frame, which is not
@@ -213,7 +237,7 @@ pub fn bulk_idx_and_is_last(
 pub fn bulk_and_first_frame_idx(hltas: &HLTAS) -> impl Iterator<Item = (&FrameBulk, usize)> {
     hltas.frame_bulks().scan(1, |frame_idx, bulk| {
         let first_frame_idx = *frame_idx;
-        *frame_idx += bulk.frame_count.get() as usize;
+        *frame_idx = frame_idx.saturating_add(bulk.frame_count.get() as usize);
         Some((bulk, first_frame_idx))
     })
 }
@@ -227,7 +251,7 @@ pub fn bulk_and_first_frame_idx_mut(
 ) -> impl Iterator<Item = (&mut FrameBulk, usize)> {
     hltas.frame_bulks_mut().scan(1, |frame_idx, bulk| {
         let first_frame_idx = *frame_idx;
-        *frame_idx += bulk.frame_count.get() as usize;
+        *frame_idx = frame_idx.saturating_add(bulk.frame_count.get() as usize);
         Some((bulk, first_frame_idx))
     })
 }
@@ -241,7 +265,7 @@ pub fn line_first_frame_idx(hltas: &HLTAS) -> impl Iterator<Item = usize> + '_ {
         let first_frame_idx = *frame_idx;
 
         if let Some(bulk) = line.frame_bulk() {
-            *frame_idx += bulk.frame_count.get() as usize;
+            *frame_idx = frame_idx.saturating_add(bulk.frame_count.get() as usize);
         }
 
         Some(first_frame_idx)
@@ -261,7 +285,7 @@ pub fn line_first_frame_idx_and_frame_count(hltas: &HLTAS) -> impl Iterator<Item
         let first_frame_idx = *frame_idx;
 
         if let Some(bulk) = line.frame_bulk() {
-            *frame_idx += bulk.frame_count.get() as usize;
+            *frame_idx = frame_idx.saturating_add(bulk.frame_count.get() as usize);
         }
 
         Some(first_frame_idx)
@@ -280,6 +304,32 @@ pub fn line_idx_and_repeat_at_frame(lines: &[Line], frame_idx: usize) -> Option<
         .nth(frame_idx)
 }
 
+/// Returns the index of the line at `frame_idx` and, if `frame_idx` is in the middle of a frame
+/// bulk, the two halves of that frame bulk split at `frame_idx`.
+///
+/// A line inserted before the returned index, or between the two halves replacing the frame bulk,
+/// starts applying exactly at `frame_idx`.
+pub fn split_line_at_frame(
+    lines: &[Line],
+    frame_idx: usize,
+) -> Option<(usize, Option<(Line, Line)>)> {
+    let (line_idx, repeat) = line_idx_and_repeat_at_frame(lines, frame_idx)?;
+    if repeat == 0 {
+        return Some((line_idx, None));
+    }
+
+    let mut line = lines[line_idx].clone();
+    let mut new_line = line.clone();
+
+    let bulk = line.frame_bulk_mut().unwrap();
+    let new_bulk = new_line.frame_bulk_mut().unwrap();
+
+    bulk.frame_count = NonZeroU32::new(repeat).unwrap();
+    new_bulk.frame_count = NonZeroU32::new(new_bulk.frame_count.get() - repeat).unwrap();
+
+    Some((line_idx, Some((line, new_line))))
+}
+
 pub fn bulk_idx_and_repeat_at_frame(hltas: &HLTAS, frame_idx: usize) -> Option<(usize, u32)> {
     hltas
         .frame_bulks()
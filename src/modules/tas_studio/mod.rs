@@ -9,8 +9,8 @@ use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
-use bxt_ipc_types::Frame;
-use bxt_strafe::{Parameters, TraceResult};
+use bxt_ipc_types::{Frame, GameResponse};
+use bxt_strafe::{Hull, Parameters, Trace, TraceResult};
 use color_eyre::eyre::{self, eyre, Context};
 use glam::{IVec2, IVec4, Vec2, Vec3};
 use hltas::types::{
@@ -20,10 +20,11 @@ use hltas::types::{
 use hltas::HLTAS;
 
 use self::editor::operation::Key;
+use self::editor::smoothing::SmoothingKind;
 use self::editor::toggle_auto_action::ToggleAutoActionTarget;
 use self::editor::utils::{bulk_and_first_frame_idx, FrameBulkExt};
 use self::editor::{Callbacks, KeyboardState};
-use self::remote::{AccurateFrame, PlayRequest};
+use self::remote::{AccurateFrame, ClientMessage, PlayRequest};
 use super::commands::{Command, Commands};
 use super::cvars::CVar;
 use super::hud::Hud;
@@ -38,7 +39,9 @@ use crate::handler;
 use crate::hooks::bxt::{OnTasPlaybackFrameData, BXT_IS_TAS_EDITOR_ACTIVE};
 use crate::hooks::engine::con_print;
 use crate::hooks::{bxt, client, engine, sdl};
-use crate::modules::tas_studio::editor::{CameraViewAdjustmentMode, MaxAccelYawOffsetMode};
+use crate::modules::tas_studio::editor::{
+    CameraViewAdjustmentMode, MaxAccelYawOffsetMode, SmoothedViewMode,
+};
 use crate::utils::*;
 
 pub struct TasStudio;
@@ -57,10 +60,21 @@ impl Module for TasStudio {
             &BXT_TAS_STUDIO_CAMERA_EDITOR,
             &BXT_TAS_STUDIO_AUTO_SMOOTHING,
             &BXT_TAS_STUDIO_SHOW_PLAYER_BBOX,
+            &BXT_TAS_STUDIO_SHOW_VELOCITY_VECTORS,
+            &BXT_TAS_STUDIO_COLOR_BY_STRAFE_ANGLE_ERROR,
+            &BXT_TAS_STUDIO_HIGHLIGHT_STEEP_SLOPES,
+            &BXT_TAS_STUDIO_MAX_DRAWN_FRAMES,
+            &BXT_TAS_STUDIO_FAST_PREVIEW,
+            &BXT_TAS_STUDIO_MAX_TRACE_DISTANCE,
+            &BXT_TAS_STUDIO_MAX_FRAME_COUNT,
+            &BXT_TAS_STUDIO_SELECT_AFTER_DELETE,
             &BXT_TAS_STUDIO_SMOOTH_WINDOW_S,
             &BXT_TAS_STUDIO_SMOOTH_SMALL_WINDOW_S,
             &BXT_TAS_STUDIO_SMOOTH_SMALL_WINDOW_MULTIPLIER,
+            &BXT_TAS_STUDIO_SMOOTH_KIND,
+            &BXT_TAS_STUDIO_SMOOTH_GAUSSIAN_SIGMA_S,
             &BXT_TAS_STUDIO_LINE_WIDTH,
+            &BXT_TAS_STUDIO_LINE_QUADS,
         ];
         CVARS
     }
@@ -69,40 +83,86 @@ impl Module for TasStudio {
         static COMMANDS: &[&Command] = &[
             &BXT_TAS_STUDIO_CONVERT_HLTAS_FROM_BXT_TAS_NEW,
             &BXT_TAS_STUDIO_SMOOTH_GLOBALLY,
+            &BXT_TAS_STUDIO_SMOOTHED_SCRATCH_SET_YAW,
+            &BXT_TAS_STUDIO_SMOOTHED_SCRATCH_CLEAR,
             &BXT_TAS_STUDIO_NEW,
             &BXT_TAS_STUDIO_LOAD,
+            &BXT_TAS_STUDIO_CHECK_DB,
             &BXT_TAS_STUDIO_CONVERT_HLTAS,
+            &BXT_TAS_STUDIO_EXPORT_PATH_OBJ,
+            &BXT_TAS_STUDIO_EXPORT,
+            &BXT_TAS_STUDIO_FIRST_FRAME_LEAVING_BOX,
+            &BXT_TAS_STUDIO_RECENTER_VIEW,
             &BXT_TAS_STUDIO_REPLAY_VIEWS,
             &BXT_TAS_STUDIO_REPLAY,
             &BXT_TAS_STUDIO_SET_STOP_FRAME,
+            &BXT_TAS_STUDIO_STOP_FRAME_OFFSET,
+            &BXT_TAS_STUDIO_NOREFRESH_UNTIL_HOVERED,
             &BXT_TAS_STUDIO_SET_YAWSPEED,
             &BXT_TAS_STUDIO_SET_PITCH,
             &BXT_TAS_STUDIO_SET_YAW,
+            &BXT_TAS_STUDIO_SET_FRAME_COUNT,
             &BXT_TAS_STUDIO_SET_FRAME_TIME,
+            &BXT_TAS_STUDIO_SET_FRAME_TIME_PRESERVING_DURATION,
+            &BXT_TAS_STUDIO_RETIME_RANGE,
             &BXT_TAS_STUDIO_SET_COMMANDS,
+            &BXT_TAS_STUDIO_INSERT_COMMAND,
+            &BXT_TAS_STUDIO_COPY_BULK,
+            &BXT_TAS_STUDIO_PASTE_BULK,
+            &BXT_TAS_STUDIO_SET_PARAMETER_OVERRIDE,
+            &BXT_TAS_STUDIO_CLEAR_PARAMETER_OVERRIDES,
             &BXT_TAS_STUDIO_UNSET_PITCH,
             &BXT_TAS_STUDIO_UNSET_YAW,
+            &BXT_TAS_STUDIO_SNAP_YAW,
             &BXT_TAS_STUDIO_SELECT_NEXT,
             &BXT_TAS_STUDIO_SELECT_PREV,
+            &BXT_TAS_STUDIO_SELECT_COLLIDED,
+            &BXT_TAS_STUDIO_SIM_STEP,
+            &BXT_TAS_STUDIO_PARAM_SENSITIVITY,
+            &BXT_TAS_STUDIO_TRACE_CROSSHAIR,
             &BXT_TAS_STUDIO_SPLIT,
             &BXT_TAS_STUDIO_DELETE,
             &BXT_TAS_STUDIO_DELETE_LAST,
+            &BXT_TAS_STUDIO_DUPLICATE,
+            &BXT_TAS_STUDIO_MIRROR,
+            &BXT_TAS_STUDIO_REVERSE,
             &BXT_TAS_STUDIO_TOGGLE,
+            &BXT_TAS_STUDIO_TOGGLE_DBC,
+            &BXT_TAS_STUDIO_AUTOJUMP_RANGE,
+            &BXT_TAS_STUDIO_FLATTEN_YAWS_IN_RANGE,
             &BXT_TAS_STUDIO_HIDE,
+            &BXT_TAS_STUDIO_HIDE_BEFORE,
+            &BXT_TAS_STUDIO_SET_CAMERA_FRAME_RANGE,
+            &BXT_TAS_STUDIO_CLEAR_CAMERA_FRAME_RANGE,
             &BXT_TAS_STUDIO_SMOOTH,
+            &BXT_TAS_STUDIO_SMOOTH_RANGE,
+            &BXT_TAS_STUDIO_ADD_YAW_OFFSET_CHANGE,
+            &BXT_TAS_STUDIO_SMOOTH_WINDOW_FROM_SEGMENT,
+            &BXT_TAS_STUDIO_TOGGLE_SMOOTHED_VIEW,
             &BXT_TAS_STUDIO_BRANCH_CLONE,
+            &BXT_TAS_STUDIO_EXTRACT_BRANCH,
             &BXT_TAS_STUDIO_BRANCH_FOCUS_ID,
+            &BXT_TAS_STUDIO_BRANCH_FOCUS_NAME,
+            &BXT_TAS_STUDIO_BRANCH_LIST,
             &BXT_TAS_STUDIO_BRANCH_FOCUS_NEXT,
             &BXT_TAS_STUDIO_BRANCH_HIDE_ID,
             &BXT_TAS_STUDIO_BRANCH_HIDE_AND_FOCUS_NEXT,
             &BXT_TAS_STUDIO_BRANCH_SHOW_ID,
+            &BXT_TAS_STUDIO_BRANCH_COLOR,
+            &BXT_TAS_STUDIO_BRANCH_RENAME,
             &BXT_TAS_STUDIO_UNDO,
             &BXT_TAS_STUDIO_REDO,
+            &BXT_TAS_STUDIO_UNDO_ALL,
+            &BXT_TAS_STUDIO_REDO_ALL,
             &BXT_TAS_STUDIO_CLOSE,
             &BXT_TAS_STUDIO_OPTIM_INIT,
             &BXT_TAS_STUDIO_OPTIM_APPLY,
             &PLUS_BXT_TAS_STUDIO_INSERT_CAMERA_LINE,
             &MINUS_BXT_TAS_STUDIO_INSERT_CAMERA_LINE,
+            &PLUS_BXT_TAS_STUDIO_EXTEND_SELECTION,
+            &MINUS_BXT_TAS_STUDIO_EXTEND_SELECTION,
+            &PLUS_BXT_TAS_STUDIO_ADJUST_FRAME_TIME,
+            &MINUS_BXT_TAS_STUDIO_ADJUST_FRAME_TIME,
             &PLUS_BXT_TAS_STUDIO_LOOK_AROUND,
             &MINUS_BXT_TAS_STUDIO_LOOK_AROUND,
         ];
@@ -148,6 +208,15 @@ use hltas_bridge::Bridge;
 static ENABLE_FREECAM_ON_CALCREFDEF: MainThreadCell<bool> = MainThreadCell::new(false);
 static LAST_BUTTONS: MainThreadCell<Buttons> = MainThreadCell::new(Buttons::empty());
 static INSERT_CAMERA_LINE_DOWN: MainThreadCell<bool> = MainThreadCell::new(false);
+static EXTEND_SELECTION_DOWN: MainThreadCell<bool> = MainThreadCell::new(false);
+static ADJUST_FRAME_TIME_DOWN: MainThreadCell<bool> = MainThreadCell::new(false);
+static SELECT_COLLIDED_REQUESTED: MainThreadCell<bool> = MainThreadCell::new(false);
+static SIM_STEP_REQUESTED: MainThreadCell<bool> = MainThreadCell::new(false);
+static TRACE_CROSSHAIR_REQUESTED: MainThreadCell<bool> = MainThreadCell::new(false);
+static PARAM_SENSITIVITY_REQUESTED: MainThreadRefCell<Option<(String, f32)>> =
+    MainThreadRefCell::new(None);
+static SMOOTHED_VIEW_MODE: MainThreadCell<SmoothedViewMode> =
+    MainThreadCell::new(SmoothedViewMode::Both);
 
 static BXT_HUD_TAS_STUDIO: CVar = CVar::new(
     b"bxt_hud_tas_studio\0",
@@ -185,6 +254,76 @@ static BXT_TAS_STUDIO_SHOW_PLAYER_BBOX: CVar = CVar::new(
 Whether to show the player bbox for the frame under the cursor.",
 );
 
+static BXT_TAS_STUDIO_SHOW_VELOCITY_VECTORS: CVar = CVar::new(
+    b"bxt_tas_studio_show_velocity_vectors\0",
+    b"0\0",
+    "\
+Whether to show arrows in the direction of the player velocity along the path. The arrows are \
+drawn next to the camera angle lines, and their length grows with the speed.",
+);
+
+static BXT_TAS_STUDIO_COLOR_BY_STRAFE_ANGLE_ERROR: CVar = CVar::new(
+    b"bxt_tas_studio_color_by_strafe_angle_error\0",
+    b"0\0",
+    "\
+Whether to color the path by the error between the requested strafing angle and the angle achieved \
+with the vectorial compensation table. Frames with small errors are green, and frames with errors \
+of 0.05 degrees or more are yellow.",
+);
+
+static BXT_TAS_STUDIO_HIGHLIGHT_STEEP_SLOPES: CVar = CVar::new(
+    b"bxt_tas_studio_highlight_steep_slopes\0",
+    b"0\0",
+    "\
+Whether to color the path blue on frames where the player is on a steep but walkable slope, which \
+affects the movement speed.",
+);
+
+static BXT_TAS_STUDIO_MAX_FRAME_COUNT: CVar = CVar::new(
+    b"bxt_tas_studio_max_frame_count\0",
+    b"10000000\0",
+    "\
+Maximum total number of frames in the script. Setting frame counts, retiming and duplicating frame \
+bulks are refused if they would make the script longer. Other operations don't check the limit. \
+This protects against running out of memory due to huge frame counts.",
+);
+
+static BXT_TAS_STUDIO_FAST_PREVIEW: CVar = CVar::new(
+    b"bxt_tas_studio_fast_preview\0",
+    b"0\0",
+    "\
+Whether to predict the path with a fast approximate collision while adjusting frame bulks with the \
+mouse. The approximate path only collides with a flat ground plane under the player, so it ignores \
+walls, slopes and stairs. The path is predicted accurately again once the adjustment is done.",
+);
+
+static BXT_TAS_STUDIO_MAX_TRACE_DISTANCE: CVar = CVar::new(
+    b"bxt_tas_studio_max_trace_distance\0",
+    b"0\0",
+    "\
+Maximum distance in units of a single collision trace when predicting the path. Longer movements \
+are traced in several shorter steps, which prevents the predicted path from passing through thin \
+walls at very high speeds, at the cost of slower prediction. Set to 0 to trace every movement at \
+once.",
+);
+
+static BXT_TAS_STUDIO_MAX_DRAWN_FRAMES: CVar = CVar::new(
+    b"bxt_tas_studio_max_drawn_frames\0",
+    b"0\0",
+    "\
+Maximum number of frames to draw around the frame under the cursor. Only the drawn frames can be \
+hovered. Set to a few thousand to improve the frame rate on huge scripts. Set to 0 to draw all \
+frames.",
+);
+
+static BXT_TAS_STUDIO_SELECT_AFTER_DELETE: CVar = CVar::new(
+    b"bxt_tas_studio_select_after_delete\0",
+    b"1\0",
+    "\
+Whether to select the next frame bulk after deleting the selected one. When disabled, the \
+selection is cleared instead.",
+);
+
 static BXT_TAS_STUDIO_SMOOTH_WINDOW_S: CVar = CVar::new(
     b"_bxt_tas_studio_smooth_window_s\0",
     b"0.15\0",
@@ -217,6 +356,28 @@ console variable defines how much stronger the influence of the camera angles in
 is compared to the big window.",
 );
 
+static BXT_TAS_STUDIO_SMOOTH_KIND: CVar = CVar::new(
+    b"_bxt_tas_studio_smooth_kind\0",
+    b"0\0",
+    "\
+Kind of smoothing to apply.
+
+- 0: average over a window with a smaller window in the middle having a higher contribution, see \
+`_bxt_tas_studio_smooth_window_s`.
+- 1: Gaussian-weighted average, see `_bxt_tas_studio_smooth_gaussian_sigma_s`. Has no visible \
+corners at the window edges.",
+);
+
+static BXT_TAS_STUDIO_SMOOTH_GAUSSIAN_SIGMA_S: CVar = CVar::new(
+    b"_bxt_tas_studio_smooth_gaussian_sigma_s\0",
+    b"0.04\0",
+    "\
+Standard deviation of the Gaussian in seconds for `_bxt_tas_studio_smooth_kind 1`.
+
+Camera angles further than four standard deviations away have no influence on the smoothed camera \
+angle.",
+);
+
 static BXT_TAS_STUDIO_LINE_WIDTH: CVar = CVar::new(
     b"bxt_tas_studio_line_width\0",
     b"2\0",
@@ -224,6 +385,15 @@ static BXT_TAS_STUDIO_LINE_WIDTH: CVar = CVar::new(
 The line width used for TAS editor drawing, in pixels.",
 );
 
+static BXT_TAS_STUDIO_LINE_QUADS: CVar = CVar::new(
+    b"bxt_tas_studio_line_quads\0",
+    b"0\0",
+    "\
+Set to `1` to draw the TAS editor lines as camera-facing quads instead of OpenGL lines. This \
+gives the same width from bxt_tas_studio_line_width on all graphics drivers, some of which don't \
+support wide lines.",
+);
+
 static BXT_TAS_STUDIO_NEW: Command = Command::new(
     b"bxt_tas_studio_new\0",
     handler!(
@@ -271,35 +441,40 @@ fn new(marker: MainThreadMarker, filename: String, command: String, fps: i32) {
         return;
     }
 
-    let frame_time = match fps {
-        1000 => "0.001",
-        500 => "0.002",
-        250 => "0.004",
-        100 => "0.010000001",
-        _ => {
+    let Some(frame_time) = frame_time_for_fps(fps) else {
+        con_print(
+            marker,
+            "You specified FPS = %d, however only FPS = 1000, 500, 250 or 100 are \
+             currently supported. If you need another FPS value, use one of the supported \
+             FPS values, and then change the frametime manually in the script",
+        );
+
+        if fps > 0 {
             con_print(
                 marker,
-                "You specified FPS = %d, however only FPS = 1000, 500, 250 or 100 are \
-                 currently supported. If you need another FPS value, use one of the supported \
-                 FPS values, and then change the frametime manually in the script",
+                &format!(" (you will want something around {})", 1. / fps as f32),
             );
-
-            if fps > 0 {
-                con_print(
-                    marker,
-                    &format!(" (you will want something around {})", 1. / fps as f32),
-                );
-            }
-
-            con_print(marker, ".\n");
-            return;
         }
+
+        con_print(marker, ".\n");
+        return;
     };
 
     // TODO: new() should be marked as unsafe because this is not always safe.
     unsafe { bxt::tas_new(marker, filename, command, frame_time.to_owned()) };
 }
 
+/// Returns the frame time string to use for the given FPS, if it is supported.
+fn frame_time_for_fps(fps: i32) -> Option<&'static str> {
+    match fps {
+        1000 => Some("0.001"),
+        500 => Some("0.002"),
+        250 => Some("0.004"),
+        100 => Some("0.010000001"),
+        _ => None,
+    }
+}
+
 static BXT_TAS_STUDIO_LOAD: Command = Command::new(
     b"bxt_tas_studio_load\0",
     handler!(
@@ -319,10 +494,68 @@ fn load(marker: MainThreadMarker, path: PathBuf) {
         }
     };
 
+    // The camera editor mode is driven by the console variable, so restore it there.
+    engine::prepend_command(
+        marker,
+        &format!(
+            "bxt_tas_studio_camera_editor {}\n",
+            u8::from(editor.in_camera_editor())
+        ),
+    );
+
     let bridge = Bridge::with_project_path(&path, editor.script());
     *STATE.borrow_mut(marker) = State::PreparingToPlayToEditor(editor, bridge, false);
 }
 
+static BXT_TAS_STUDIO_CHECK_DB: Command = Command::new(
+    b"bxt_tas_studio_check_db\0",
+    handler!(
+        "bxt_tas_studio_check_db <tas.hltasproj>
+
+Checks the undo and redo history of the TAS project and removes entries that cannot be loaded, \
+along with the older entries that depend on them. A backup with the .bak extension is made before \
+opening the project. Use this when the TAS editor warns about skipped history entries. The project \
+must not be open in the TAS editor.",
+        check_db as fn(_, _)
+    ),
+);
+
+fn check_db(marker: MainThreadMarker, path: PathBuf) {
+    if !matches!(*STATE.borrow(marker), State::Idle) {
+        con_print(marker, "Please close the TAS editor first.\n");
+        return;
+    }
+
+    if let Err(err) = check_and_repair_db(marker, &path) {
+        con_print(marker, &format!("Error checking the TAS project: {err}\n"));
+    }
+}
+
+fn check_and_repair_db(marker: MainThreadMarker, path: &Path) -> eyre::Result<()> {
+    // Opening the project can upgrade it, so make the backup before that.
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(".bak");
+    fs::copy(path, &backup_path).context("error making a backup")?;
+
+    let (undo_count, redo_count) = Editor::check_db(path)?;
+    if undo_count == 0 && redo_count == 0 {
+        con_print(marker, "No problems found.\n");
+        return Ok(());
+    }
+
+    let (undo_count, redo_count) = Editor::repair_db(path)?;
+    con_print(
+        marker,
+        &format!(
+            "Removed {undo_count} undo and {redo_count} redo history entries. The original \
+             project was saved to {}.\n",
+            Path::new(&backup_path).display()
+        ),
+    );
+
+    Ok(())
+}
+
 static BXT_TAS_STUDIO_CONVERT_HLTAS: Command = Command::new(
     b"bxt_tas_studio_convert_hltas\0",
     handler!(
@@ -354,6 +587,133 @@ fn convert(marker: MainThreadMarker, path: PathBuf) -> eyre::Result<()> {
     Ok(())
 }
 
+static BXT_TAS_STUDIO_EXPORT_PATH_OBJ: Command = Command::new(
+    b"bxt_tas_studio_export_path_obj\0",
+    handler!(
+        "bxt_tas_studio_export_path_obj <path.obj> [include other branches]
+
+Exports the player path of the current branch as a Wavefront OBJ polyline for use in 3D tools like \
+Blender. If the second argument is 1, other visible branches are exported too, as separate \
+objects.",
+        export_path_obj as fn(_, _),
+        export_path_obj_with_other_branches as fn(_, _, _)
+    ),
+);
+
+fn export_path_obj(marker: MainThreadMarker, path: PathBuf) {
+    export_path_obj_with_other_branches(marker, path, 0);
+}
+
+fn export_path_obj_with_other_branches(
+    marker: MainThreadMarker,
+    path: PathBuf,
+    include_other_branches: i32,
+) {
+    let state = STATE.borrow(marker);
+    let State::Editing { editor, .. } = &*state else {
+        return;
+    };
+
+    let obj = editor.path_obj(include_other_branches != 0);
+    if let Err(err) = fs::write(&path, obj) {
+        con_print(marker, &format!("Error writing the path: {err}\n"));
+    }
+}
+
+static BXT_TAS_STUDIO_EXPORT: Command = Command::new(
+    b"bxt_tas_studio_export\0",
+    handler!(
+        "bxt_tas_studio_export <filename.hltas> [smoothed]
+
+Writes the script of the current branch to a .hltas file. If the second argument is 1, writes the \
+auto-smoothed script instead.",
+        export as fn(_, _),
+        export_smoothed as fn(_, _, _)
+    ),
+);
+
+fn export(marker: MainThreadMarker, path: PathBuf) {
+    export_smoothed(marker, path, 0);
+}
+
+fn export_smoothed(marker: MainThreadMarker, path: PathBuf, smoothed: i32) {
+    let state = STATE.borrow(marker);
+    let State::Editing { editor, .. } = &*state else {
+        return;
+    };
+
+    if let Err(err) = editor.export_hltas_to_path(&path, smoothed != 0) {
+        con_print(marker, &format!("Error exporting the HLTAS: {err}\n"));
+    }
+}
+
+static BXT_TAS_STUDIO_FIRST_FRAME_LEAVING_BOX: Command = Command::new(
+    b"bxt_tas_studio_first_frame_leaving_box\0",
+    handler!(
+        "bxt_tas_studio_first_frame_leaving_box \"<x1> <y1> <z1>\" \"<x2> <y2> <z2>\"
+
+Prints the index of the first frame where the player position is outside of the axis-aligned box \
+with the given opposite corners.",
+        first_frame_leaving_box as fn(_, _, _)
+    ),
+);
+
+fn first_frame_leaving_box(marker: MainThreadMarker, corner1: String, corner2: String) {
+    let state = STATE.borrow(marker);
+    let State::Editing { editor, .. } = &*state else {
+        return;
+    };
+
+    let (Some(corner1), Some(corner2)) = (parse_vec3(&corner1), parse_vec3(&corner2)) else {
+        con_print(
+            marker,
+            "Error: the box corners must be three numbers each, like \"0 0 0\"\n",
+        );
+        return;
+    };
+
+    match editor.first_frame_leaving_box(corner1, corner2) {
+        Some(frame_idx) => con_print(marker, &format!("{frame_idx}\n")),
+        None => con_print(marker, "The player never leaves the box.\n"),
+    }
+}
+
+static BXT_TAS_STUDIO_RECENTER_VIEW: Command = Command::new(
+    b"bxt_tas_studio_recenter_view\0",
+    handler!(
+        "bxt_tas_studio_recenter_view <frame>
+
+Moves the camera back from the player position on the given frame so that it is at the center of \
+the screen, keeping the current view angles. The script is not changed.",
+        recenter_view as fn(_, _)
+    ),
+);
+
+fn recenter_view(marker: MainThreadMarker, frame_idx: usize) {
+    let state = STATE.borrow(marker);
+    let State::Editing { editor, .. } = &*state else {
+        return;
+    };
+
+    let view_angles = unsafe { get_viewangles(marker) };
+    match editor.recentered_view_origin(frame_idx, view_angles, 200.) {
+        Some(origin) => change_view_origin(marker, origin),
+        None => con_print(marker, "Error: the frame hasn't been simulated yet.\n"),
+    }
+}
+
+/// Parses a vector from three whitespace-separated numbers.
+fn parse_vec3(s: &str) -> Option<Vec3> {
+    let mut iter = s.split_ascii_whitespace().map(str::parse);
+    let (Some(Ok(x)), Some(Ok(y)), Some(Ok(z)), None) =
+        (iter.next(), iter.next(), iter.next(), iter.next())
+    else {
+        return None;
+    };
+
+    Some(Vec3::new(x, y, z))
+}
+
 static BXT_TAS_STUDIO_CONVERT_HLTAS_FROM_BXT_TAS_NEW: Command = Command::new(
     b"_bxt_tas_studio_convert_hltas_from_bxt_tas_new\0",
     handler!(
@@ -548,6 +908,38 @@ fn set_stop_frame_to_hovered(marker: MainThreadMarker) {
     }
 }
 
+static BXT_TAS_STUDIO_NOREFRESH_UNTIL_HOVERED: Command = Command::new(
+    b"bxt_tas_studio_norefresh_until_hovered\0",
+    handler!(
+        "bxt_tas_studio_norefresh_until_hovered
+
+Sets bxt_tas_norefresh_until_last_frames so that norefresh during playback lasts until the frame \
+under the cursor.",
+        norefresh_until_hovered as fn(_)
+    ),
+);
+
+fn norefresh_until_hovered(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    match editor.norefresh_until_last_frames_for_hovered() {
+        Ok(last_frames) => engine::prepend_command(
+            marker,
+            &format!("bxt_tas_norefresh_until_last_frames {last_frames}\n"),
+        ),
+        Err(err) => {
+            con_print(marker, &format!("Error setting norefresh frame: {err}\n"));
+            if err.is_internal() {
+                error!("error setting norefresh frame: {err:?}");
+                *state = State::Idle;
+            }
+        }
+    }
+}
+
 fn set_stop_frame(marker: MainThreadMarker, stop_frame: u32) {
     let mut state = STATE.borrow_mut(marker);
     let State::Editing { editor, .. } = &mut *state else {
@@ -563,6 +955,31 @@ fn set_stop_frame(marker: MainThreadMarker, stop_frame: u32) {
     }
 }
 
+static BXT_TAS_STUDIO_STOP_FRAME_OFFSET: Command = Command::new(
+    b"bxt_tas_studio_stop_frame_offset\0",
+    handler!(
+        "bxt_tas_studio_stop_frame_offset <delta>
+
+Moves the stop frame by the given number of frames, which can be negative.",
+        stop_frame_offset as fn(_, _)
+    ),
+);
+
+fn stop_frame_offset(marker: MainThreadMarker, delta: i64) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.nudge_stop_frame(delta) {
+        con_print(marker, &format!("Error setting stop frame: {err}\n"));
+        if err.is_internal() {
+            error!("error setting stop frame: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
 static BXT_TAS_STUDIO_SET_PITCH: Command = Command::new(
     b"bxt_tas_studio_set_pitch\0",
     handler!(
@@ -663,6 +1080,33 @@ fn unset_yaw(marker: MainThreadMarker) {
     }
 }
 
+static BXT_TAS_STUDIO_SNAP_YAW: Command = Command::new(
+    b"bxt_tas_studio_snap_yaw\0",
+    handler!(
+        "bxt_tas_studio_snap_yaw
+
+Snaps the yaw of the selected frame bulk to the closest angle in the vectorial compensation table.
+
+The table is computed on first use, which takes a few seconds.",
+        snap_yaw as fn(_)
+    ),
+);
+
+fn snap_yaw(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.snap_selected_yaw_to_vct() {
+        con_print(marker, &format!("Error snapping yaw: {err}\n"));
+        if err.is_internal() {
+            error!("error snapping yaw: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
 static BXT_TAS_STUDIO_SET_YAWSPEED: Command = Command::new(
     b"bxt_tas_studio_set_yawspeed\0",
     handler!(
@@ -688,6 +1132,31 @@ fn set_yawspeed(marker: MainThreadMarker, yawspeed: f32) {
     }
 }
 
+static BXT_TAS_STUDIO_SET_FRAME_COUNT: Command = Command::new(
+    b"bxt_tas_studio_set_frame_count\0",
+    handler!(
+        "bxt_tas_studio_set_frame_count <frame count>
+
+Sets the frame count of the selected frame bulk.",
+        set_frame_count as fn(_, _)
+    ),
+);
+
+fn set_frame_count(marker: MainThreadMarker, frame_count: u32) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.set_frame_count(frame_count) {
+        con_print(marker, &format!("Error setting frame count: {err}\n"));
+        if err.is_internal() {
+            error!("error setting frame count: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
 static BXT_TAS_STUDIO_SET_FRAME_TIME: Command = Command::new(
     b"bxt_tas_studio_set_frame_time\0",
     handler!(
@@ -713,56 +1182,393 @@ fn set_frame_time(marker: MainThreadMarker, frame_time: String) {
     }
 }
 
-static BXT_TAS_STUDIO_SET_COMMANDS: Command = Command::new(
-    b"bxt_tas_studio_set_commands\0",
+static BXT_TAS_STUDIO_SET_FRAME_TIME_PRESERVING_DURATION: Command = Command::new(
+    b"bxt_tas_studio_set_frame_time_preserving_duration\0",
     handler!(
-        "bxt_tas_studio_set_commands <console commands>
-        
-Sets the console commands of the selected frame bulk.",
-        set_commands as fn(_, _)
+        "bxt_tas_studio_set_frame_time_preserving_duration <frame time>
+
+Sets the frame time of the selected frame bulk, changing its frame count to keep the same \
+duration.",
+        set_frame_time_preserving_duration as fn(_, _)
     ),
 );
 
-fn set_commands(marker: MainThreadMarker, commands: String) {
+fn set_frame_time_preserving_duration(marker: MainThreadMarker, frame_time: String) {
     let mut state = STATE.borrow_mut(marker);
     let State::Editing { editor, .. } = &mut *state else {
         return;
     };
 
-    let commands = if commands.is_empty() {
-        None
-    } else {
-        Some(commands)
-    };
-
-    if let Err(err) = editor.set_commands(commands) {
-        con_print(marker, &format!("Error setting commands: {err}\n"));
+    if let Err(err) = editor.set_frame_time_preserving_duration(frame_time) {
+        con_print(marker, &format!("Error setting frame time: {err}\n"));
         if err.is_internal() {
-            error!("error setting commands: {err:?}\n");
+            error!("error setting frame time: {err:?}\n");
             *state = State::Idle;
         }
     }
 }
 
-static BXT_TAS_STUDIO_SELECT_NEXT: Command = Command::new(
-    b"bxt_tas_studio_select_next\0",
+static BXT_TAS_STUDIO_RETIME_RANGE: Command = Command::new(
+    b"bxt_tas_studio_retime_range\0",
     handler!(
-        "bxt_tas_studio_select_next
+        "bxt_tas_studio_retime_range <start bulk> <end bulk> <fps>
 
-Selects the next frame bulk.",
-        select_next as fn(_)
+Sets the frame time of the frame bulks from start bulk to end bulk inclusive, counting from 0, to \
+the one for the given FPS (1000, 500, 250 or 100). The frame counts are scaled to preserve the \
+duration of every frame bulk, with a warning for frame bulks where this is not exactly possible.",
+        retime_range as fn(_, _, _, _)
     ),
 );
 
-fn select_next(marker: MainThreadMarker) {
+fn retime_range(marker: MainThreadMarker, start_bulk: usize, end_bulk: usize, fps: i32) {
     let mut state = STATE.borrow_mut(marker);
     let State::Editing { editor, .. } = &mut *state else {
         return;
     };
 
-    if let Err(err) = editor.select_next() {
-        con_print(marker, &format!("Error selecting frame bulk: {err}\n"));
-        if err.is_internal() {
+    let Some(frame_time) = frame_time_for_fps(fps) else {
+        con_print(
+            marker,
+            "Error: only FPS = 1000, 500, 250 or 100 are currently supported.\n",
+        );
+        return;
+    };
+
+    match editor.retime_range(start_bulk, end_bulk, frame_time) {
+        Ok(inexact) => {
+            for bulk_idx in inexact {
+                con_print(
+                    marker,
+                    &format!(
+                        "Warning: the duration of frame bulk {bulk_idx} could not be preserved \
+                         exactly.\n"
+                    ),
+                );
+            }
+        }
+        Err(err) => {
+            con_print(marker, &format!("Error retiming the frame bulks: {err}\n"));
+            if err.is_internal() {
+                error!("error retiming the frame bulks: {err:?}\n");
+                *state = State::Idle;
+            }
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_SET_COMMANDS: Command = Command::new(
+    b"bxt_tas_studio_set_commands\0",
+    handler!(
+        "bxt_tas_studio_set_commands <console commands>
+        
+Sets the console commands of the selected frame bulk.",
+        set_commands as fn(_, _)
+    ),
+);
+
+fn set_commands(marker: MainThreadMarker, commands: String) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    let commands = if commands.is_empty() {
+        None
+    } else {
+        Some(commands)
+    };
+
+    if let Err(err) = editor.set_commands(commands) {
+        con_print(marker, &format!("Error setting commands: {err}\n"));
+        if err.is_internal() {
+            error!("error setting commands: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_INSERT_COMMAND: Command = Command::new(
+    b"bxt_tas_studio_insert_command\0",
+    handler!(
+        "bxt_tas_studio_insert_command <console commands>
+
+Inserts console commands to run exactly at the hovered frame. The frame bulk is split at the \
+hovered frame if needed, and the commands are added to the frame bulk starting there.",
+        insert_command as fn(_, _)
+    ),
+);
+
+fn insert_command(marker: MainThreadMarker, command: String) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.insert_command(command) {
+        con_print(marker, &format!("Error inserting command: {err}\n"));
+        if err.is_internal() {
+            error!("error inserting command: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_COPY_BULK: Command = Command::new(
+    b"bxt_tas_studio_copy_bulk\0",
+    handler!(
+        "bxt_tas_studio_copy_bulk
+
+Prints the selected frame bulk as HLTAS text, suitable for bxt_tas_studio_paste_bulk.",
+        copy_bulk as fn(_)
+    ),
+);
+
+fn copy_bulk(marker: MainThreadMarker) {
+    let state = STATE.borrow(marker);
+    let State::Editing { editor, .. } = &*state else {
+        return;
+    };
+
+    match editor.selected_bulk_text() {
+        Ok(text) => con_print(marker, &format!("{}\n", text.trim_end())),
+        Err(err) => con_print(marker, &format!("Error copying frame bulk: {err}\n")),
+    }
+}
+
+static BXT_TAS_STUDIO_PASTE_BULK: Command = Command::new(
+    b"bxt_tas_studio_paste_bulk\0",
+    handler!(
+        "bxt_tas_studio_paste_bulk \"<frame bulk>\"
+
+Replaces the settings of the selected frame bulk with the given frame bulk in HLTAS format, for \
+example as printed by bxt_tas_studio_copy_bulk. The frame count of the selected frame bulk is \
+kept. Put the frame bulk in quotes.",
+        paste_bulk as fn(_, _)
+    ),
+);
+
+fn paste_bulk(marker: MainThreadMarker, text: String) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.paste_bulk(&text) {
+        con_print(marker, &format!("Error pasting frame bulk: {err}\n"));
+        if err.is_internal() {
+            error!("error pasting frame bulk: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_SET_PARAMETER_OVERRIDE: Command = Command::new(
+    b"bxt_tas_studio_set_parameter_override\0",
+    handler!(
+        "bxt_tas_studio_set_parameter_override <frame> <parameter> <value>
+
+Overrides a movement parameter (for example, friction) for predicted frames of the current branch \
+starting from the given frame. Frames played by the game are not affected. Overrides are not saved \
+in the project. Boolean parameters are set with 0 or 1. \
+bhop_cap is set with 0 for none, 1 for standard or 2 for custom, which uses \
+bhop_cap_custom_multiplier and bhop_cap_custom_max_speed_scale.",
+        set_parameter_override as fn(_, _, _, _)
+    ),
+);
+
+fn set_parameter_override(marker: MainThreadMarker, frame_idx: usize, name: String, value: f32) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.set_parameter_override(frame_idx, name, value) {
+        con_print(
+            marker,
+            &format!("Error setting parameter override: {err}\n"),
+        );
+        if err.is_internal() {
+            error!("error setting parameter override: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_CLEAR_PARAMETER_OVERRIDES: Command = Command::new(
+    b"bxt_tas_studio_clear_parameter_overrides\0",
+    handler!(
+        "bxt_tas_studio_clear_parameter_overrides [frame]
+
+Clears all movement parameter overrides of the current branch, or only the ones set at the given \
+frame, if provided.",
+        clear_parameter_overrides as fn(_),
+        clear_parameter_overrides_at_frame as fn(_, _)
+    ),
+);
+
+fn clear_parameter_overrides(marker: MainThreadMarker) {
+    clear_parameter_overrides_internal(marker, None);
+}
+
+fn clear_parameter_overrides_at_frame(marker: MainThreadMarker, frame_idx: usize) {
+    clear_parameter_overrides_internal(marker, Some(frame_idx));
+}
+
+fn clear_parameter_overrides_internal(marker: MainThreadMarker, frame_idx: Option<usize>) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.clear_parameter_overrides(frame_idx) {
+        con_print(
+            marker,
+            &format!("Error clearing parameter overrides: {err}\n"),
+        );
+        if err.is_internal() {
+            error!("error clearing parameter overrides: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_SELECT_COLLIDED: Command = Command::new(
+    b"bxt_tas_studio_select_collided\0",
+    handler!(
+        "bxt_tas_studio_select_collided
+
+Selects the frame bulk with the visible collision closest to the center of the screen.",
+        select_collided as fn(_)
+    ),
+);
+
+fn select_collided(marker: MainThreadMarker) {
+    if !matches!(*STATE.borrow(marker), State::Editing { .. }) {
+        return;
+    }
+
+    // Selecting needs screen projection, which is only available when drawing.
+    SELECT_COLLIDED_REQUESTED.set(marker, true);
+}
+
+static BXT_TAS_STUDIO_SIM_STEP: Command = Command::new(
+    b"bxt_tas_studio_sim_step\0",
+    handler!(
+        "bxt_tas_studio_sim_step
+
+Simulates one frame starting from the hovered frame and prints the resulting player position and \
+velocity. The TAS itself is not changed.",
+        sim_step as fn(_)
+    ),
+);
+
+fn sim_step(marker: MainThreadMarker) {
+    if !matches!(*STATE.borrow(marker), State::Editing { .. }) {
+        return;
+    }
+
+    // Simulating needs player tracing, which is only available when drawing.
+    SIM_STEP_REQUESTED.set(marker, true);
+}
+
+static BXT_TAS_STUDIO_PARAM_SENSITIVITY: Command = Command::new(
+    b"bxt_tas_studio_param_sensitivity\0",
+    handler!(
+        "bxt_tas_studio_param_sensitivity <parameter> <delta>
+
+Re-simulates the current branch with the movement parameter increased by delta on every frame and \
+prints how far the final player position moves compared to the unchanged parameters. The parameter \
+names are the same as in bxt_tas_studio_set_parameter_override. The TAS itself is not changed.",
+        param_sensitivity as fn(_, _, _)
+    ),
+);
+
+fn param_sensitivity(marker: MainThreadMarker, name: String, delta: f32) {
+    if !matches!(*STATE.borrow(marker), State::Editing { .. }) {
+        return;
+    }
+
+    // Simulating needs player tracing, which is only available when drawing.
+    *PARAM_SENSITIVITY_REQUESTED.borrow_mut(marker) = Some((name, delta));
+}
+
+static BXT_TAS_STUDIO_TRACE_CROSSHAIR: Command = Command::new(
+    b"bxt_tas_studio_trace_crosshair\0",
+    handler!(
+        "bxt_tas_studio_trace_crosshair
+
+Traces a point from the camera along the view direction using the TAS editor tracer and prints \
+the result. Useful for checking that the tracer matches the map geometry.",
+        trace_crosshair as fn(_)
+    ),
+);
+
+fn trace_crosshair(marker: MainThreadMarker) {
+    if !matches!(*STATE.borrow(marker), State::Editing { .. }) {
+        return;
+    }
+
+    // Tracing is only available when drawing.
+    TRACE_CROSSHAIR_REQUESTED.set(marker, true);
+}
+
+fn print_crosshair_trace(marker: MainThreadMarker, tracer: &Tracer) {
+    let (Some(vieworg), Some(viewangles)) = (
+        engine::r_refdef_vieworg.get_opt(marker),
+        engine::r_refdef_viewangles.get_opt(marker),
+    ) else {
+        con_print(marker, "Error tracing: the view origin is not available\n");
+        return;
+    };
+
+    let (origin, [pitch, yaw, _]) = unsafe { (Vec3::from(*vieworg), *viewangles) };
+    let (pitch, yaw) = (pitch.to_radians(), yaw.to_radians());
+    let forward = Vec3::new(
+        pitch.cos() * yaw.cos(),
+        pitch.cos() * yaw.sin(),
+        -pitch.sin(),
+    );
+
+    let tr = tracer.trace(origin, origin + forward * 8192., Hull::Point);
+    con_print(
+        marker,
+        &format!(
+            "Fraction: {}\nEnd position: {} {} {}\nPlane normal: {} {} {}\nEntity: {}\n\
+             All solid: {}\nStart solid: {}\n",
+            tr.fraction,
+            tr.end_pos.x,
+            tr.end_pos.y,
+            tr.end_pos.z,
+            tr.plane_normal.x,
+            tr.plane_normal.y,
+            tr.plane_normal.z,
+            tr.entity,
+            tr.all_solid,
+            tr.start_solid,
+        ),
+    );
+}
+
+static BXT_TAS_STUDIO_SELECT_NEXT: Command = Command::new(
+    b"bxt_tas_studio_select_next\0",
+    handler!(
+        "bxt_tas_studio_select_next
+
+Selects the next frame bulk. Hold `+bxt_tas_studio_extend_selection` to extend the selected range \
+instead.",
+        select_next as fn(_)
+    ),
+);
+
+fn select_next(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.select_next() {
+        con_print(marker, &format!("Error selecting frame bulk: {err}\n"));
+        if err.is_internal() {
             error!("error selecting frame bulk: {err:?}\n");
             *state = State::Idle;
         }
@@ -774,7 +1580,8 @@ static BXT_TAS_STUDIO_SELECT_PREV: Command = Command::new(
     handler!(
         "bxt_tas_studio_select_prev
 
-Selects the previous frame bulk.",
+Selects the previous frame bulk. Hold `+bxt_tas_studio_extend_selection` to extend the selected \
+range instead.",
         select_prev as fn(_)
     ),
 );
@@ -840,6 +1647,102 @@ fn minus_insert_camera_line_key(marker: MainThreadMarker, _key: i32) {
     minus_insert_camera_line(marker);
 }
 
+static PLUS_BXT_TAS_STUDIO_EXTEND_SELECTION: Command = Command::new(
+    b"+bxt_tas_studio_extend_selection\0",
+    handler!(
+        "+bxt_tas_studio_extend_selection [key]
+
+Hold to make bxt_tas_studio_select_next and bxt_tas_studio_select_prev extend the selected range \
+of frame bulks.",
+        plus_extend_selection as fn(_),
+        plus_extend_selection_key as fn(_, _)
+    ),
+);
+
+fn plus_extend_selection(marker: MainThreadMarker) {
+    if !matches!(*STATE.borrow(marker), State::Editing { .. }) {
+        return;
+    }
+
+    EXTEND_SELECTION_DOWN.set(marker, true);
+}
+
+fn plus_extend_selection_key(marker: MainThreadMarker, _key: i32) {
+    plus_extend_selection(marker);
+}
+
+static MINUS_BXT_TAS_STUDIO_EXTEND_SELECTION: Command = Command::new(
+    b"-bxt_tas_studio_extend_selection\0",
+    handler!(
+        "-bxt_tas_studio_extend_selection [key]
+
+Hold to make bxt_tas_studio_select_next and bxt_tas_studio_select_prev extend the selected range \
+of frame bulks.",
+        minus_extend_selection as fn(_),
+        minus_extend_selection_key as fn(_, _)
+    ),
+);
+
+fn minus_extend_selection(marker: MainThreadMarker) {
+    if !matches!(*STATE.borrow(marker), State::Editing { .. }) {
+        return;
+    }
+
+    EXTEND_SELECTION_DOWN.set(marker, false);
+}
+
+fn minus_extend_selection_key(marker: MainThreadMarker, _key: i32) {
+    minus_extend_selection(marker);
+}
+
+static PLUS_BXT_TAS_STUDIO_ADJUST_FRAME_TIME: Command = Command::new(
+    b"+bxt_tas_studio_adjust_frame_time\0",
+    handler!(
+        "+bxt_tas_studio_adjust_frame_time [key]
+
+Hold while dragging a frame bulk with the middle mouse button to adjust its frame time instead of \
+its frame count. The frame count of the next frame bulk changes to keep the total duration.",
+        plus_adjust_frame_time as fn(_),
+        plus_adjust_frame_time_key as fn(_, _)
+    ),
+);
+
+fn plus_adjust_frame_time(marker: MainThreadMarker) {
+    if !matches!(*STATE.borrow(marker), State::Editing { .. }) {
+        return;
+    }
+
+    ADJUST_FRAME_TIME_DOWN.set(marker, true);
+}
+
+fn plus_adjust_frame_time_key(marker: MainThreadMarker, _key: i32) {
+    plus_adjust_frame_time(marker);
+}
+
+static MINUS_BXT_TAS_STUDIO_ADJUST_FRAME_TIME: Command = Command::new(
+    b"-bxt_tas_studio_adjust_frame_time\0",
+    handler!(
+        "-bxt_tas_studio_adjust_frame_time [key]
+
+Hold while dragging a frame bulk with the middle mouse button to adjust its frame time instead of \
+its frame count. The frame count of the next frame bulk changes to keep the total duration.",
+        minus_adjust_frame_time as fn(_),
+        minus_adjust_frame_time_key as fn(_, _)
+    ),
+);
+
+fn minus_adjust_frame_time(marker: MainThreadMarker) {
+    if !matches!(*STATE.borrow(marker), State::Editing { .. }) {
+        return;
+    }
+
+    ADJUST_FRAME_TIME_DOWN.set(marker, false);
+}
+
+fn minus_adjust_frame_time_key(marker: MainThreadMarker, _key: i32) {
+    minus_adjust_frame_time(marker);
+}
+
 static PLUS_BXT_TAS_STUDIO_LOOK_AROUND: Command = Command::new(
     b"+bxt_tas_studio_look_around\0",
     handler!(
@@ -964,6 +1867,82 @@ fn redo(marker: MainThreadMarker) {
     *simulate_at = Some(Instant::now() + Duration::from_millis(100));
 }
 
+static BXT_TAS_STUDIO_UNDO_ALL: Command = Command::new(
+    b"bxt_tas_studio_undo_all\0",
+    handler!(
+        "bxt_tas_studio_undo_all
+
+Undoes all changes to the script, going back to the start of the history.",
+        undo_all as fn(_)
+    ),
+);
+
+fn undo_all(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing {
+        editor,
+        last_generation,
+        last_branch_idx,
+        simulate_at,
+        ..
+    } = &mut *state
+    else {
+        return;
+    };
+
+    if let Err(err) = editor.undo_all() {
+        con_print(marker, &format!("Error undoing: {err}\n"));
+        if err.is_internal() {
+            error!("error undoing: {err:?}\n");
+            *state = State::Idle;
+        }
+        return;
+    }
+
+    // Force a bridged file update.
+    *last_generation = editor.generation();
+    *last_branch_idx = editor.branch_idx();
+    *simulate_at = Some(Instant::now() + Duration::from_millis(100));
+}
+
+static BXT_TAS_STUDIO_REDO_ALL: Command = Command::new(
+    b"bxt_tas_studio_redo_all\0",
+    handler!(
+        "bxt_tas_studio_redo_all
+
+Redoes all undone changes to the script, going forward to the end of the history.",
+        redo_all as fn(_)
+    ),
+);
+
+fn redo_all(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing {
+        editor,
+        last_generation,
+        last_branch_idx,
+        simulate_at,
+        ..
+    } = &mut *state
+    else {
+        return;
+    };
+
+    if let Err(err) = editor.redo_all() {
+        con_print(marker, &format!("Error redoing: {err}\n"));
+        if err.is_internal() {
+            error!("error redoing: {err:?}\n");
+            *state = State::Idle;
+        }
+        return;
+    }
+
+    // Force a bridged file update.
+    *last_generation = editor.generation();
+    *last_branch_idx = editor.branch_idx();
+    *simulate_at = Some(Instant::now() + Duration::from_millis(100));
+}
+
 static BXT_TAS_STUDIO_BRANCH_CLONE: Command = Command::new(
     b"bxt_tas_studio_branch_clone\0",
     handler!(
@@ -989,6 +1968,33 @@ fn branch_clone(marker: MainThreadMarker) {
     }
 }
 
+static BXT_TAS_STUDIO_EXTRACT_BRANCH: Command = Command::new(
+    b"bxt_tas_studio_extract_branch\0",
+    handler!(
+        "bxt_tas_studio_extract_branch <start frame> <end frame>
+
+Creates a new branch with only the frames from start frame to end frame of the current branch, \
+splitting frame bulks at the boundaries. The new branch is predicted from the player state at the \
+start frame, but it needs a save at that point to be played back in the game.",
+        extract_branch as fn(_, _, _)
+    ),
+);
+
+fn extract_branch(marker: MainThreadMarker, start_frame: usize, end_frame: usize) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.extract_branch(start_frame, end_frame) {
+        con_print(marker, &format!("Error extracting branch: {err}\n"));
+        if err.is_internal() {
+            error!("error extracting branch: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
 static BXT_TAS_STUDIO_BRANCH_FOCUS_ID: Command = Command::new(
     b"bxt_tas_studio_branch_focus_id\0",
     handler!(
@@ -1014,6 +2020,57 @@ fn branch_focus_id(marker: MainThreadMarker, branch_idx: usize) {
     }
 }
 
+static BXT_TAS_STUDIO_BRANCH_FOCUS_NAME: Command = Command::new(
+    b"bxt_tas_studio_branch_focus_name\0",
+    handler!(
+        "bxt_tas_studio_branch_focus_name <name>
+
+Focuses the first visible branch with the given name.",
+        branch_focus_name as fn(_, _)
+    ),
+);
+
+fn branch_focus_name(marker: MainThreadMarker, name: String) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.branch_focus_by_name(&name) {
+        con_print(marker, &format!("Error focusing branch: {err}\n"));
+        if err.is_internal() {
+            error!("error focusing branch: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_BRANCH_LIST: Command = Command::new(
+    b"bxt_tas_studio_branch_list\0",
+    handler!(
+        "bxt_tas_studio_branch_list
+
+Prints the index, name and visibility of every branch. The focused branch is marked with *.",
+        branch_list as fn(_)
+    ),
+);
+
+fn branch_list(marker: MainThreadMarker) {
+    let state = STATE.borrow(marker);
+    let State::Editing { editor, .. } = &*state else {
+        return;
+    };
+
+    for (branch_idx, info) in editor.branches_info().into_iter().enumerate() {
+        let focused = if info.is_focused { "*" } else { " " };
+        let hidden = if info.is_hidden { " (hidden)" } else { "" };
+        con_print(
+            marker,
+            &format!("{focused} #{branch_idx}: {}{hidden}\n", info.name),
+        );
+    }
+}
+
 static BXT_TAS_STUDIO_BRANCH_FOCUS_NEXT: Command = Command::new(
     b"bxt_tas_studio_branch_focus_next\0",
     handler!(
@@ -1117,6 +2174,66 @@ fn branch_show_id(marker: MainThreadMarker, branch_idx: usize) {
     }
 }
 
+static BXT_TAS_STUDIO_BRANCH_COLOR: Command = Command::new(
+    b"bxt_tas_studio_branch_color\0",
+    handler!(
+        "bxt_tas_studio_branch_color <index> [<r> <g> <b>]
+
+Sets the color used to draw the branch with the given index when it's not focused. Color \
+components range from 0 to 255. Without a color, resets the branch to the default gray.",
+        branch_reset_color as fn(_, _),
+        branch_color as fn(_, _, _, _, _)
+    ),
+);
+
+fn branch_reset_color(marker: MainThreadMarker, branch_idx: usize) {
+    set_branch_color(marker, branch_idx, None);
+}
+
+fn branch_color(marker: MainThreadMarker, branch_idx: usize, r: u8, g: u8, b: u8) {
+    set_branch_color(marker, branch_idx, Some([r, g, b]));
+}
+
+fn set_branch_color(marker: MainThreadMarker, branch_idx: usize, color: Option<[u8; 3]>) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.branch_set_color(branch_idx, color) {
+        con_print(marker, &format!("Error setting branch color: {err}\n"));
+        if err.is_internal() {
+            error!("error setting branch color: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_BRANCH_RENAME: Command = Command::new(
+    b"bxt_tas_studio_branch_rename\0",
+    handler!(
+        "bxt_tas_studio_branch_rename <index> <name>
+
+Renames the branch with the given index.",
+        branch_rename as fn(_, _, _)
+    ),
+);
+
+fn branch_rename(marker: MainThreadMarker, branch_idx: usize, name: String) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.rename_branch(branch_idx, name) {
+        con_print(marker, &format!("Error renaming branch: {err}\n"));
+        if err.is_internal() {
+            error!("error renaming branch: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
 static BXT_TAS_STUDIO_TOGGLE: Command = Command::new(
     b"bxt_tas_studio_toggle\0",
     handler!(
@@ -1280,33 +2397,159 @@ fn toggle(marker: MainThreadMarker, what: String) {
         }
     };
 
-    if let Err(err) = editor.toggle_auto_action(target) {
-        con_print(marker, &format!("Error toggling value: {err}\n"));
+    if let Err(err) = editor.toggle_auto_action(target) {
+        con_print(marker, &format!("Error toggling value: {err}\n"));
+        if err.is_internal() {
+            error!("error toggling value: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_TOGGLE_DBC: Command = Command::new(
+    b"bxt_tas_studio_toggle_dbc\0",
+    handler!(
+        "bxt_tas_studio_toggle_dbc [ceilings]
+
+Toggles duck before collision on the selected frame bulk. With `ceilings`, toggles duck before \
+collision including ceilings.",
+        toggle_dbc as fn(_),
+        toggle_dbc_with_arg as fn(_, _)
+    ),
+);
+
+fn toggle_dbc(marker: MainThreadMarker) {
+    toggle_dbc_target(marker, ToggleAutoActionTarget::DuckBeforeCollision);
+}
+
+fn toggle_dbc_with_arg(marker: MainThreadMarker, what: String) {
+    let target = match &*what.trim().to_ascii_lowercase() {
+        "ceilings" => ToggleAutoActionTarget::DuckBeforeCollisionIncludingCeilings,
+        _ => {
+            con_print(
+                marker,
+                &format!(
+                    "Error: unknown value.\n\nUsage: {}\n",
+                    BXT_TAS_STUDIO_TOGGLE_DBC.description()
+                ),
+            );
+            return;
+        }
+    };
+
+    toggle_dbc_target(marker, target);
+}
+
+fn toggle_dbc_target(marker: MainThreadMarker, target: ToggleAutoActionTarget) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.toggle_auto_action(target) {
+        con_print(marker, &format!("Error toggling value: {err}\n"));
+        if err.is_internal() {
+            error!("error toggling value: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_AUTOJUMP_RANGE: Command = Command::new(
+    b"bxt_tas_studio_autojump_range\0",
+    handler!(
+        "bxt_tas_studio_autojump_range <start bulk> <end bulk>
+
+Enables autojump on the frame bulks from start bulk to end bulk inclusive, counting from 0. Frame \
+bulks that already have autojump are left as is, and ducktap is replaced with autojump.",
+        autojump_range as fn(_, _, _)
+    ),
+);
+
+fn autojump_range(marker: MainThreadMarker, start_bulk: usize, end_bulk: usize) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.autojump_range(start_bulk, end_bulk) {
+        con_print(marker, &format!("Error enabling autojump: {err}\n"));
+        if err.is_internal() {
+            error!("error enabling autojump: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_FLATTEN_YAWS_IN_RANGE: Command = Command::new(
+    b"bxt_tas_studio_flatten_yaws_in_range\0",
+    handler!(
+        "bxt_tas_studio_flatten_yaws_in_range <start bulk> <end bulk>
+
+Sets the yaws of the frame bulks from start bulk to end bulk inclusive, counting from 0, to their \
+average weighted by the horizontal speed in each frame bulk.",
+        flatten_yaws_in_range as fn(_, _, _)
+    ),
+);
+
+fn flatten_yaws_in_range(marker: MainThreadMarker, start_bulk: usize, end_bulk: usize) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.flatten_yaws_in_range(start_bulk, end_bulk) {
+        con_print(marker, &format!("Error flattening yaws: {err}\n"));
+        if err.is_internal() {
+            error!("error flattening yaws: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_HIDE: Command = Command::new(
+    b"bxt_tas_studio_hide\0",
+    handler!(
+        "bxt_tas_studio_hide
+
+Hides the frames before the one under cursor to avoid clutter. If there's no visible frame under \
+the cursor, makes all frames visible.",
+        hide as fn(_)
+    ),
+);
+
+fn hide(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.hide_frames_up_to_hovered() {
+        con_print(marker, &format!("Error hiding: {err}\n"));
         if err.is_internal() {
-            error!("error toggling value: {err:?}\n");
+            error!("error hiding: {err:?}\n");
             *state = State::Idle;
         }
     }
 }
 
-static BXT_TAS_STUDIO_HIDE: Command = Command::new(
-    b"bxt_tas_studio_hide\0",
+static BXT_TAS_STUDIO_HIDE_BEFORE: Command = Command::new(
+    b"bxt_tas_studio_hide_before\0",
     handler!(
-        "bxt_tas_studio_hide
+        "bxt_tas_studio_hide_before <frame>
 
-Hides the frames before the one under cursor to avoid clutter. If there's no visible frame under \
-the cursor, makes all frames visible.",
-        hide as fn(_)
+Hides the frames before the given frame to avoid clutter. Use 0 to make all frames visible.",
+        hide_before as fn(_, _)
     ),
 );
 
-fn hide(marker: MainThreadMarker) {
+fn hide_before(marker: MainThreadMarker, frame_idx: usize) {
     let mut state = STATE.borrow_mut(marker);
     let State::Editing { editor, .. } = &mut *state else {
         return;
     };
 
-    if let Err(err) = editor.hide_frames_up_to_hovered() {
+    if let Err(err) = editor.hide_frames_before(frame_idx) {
         con_print(marker, &format!("Error hiding: {err}\n"));
         if err.is_internal() {
             error!("error hiding: {err:?}\n");
@@ -1315,6 +2558,52 @@ fn hide(marker: MainThreadMarker) {
     }
 }
 
+static BXT_TAS_STUDIO_SET_CAMERA_FRAME_RANGE: Command = Command::new(
+    b"bxt_tas_studio_set_camera_frame_range\0",
+    handler!(
+        "bxt_tas_studio_set_camera_frame_range <start frame> <end frame>
+
+Restricts hovering camera lines in the camera editor to the given range of frames (inclusive).",
+        set_camera_frame_range as fn(_, _, _)
+    ),
+);
+
+fn set_camera_frame_range(marker: MainThreadMarker, start: usize, end: usize) {
+    set_camera_frame_range_internal(marker, Some((start, end)));
+}
+
+static BXT_TAS_STUDIO_CLEAR_CAMERA_FRAME_RANGE: Command = Command::new(
+    b"bxt_tas_studio_clear_camera_frame_range\0",
+    handler!(
+        "bxt_tas_studio_clear_camera_frame_range
+
+Allows hovering camera lines in the camera editor on all frames again.",
+        clear_camera_frame_range as fn(_)
+    ),
+);
+
+fn clear_camera_frame_range(marker: MainThreadMarker) {
+    set_camera_frame_range_internal(marker, None);
+}
+
+fn set_camera_frame_range_internal(marker: MainThreadMarker, range: Option<(usize, usize)>) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.set_camera_frame_range(range) {
+        con_print(
+            marker,
+            &format!("Error setting camera frame range: {err}\n"),
+        );
+        if err.is_internal() {
+            error!("error setting camera frame range: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
 static BXT_TAS_STUDIO_SMOOTH: Command = Command::new(
     b"bxt_tas_studio_smooth\0",
     handler!(
@@ -1340,6 +2629,113 @@ fn smooth(marker: MainThreadMarker) {
     }
 }
 
+static BXT_TAS_STUDIO_SMOOTH_RANGE: Command = Command::new(
+    b"bxt_tas_studio_smooth_range\0",
+    handler!(
+        "bxt_tas_studio_smooth_range <start frame> <end frame>
+
+Applies smoothing to the frames from start frame to end frame inclusive. All frames in the range \
+must be accurate.",
+        smooth_range as fn(_, _, _)
+    ),
+);
+
+fn smooth_range(marker: MainThreadMarker, start_frame: usize, end_frame: usize) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.apply_smoothing_to_range(start_frame, end_frame) {
+        con_print(marker, &format!("Error applying smoothing: {err}\n"));
+        if err.is_internal() {
+            error!("error applying smoothing: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_ADD_YAW_OFFSET_CHANGE: Command = Command::new(
+    b"bxt_tas_studio_add_yaw_offset_change\0",
+    handler!(
+        "bxt_tas_studio_add_yaw_offset_change <degrees> <over seconds>
+
+Inserts a change line at the hovered frame that changes the vectorial strafing yaw offset to the \
+given value over the given number of seconds. Works in the camera editor.",
+        add_yaw_offset_change as fn(_, _, _)
+    ),
+);
+
+fn add_yaw_offset_change(marker: MainThreadMarker, final_value: f32, over: f32) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.add_yaw_offset_change(final_value, over) {
+        con_print(marker, &format!("Error adding yaw offset change: {err}\n"));
+        if err.is_internal() {
+            error!("error adding yaw offset change: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_SMOOTH_WINDOW_FROM_SEGMENT: Command = Command::new(
+    b"bxt_tas_studio_smooth_window_from_segment\0",
+    handler!(
+        "bxt_tas_studio_smooth_window_from_segment
+
+Sets _bxt_tas_studio_smooth_window_s to the duration of the hovered segment.",
+        smooth_window_from_segment as fn(_)
+    ),
+);
+
+fn smooth_window_from_segment(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    match editor.set_smooth_window_from_hovered_segment() {
+        // Update the cvar too, otherwise the editor value is overwritten on the next draw.
+        Ok(window_s) => engine::prepend_command(
+            marker,
+            &format!("_bxt_tas_studio_smooth_window_s {window_s}\n"),
+        ),
+        Err(err) => {
+            con_print(marker, &format!("Error setting smoothing window: {err}\n"));
+            if err.is_internal() {
+                error!("error setting smoothing window: {err:?}\n");
+                *state = State::Idle;
+            }
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_TOGGLE_SMOOTHED_VIEW: Command = Command::new(
+    b"bxt_tas_studio_toggle_smoothed_view\0",
+    handler!(
+        "bxt_tas_studio_toggle_smoothed_view
+
+Cycles between drawing both the raw and the smoothed path, only the raw path, and only the \
+smoothed path when bxt_tas_studio_auto_smoothing is enabled.",
+        toggle_smoothed_view as fn(_)
+    ),
+);
+
+fn toggle_smoothed_view(marker: MainThreadMarker) {
+    let mode = SMOOTHED_VIEW_MODE.get(marker).next();
+    SMOOTHED_VIEW_MODE.set(marker, mode);
+
+    let description = match mode {
+        SmoothedViewMode::Both => "both paths",
+        SmoothedViewMode::RawOnly => "only the raw path",
+        SmoothedViewMode::SmoothedOnly => "only the smoothed path",
+    };
+    con_print(marker, &format!("Showing {description}.\n"));
+}
+
 static BXT_TAS_STUDIO_SMOOTH_GLOBALLY: Command = Command::new(
     b"_bxt_tas_studio_smooth_globally\0",
     handler!(
@@ -1365,12 +2761,60 @@ fn smooth_globally(marker: MainThreadMarker) {
     }
 }
 
+static BXT_TAS_STUDIO_SMOOTHED_SCRATCH_SET_YAW: Command = Command::new(
+    b"bxt_tas_studio_smoothed_scratch_set_yaw\0",
+    handler!(
+        "bxt_tas_studio_smoothed_scratch_set_yaw <bulk> <yaw>
+
+Sets the yaw of the frame bulk, counting from 0, in a scratch copy of the auto-smoothed script for \
+experimentation. The scratch path is predicted and drawn in blue where it differs from the \
+smoothed path. The prediction does not account for the smoothed view overrides, so the scratch \
+path is approximate. The scratch copy is never saved and is discarded when the smoothed script \
+changes.",
+        smoothed_scratch_set_yaw as fn(_, _, _)
+    ),
+);
+
+fn smoothed_scratch_set_yaw(marker: MainThreadMarker, bulk_idx: usize, yaw: f32) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.smoothed_scratch_set_yaw(bulk_idx, yaw) {
+        con_print(marker, &format!("Error setting yaw: {err}\n"));
+        if err.is_internal() {
+            error!("error setting scratch yaw: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_SMOOTHED_SCRATCH_CLEAR: Command = Command::new(
+    b"bxt_tas_studio_smoothed_scratch_clear\0",
+    handler!(
+        "bxt_tas_studio_smoothed_scratch_clear
+
+Discards the scratch copy of the auto-smoothed script.",
+        smoothed_scratch_clear as fn(_)
+    ),
+);
+
+fn smoothed_scratch_clear(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    editor.smoothed_scratch_clear();
+}
+
 static BXT_TAS_STUDIO_DELETE: Command = Command::new(
     b"bxt_tas_studio_delete\0",
     handler!(
         "bxt_tas_studio_delete
 
-Deletes the selected frame bulk or the line under cursor in the camera editor.",
+Deletes the selected frame bulks or the line under cursor in the camera editor.",
         delete as fn(_)
     ),
 );
@@ -1390,6 +2834,84 @@ fn delete(marker: MainThreadMarker) {
     }
 }
 
+static BXT_TAS_STUDIO_DUPLICATE: Command = Command::new(
+    b"bxt_tas_studio_duplicate\0",
+    handler!(
+        "bxt_tas_studio_duplicate
+
+Inserts a copy of the selected frame bulk right after it. The original frame bulk stays selected.",
+        duplicate as fn(_)
+    ),
+);
+
+fn duplicate(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.duplicate_selected_bulk() {
+        con_print(marker, &format!("Error duplicating frame bulk: {err}\n"));
+        if err.is_internal() {
+            error!("error duplicating frame bulk: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_MIRROR: Command = Command::new(
+    b"bxt_tas_studio_mirror\0",
+    handler!(
+        "bxt_tas_studio_mirror
+
+Mirrors the movement of the selected frame bulks, turning left into right and vice versa. Yaws are \
+negated, and strafing directions and left and right movement keys are swapped. Strafing towards a \
+point is kept as is.
+        mirror as fn(_)
+    ),
+);
+
+fn mirror(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.mirror_selected() {
+        con_print(marker, &format!("Error mirroring frame bulks: {err}\n"));
+        if err.is_internal() {
+            error!("error mirroring frame bulks: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
+static BXT_TAS_STUDIO_REVERSE: Command = Command::new(
+    b"bxt_tas_studio_reverse\0",
+    handler!(
+        "bxt_tas_studio_reverse
+
+Reverses the order of the selected frame bulks. Other lines, like strafing type changes, stay in \
+place.",
+        reverse as fn(_)
+    ),
+);
+
+fn reverse(marker: MainThreadMarker) {
+    let mut state = STATE.borrow_mut(marker);
+    let State::Editing { editor, .. } = &mut *state else {
+        return;
+    };
+
+    if let Err(err) = editor.reverse_selected() {
+        con_print(marker, &format!("Error reversing frame bulks: {err}\n"));
+        if err.is_internal() {
+            error!("error reversing frame bulks: {err:?}\n");
+            *state = State::Idle;
+        }
+    }
+}
+
 static BXT_TAS_STUDIO_DELETE_LAST: Command = Command::new(
     b"bxt_tas_studio_delete_last\0",
     handler!(
@@ -1538,6 +3060,8 @@ enum State {
         is_smoothed: bool,
         frames_played: usize,
         next_frame_params: Option<Parameters>,
+        /// Load command of the script, for reporting map load failures.
+        load_command: Option<String>,
     },
     /// Preparing to play a HLTAS, will open the editor afterwards.
     PreparingToPlayToEditor(Editor, Bridge, bool),
@@ -1661,16 +3185,25 @@ pub unsafe fn maybe_receive_messages_from_remote_server(marker: MainThreadMarker
                     is_smoothed,
                     frames_played: 0,
                     next_frame_params: None,
+                    load_command: script.properties.load_command,
                 };
             }
         }
         State::PlayingToEditor { editor, .. } | State::Editing { editor, .. } => {
-            while let Ok(Some(frame)) = remote::receive_frame_from_client() {
-                // Don't truncate the frames here as it makes it more annoying to work on TASes with
-                // loading desync or other inconsistencies.
-                if let Some(play_request) = editor.apply_accurate_frame(frame, false) {
-                    info!("sending second play request");
-                    remote::maybe_send_request_to_client(play_request);
+            while let Ok(Some(message)) = remote::receive_message_from_client() {
+                match message {
+                    ClientMessage::AccurateFrame(frame) => {
+                        // Don't truncate the frames here as it makes it more annoying to work on
+                        // TASes with loading desync or other inconsistencies.
+                        if let Some(play_request) = editor.apply_accurate_frame(frame, false) {
+                            info!("sending second play request");
+                            remote::maybe_send_request_to_client(play_request);
+                        }
+                    }
+                    ClientMessage::Response(response) => {
+                        warn!("remote game: {response}");
+                        con_print(marker, &format!("Error in the remote game: {response}\n"));
+                    }
                 }
             }
             editor.recompute_extra_camera_frame_data_if_needed();
@@ -1739,9 +3272,18 @@ pub fn tas_playback_rendered_views(marker: MainThreadMarker) {
                     r_refdef_vieworg[2] -= 16.;
                 }
 
-                r_refdef_viewangles[0] = state.rendered_viewangles[0];
+                // Predicted frames don't have rendered viewangles, use the input ones instead.
+                let rendered_viewangles = state.rendered_viewangles.unwrap_or_else(|| {
+                    Vec3::new(
+                        state.prev_frame_input.pitch.to_degrees(),
+                        state.prev_frame_input.yaw.to_degrees(),
+                        0.,
+                    )
+                });
 
-                r_refdef_viewangles[1] = state.rendered_viewangles[1];
+                r_refdef_viewangles[0] = rendered_viewangles[0];
+
+                r_refdef_viewangles[1] = rendered_viewangles[1];
 
                 State::PlayingViews {
                     editor,
@@ -1778,6 +3320,28 @@ pub unsafe fn on_tas_playback_frame(
         let Some(player) = player_data(marker) else {
             // This can happen when opening a .hltasproj for a missing map file from an
             // already-loaded different map.
+            if let State::Playing {
+                generation,
+                frames_played,
+                load_command,
+                ..
+            } = &*state
+            {
+                // Let the main game know instead of it waiting for frames forever.
+                let response = if *frames_played == 0 {
+                    GameResponse::MapLoadFailed {
+                        map: load_command.clone().unwrap_or_default(),
+                    }
+                } else {
+                    GameResponse::PlaybackError {
+                        generation: *generation,
+                        frame_idx: u32::try_from(*frames_played).unwrap_or(u32::MAX),
+                        message: "no player data, the map is probably not loaded".to_owned(),
+                    }
+                };
+                let _ = remote::send_response_to_server(response);
+            }
+
             *state = State::Idle;
             // This whole issue should be fixed in BXT (stop TAS playback there if no map loaded),
             // but until it isn't, also try to fix up some state like norefresh.
@@ -1812,7 +3376,7 @@ pub unsafe fn on_tas_playback_frame(
         }
 
         // Store rendered viewangles.
-        strafe_state.rendered_viewangles = data.rendered_viewangles.into();
+        strafe_state.rendered_viewangles = Some(data.rendered_viewangles.into());
 
         // We don't have a good way to extract real trace results from the movement code, so let's
         // make up trace results based on previous frame's predicted fractions and normal Zs from
@@ -1996,11 +3560,38 @@ pub fn draw(marker: MainThreadMarker, tri: &TriangleApi) {
     editor.set_in_camera_editor(BXT_TAS_STUDIO_CAMERA_EDITOR.as_bool(marker));
     editor.set_auto_smoothing(BXT_TAS_STUDIO_AUTO_SMOOTHING.as_bool(marker));
     editor.set_show_player_bbox(BXT_TAS_STUDIO_SHOW_PLAYER_BBOX.as_bool(marker));
+    editor.set_show_velocity_vectors(BXT_TAS_STUDIO_SHOW_VELOCITY_VECTORS.as_bool(marker));
+    editor.set_fast_preview(BXT_TAS_STUDIO_FAST_PREVIEW.as_bool(marker));
+    editor.set_max_trace_distance(
+        Some(BXT_TAS_STUDIO_MAX_TRACE_DISTANCE.as_f32(marker)).filter(|&distance| distance > 0.),
+    );
+    editor.set_max_frame_count(
+        BXT_TAS_STUDIO_MAX_FRAME_COUNT
+            .as_u64(marker)
+            .try_into()
+            .unwrap_or(usize::MAX),
+    );
+    editor.set_color_by_strafe_angle_error(
+        BXT_TAS_STUDIO_COLOR_BY_STRAFE_ANGLE_ERROR.as_bool(marker),
+    );
+    editor.set_highlight_steep_slopes(BXT_TAS_STUDIO_HIGHLIGHT_STEEP_SLOPES.as_bool(marker));
+    editor.set_max_drawn_frames(match BXT_TAS_STUDIO_MAX_DRAWN_FRAMES.as_u64(marker) {
+        0 => None,
+        x => Some(x as usize),
+    });
+    editor.set_smoothed_view_mode(SMOOTHED_VIEW_MODE.get(marker));
+    editor.set_select_after_delete(BXT_TAS_STUDIO_SELECT_AFTER_DELETE.as_bool(marker));
     editor.set_smooth_window_s(BXT_TAS_STUDIO_SMOOTH_WINDOW_S.as_f32(marker));
     editor.set_smooth_small_window_s(BXT_TAS_STUDIO_SMOOTH_SMALL_WINDOW_S.as_f32(marker));
     editor.set_smooth_small_window_multiplier(
         BXT_TAS_STUDIO_SMOOTH_SMALL_WINDOW_MULTIPLIER.as_f32(marker),
     );
+    editor.set_smooth_kind(match BXT_TAS_STUDIO_SMOOTH_KIND.as_u64(marker) {
+        1 => SmoothingKind::Gaussian {
+            sigma_s: BXT_TAS_STUDIO_SMOOTH_GAUSSIAN_SIGMA_S.as_f32(marker),
+        },
+        _ => SmoothingKind::Windowed,
+    });
     editor.set_norefresh_until_stop_frame(norefresh_until_stop_frame_frame_idx(marker, editor));
 
     // SAFETY: if we have access to TriangleApi, it's safe to do player tracing too.
@@ -2020,6 +3611,8 @@ pub fn draw(marker: MainThreadMarker, tri: &TriangleApi) {
         adjust_faster: last_buttons.contains(Buttons::IN_ALT1),
         adjust_slower: last_buttons.contains(Buttons::IN_DUCK),
         insert_camera_line: INSERT_CAMERA_LINE_DOWN.get(marker),
+        extend_selection: EXTEND_SELECTION_DOWN.get(marker),
+        adjust_frame_time: ADJUST_FRAME_TIME_DOWN.get(marker),
     };
 
     let deadline = Instant::now() + Duration::from_millis(20);
@@ -2042,6 +3635,82 @@ pub fn draw(marker: MainThreadMarker, tri: &TriangleApi) {
         return;
     }
 
+    if SELECT_COLLIDED_REQUESTED.get(marker) {
+        SELECT_COLLIDED_REQUESTED.set(marker, false);
+
+        let screen_center = Vec2::new(width as f32, height as f32) / 2.;
+        if let Err(err) = editor.select_collided(world_to_screen, screen_center) {
+            con_print(marker, &format!("Error selecting collided bulk: {err}\n"));
+            if err.is_internal() {
+                error!("error selecting collided bulk: {err:?}\n");
+                *state = State::Idle;
+                return;
+            }
+        }
+    }
+
+    if TRACE_CROSSHAIR_REQUESTED.get(marker) {
+        TRACE_CROSSHAIR_REQUESTED.set(marker, false);
+        print_crosshair_trace(marker, &tracer);
+    }
+
+    if SIM_STEP_REQUESTED.get(marker) {
+        SIM_STEP_REQUESTED.set(marker, false);
+
+        match editor.sim_step(&tracer) {
+            Ok(strafe_state) => {
+                let player = strafe_state.player;
+                con_print(
+                    marker,
+                    &format!(
+                        "Position: {} {} {}\nVelocity: {} {} {} (horizontal speed {})\n",
+                        player.pos.x,
+                        player.pos.y,
+                        player.pos.z,
+                        player.vel.x,
+                        player.vel.y,
+                        player.vel.z,
+                        player.vel.truncate().length(),
+                    ),
+                );
+            }
+            Err(err) => {
+                con_print(marker, &format!("Error simulating a frame: {err}\n"));
+                if err.is_internal() {
+                    error!("error simulating a frame: {err:?}\n");
+                    *state = State::Idle;
+                    return;
+                }
+            }
+        }
+    }
+
+    let param_sensitivity_request = PARAM_SENSITIVITY_REQUESTED.borrow_mut(marker).take();
+    if let Some((name, delta)) = param_sensitivity_request {
+        match editor.param_sensitivity(&tracer, &name, delta) {
+            Ok(offset) => {
+                con_print(
+                    marker,
+                    &format!(
+                        "Final position offset: {} {} {} (distance {})\n",
+                        offset.x,
+                        offset.y,
+                        offset.z,
+                        offset.length(),
+                    ),
+                );
+            }
+            Err(err) => {
+                con_print(marker, &format!("Error computing sensitivity: {err}\n"));
+                if err.is_internal() {
+                    error!("error computing sensitivity: {err:?}\n");
+                    *state = State::Idle;
+                    return;
+                }
+            }
+        }
+    }
+
     if *last_generation != editor.generation() || *last_branch_idx != editor.branch_idx() {
         *last_generation = editor.generation();
         *last_branch_idx = editor.branch_idx();
@@ -2061,11 +3730,19 @@ pub fn draw(marker: MainThreadMarker, tri: &TriangleApi) {
         }
     }
 
+    let line_width = BXT_TAS_STUDIO_LINE_WIDTH.as_f32(marker).max(0.);
+    if BXT_TAS_STUDIO_LINE_QUADS.as_bool(marker) {
+        if let Some(vieworg) = engine::r_refdef_vieworg.get_opt(marker) {
+            let view_origin = Vec3::from(unsafe { *vieworg });
+            editor.draw_quads(tri, view_origin, line_width, world_to_screen);
+            return;
+        }
+    }
+
     let gl = crate::gl::GL.borrow(marker);
     if let Some(gl) = gl.as_ref() {
-        let width = BXT_TAS_STUDIO_LINE_WIDTH.as_f32(marker).max(0.);
         unsafe {
-            gl.LineWidth(width);
+            gl.LineWidth(line_width);
         }
     }
 
@@ -2228,24 +3905,41 @@ pub fn draw_hud(marker: MainThreadMarker, draw: &hud::Draw) {
 
     write!(&mut text, "Re-records: {}\0", editor.undo_log_len()).unwrap();
 
-    write!(&mut text, "Branch #{}\0", editor.branch_idx()).unwrap();
+    let (simulated_frames, total_frames) = editor.prediction_progress();
+    write!(
+        &mut text,
+        "Simulated: {simulated_frames} / {total_frames} frames\0"
+    )
+    .unwrap();
+
+    write!(
+        &mut text,
+        "Branch #{}: {}\0",
+        editor.branch_idx(),
+        editor.branch().name()
+    )
+    .unwrap();
 
     match editor.selected_bulk_idx() {
         None => text.extend(b"  no frame bulk selected\0"),
         Some(selected_bulk_idx) => {
             let script = editor.script();
 
-            let (bulk, _) = bulk_and_first_frame_idx(script)
+            let (bulk, first_frame_idx) = bulk_and_first_frame_idx(script)
                 .nth(selected_bulk_idx)
                 .unwrap();
 
             add_frame_bulk_hud_lines(&mut text, bulk);
+
+            let last_frame_idx = first_frame_idx + bulk.frame_count.get() as usize - 1;
+            add_bulk_timing_hud_lines(&mut text, &editor.branch().frames, last_frame_idx);
         }
     };
 
     if let Some(hovered_frame) = editor.hovered_frame() {
         let hovered_frame_idx = editor.hovered_frame_idx().unwrap();
-        add_hovered_frame_hud_lines(&mut text, hovered_frame_idx, hovered_frame);
+        let view_angles = editor.view_angles_deg(hovered_frame_idx).unwrap();
+        add_hovered_frame_hud_lines(&mut text, hovered_frame_idx, hovered_frame, view_angles);
     }
 
     // Measure using our longest string and draw background.
@@ -2267,6 +3961,26 @@ pub fn draw_hud(marker: MainThreadMarker, draw: &hud::Draw) {
         ml.line(line);
     }
 
+    // Show a progress bar under the status while prediction is catching up.
+    if simulated_frames < total_frames {
+        let pos = IVec2::new(0, 2 * info.iCharHeight + height + 2 * PADDING);
+        let bar_height = info.iCharHeight / 2;
+        let bar_width = width + 2 * PADDING;
+        draw.fill(
+            pos,
+            IVec2::new(bar_width, bar_height),
+            IVec4::new(0, 0, 0, 150),
+        );
+
+        let filled_width =
+            (bar_width as f32 * simulated_frames as f32 / total_frames as f32) as i32;
+        draw.fill(
+            pos,
+            IVec2::new(filled_width, bar_height),
+            IVec4::new(255, 255, 255, 150),
+        );
+    }
+
     if let Some(side_strafe_accelerated_yawspeed_adjustment) =
         editor.side_strafe_accelerated_yawspeed_adjustment()
     {
@@ -2300,7 +4014,30 @@ pub fn draw_hud(marker: MainThreadMarker, draw: &hud::Draw) {
     }
 }
 
-fn add_hovered_frame_hud_lines(text: &mut Vec<u8>, frame_idx: usize, frame: &Frame) {
+fn add_bulk_timing_hud_lines(text: &mut Vec<u8>, frames: &[Frame], last_frame_idx: usize) {
+    // The frames of the bulk may not be simulated yet.
+    let Some(last_frame) = frames.get(last_frame_idx) else {
+        return;
+    };
+
+    // Skip the initial frame since it's not simulated.
+    let time: f32 = frames[1..=last_frame_idx]
+        .iter()
+        .map(|frame| frame.parameters.frame_time)
+        .sum();
+    write!(text, "Time at End: {time:.3} s\0").unwrap();
+
+    // Changing the frame count by one adds or removes a frame with the same frame time.
+    let delta = last_frame.parameters.frame_time;
+    write!(text, "Frame Count +/-1: +/-{delta:.3} s\0").unwrap();
+}
+
+fn add_hovered_frame_hud_lines(
+    text: &mut Vec<u8>,
+    frame_idx: usize,
+    frame: &Frame,
+    (yaw, pitch): (f32, f32),
+) {
     text.extend(b"\0Frame Under Cursor:\0");
 
     write!(text, "  Frame #{}\0", frame_idx).unwrap();
@@ -2309,9 +4046,7 @@ fn add_hovered_frame_hud_lines(text: &mut Vec<u8>, frame_idx: usize, frame: &Fra
     let fps = (1. / frame_time).round();
     write!(text, "  Duration: {frame_time:.3} s ({fps} FPS)\0").unwrap();
 
-    let yaw = frame.state.prev_frame_input.yaw.to_degrees();
     write!(text, "  Yaw: {:.3}\0", yaw).unwrap();
-    let pitch = frame.state.prev_frame_input.pitch.to_degrees();
     write!(text, "  Pitch: {:.3}\0", pitch).unwrap();
 
     write!(
@@ -2333,6 +4068,15 @@ fn add_hovered_frame_hud_lines(text: &mut Vec<u8>, frame_idx: usize, frame: &Fra
     write!(text, "  Z Pos: {:.1}\0", frame.state.player.pos.z).unwrap();
 
     write!(text, "  Stamina: {:.1}\0", frame.state.player.stamina_time).unwrap();
+    if frame.parameters.has_stamina {
+        // Same factor as in PM_WalkMove() and PM_Jump(): 19% per second of stamina time.
+        let penalty = frame.state.player.stamina_time / 1000. * 19.;
+        write!(text, "  Stamina Penalty: {penalty:.1}%\0").unwrap();
+    }
+
+    if let Some(error) = frame.state.strafe_angle_error() {
+        write!(text, "  Strafe Angle Error: {:.4}\0", error.to_degrees()).unwrap();
+    }
 }
 
 static PREVENT_UNPAUSE: MainThreadCell<bool> = MainThreadCell::new(false);
@@ -7,7 +7,7 @@ use std::sync::{Mutex, TryLockError};
 use std::time::{Duration, Instant};
 use std::{fmt, thread};
 
-use bxt_ipc_types::Frame;
+use bxt_ipc_types::{Frame, GameResponse, Handshake, VersionMismatch};
 use color_eyre::eyre::{self, eyre, Context};
 use hltas::HLTAS;
 use ipc_channel::ipc::{IpcOneShotServer, IpcReceiver, IpcSender};
@@ -45,6 +45,15 @@ pub struct AccurateFrame {
     pub is_smoothed: bool,
 }
 
+/// Message from the client to the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClientMessage {
+    /// Frame played by the client.
+    AccurateFrame(AccurateFrame),
+    /// Response to the current play request, such as an error.
+    Response(GameResponse),
+}
+
 impl fmt::Debug for AccurateFrame {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AccurateFrame")
@@ -57,7 +66,7 @@ impl fmt::Debug for AccurateFrame {
 enum State {
     Server(Option<RemoteClient>),
     Client {
-        sender: IpcSender<AccurateFrame>,
+        sender: IpcSender<ClientMessage>,
         receiver: IpcReceiver<PlayRequest>,
     },
 }
@@ -73,7 +82,7 @@ impl State {
 }
 
 struct RemoteClient {
-    receiver: IpcReceiver<AccurateFrame>,
+    receiver: IpcReceiver<ClientMessage>,
     // The sender is split into a separate Mutex.
 }
 
@@ -147,8 +156,7 @@ fn server_thread(listener: TcpListener) {
         }
         drop(stream);
 
-        let (_, (request_sender, workaround_sender)): (_, (_, IpcSender<_>)) = match server.accept()
-        {
+        let (_, handshake): (_, Handshake<(_, IpcSender<_>)>) = match server.accept() {
             Ok(x) => x,
             Err(err) => {
                 error!("Error accepting remote client IPC connection: {err:?}");
@@ -156,6 +164,13 @@ fn server_thread(listener: TcpListener) {
             }
         };
 
+        let (request_sender, workaround_sender) = handshake.channels;
+        if let Err(err) = handshake.check_version() {
+            error!("Rejecting remote client: {err}");
+            let _ = workaround_sender.send(Err(err));
+            continue;
+        }
+
         let (frames_sender, frames_receiver) = match ipc_channel::ipc::channel() {
             Ok(x) => x,
             Err(err) => {
@@ -164,7 +179,7 @@ fn server_thread(listener: TcpListener) {
             }
         };
 
-        if let Err(err) = workaround_sender.send(frames_sender) {
+        if let Err(err) = workaround_sender.send(Ok(frames_sender)) {
             error!("Error sending the frames sender to the remote client: {err:?}");
             return;
         };
@@ -266,7 +281,7 @@ fn client_connection_thread() {
 
 fn connect_to_server(
     mut stream: TcpStream,
-) -> eyre::Result<(IpcReceiver<PlayRequest>, IpcSender<AccurateFrame>)> {
+) -> eyre::Result<(IpcReceiver<PlayRequest>, IpcSender<ClientMessage>)> {
     // The first messages are trace!() because they will spam every second if we're trying to
     // connect to a server which already has a game connected.
     trace!("reading IPC name from server");
@@ -291,13 +306,14 @@ fn connect_to_server(
         ipc_channel::ipc::channel().context("error creating workaround IPC channel")?;
 
     trace!("sending senders to server");
-    tx.send((hltas_sender, workaround_sender))
+    tx.send(Handshake::new((hltas_sender, workaround_sender)))
         .context("error sending IPC channels to server")?;
 
     trace!("receiving sender from server");
-    let response_sender = workaround_receiver
+    let response: Result<_, VersionMismatch> = workaround_receiver
         .recv()
         .context("error receiving frames sender from server")?;
+    let response_sender = response.context("server rejected the connection")?;
 
     debug!("connected to remote server");
 
@@ -365,23 +381,32 @@ pub fn receive_request_from_server() -> Result<Option<PlayRequest>, ()> {
 
 #[instrument(skip_all)]
 pub fn send_frame_to_server(frame: AccurateFrame) -> Result<(), ()> {
+    send_message_to_server(ClientMessage::AccurateFrame(frame))
+}
+
+#[instrument(skip_all)]
+pub fn send_response_to_server(response: GameResponse) -> Result<(), ()> {
+    send_message_to_server(ClientMessage::Response(response))
+}
+
+fn send_message_to_server(message: ClientMessage) -> Result<(), ()> {
     let mut state = STATE.lock().unwrap();
     let Some(State::Client { sender, .. }) = state.as_mut() else {
         return Err(());
     };
 
-    match sender.send(frame) {
+    match sender.send(message) {
         Ok(()) => Ok(()),
         Err(err) => {
             // TODO: propagate error, print outside.
-            error!("error sending frame to server: {err:?}");
+            error!("error sending message to server: {err:?}");
             *state = None;
             Err(())
         }
     }
 }
 
-pub fn receive_frame_from_client() -> Result<Option<AccurateFrame>, ()> {
+pub fn receive_message_from_client() -> Result<Option<ClientMessage>, ()> {
     let mut state = match STATE.try_lock() {
         Err(TryLockError::Poisoned(guard)) => panic!("{guard:?}"),
         Err(TryLockError::WouldBlock) => return Ok(None),
@@ -392,11 +417,11 @@ pub fn receive_frame_from_client() -> Result<Option<AccurateFrame>, ()> {
     };
 
     match receiver.try_recv() {
-        Ok(frame) => Ok(Some(frame)),
+        Ok(message) => Ok(Some(message)),
         Err(ipc_channel::ipc::TryRecvError::Empty) => Ok(None),
         Err(ipc_channel::ipc::TryRecvError::IpcError(err)) => {
             // TODO: propagate error, print outside.
-            error!("error receiving frame from client: {err:?}");
+            error!("error receiving message from client: {err:?}");
             *state = Some(State::Server(None));
             Err(())
         }
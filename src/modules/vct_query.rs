@@ -0,0 +1,104 @@
+//! `bxt_vct_query` and `bxt_vct_max_gap`.
+
+use bxt_vct::Vct;
+
+use super::Module;
+use crate::handler;
+use crate::hooks::engine::con_print;
+use crate::modules::commands::{self, Command};
+use crate::utils::*;
+
+pub struct VctQuery;
+impl Module for VctQuery {
+    fn name(&self) -> &'static str {
+        "bxt_vct_query"
+    }
+
+    fn description(&self) -> &'static str {
+        "Inspecting the vectorial compensation table."
+    }
+
+    fn commands(&self) -> &'static [&'static Command] {
+        static COMMANDS: &[&Command] = &[&BXT_VCT_QUERY, &BXT_VCT_MAX_GAP];
+        COMMANDS
+    }
+
+    fn is_enabled(&self, marker: MainThreadMarker) -> bool {
+        commands::Commands.is_enabled(marker)
+    }
+}
+
+static BXT_VCT_QUERY: Command = Command::new(
+    b"bxt_vct_query\0",
+    handler!(
+        "bxt_vct_query <angle>
+
+Finds the vectorial compensation table entry closest to the given angle (in degrees) and prints \
+its forwardmove and sidemove values, the angle that they give and the error from the requested \
+angle.
+
+The angle is relative to the view yaw: 0 is forward, positive angles are to the left.
+
+The table is computed on first use, which takes a few seconds.",
+        vct_query as fn(_, _)
+    ),
+);
+
+fn vct_query(marker: MainThreadMarker, angle: f32) {
+    if !angle.is_finite() {
+        con_print(marker, "The angle must be a finite number.\n");
+        return;
+    }
+
+    let entry = Vct::get().find_best(angle.to_radians());
+    let achieved = entry.angle_radians().to_degrees();
+
+    let mut error = (achieved - angle) % 360.;
+    if error >= 180. {
+        error -= 360.;
+    } else if error < -180. {
+        error += 360.;
+    }
+
+    con_print(
+        marker,
+        &format!(
+            "forward: {}\nside: {}\nangle: {achieved:.6}\nerror: {error:.6}\n",
+            entry.forward, entry.side
+        ),
+    );
+}
+
+static BXT_VCT_MAX_GAP: Command = Command::new(
+    b"bxt_vct_max_gap\0",
+    handler!(
+        "bxt_vct_max_gap
+
+Finds the largest angular gap between consecutive vectorial compensation table entries and prints \
+its size and position. Target angles inside this gap have the largest strafing error.
+
+The table is computed on first use, which takes a few seconds.",
+        vct_max_gap as fn(_)
+    ),
+);
+
+fn vct_max_gap(marker: MainThreadMarker) {
+    let Some(gap) = Vct::get().max_gap() else {
+        con_print(marker, "The table is empty.\n");
+        return;
+    };
+
+    let size = gap.size().to_degrees();
+    let start = gap.start.to_degrees();
+    let end = gap.end.to_degrees();
+    let location = if gap.is_near_axis() {
+        "near an axis"
+    } else {
+        "near a diagonal"
+    };
+
+    con_print(
+        marker,
+        &format!("gap: {size:.6}\nfrom: {start:.6}\nto: {end:.6}\nlocation: {location}\n"),
+    );
+}
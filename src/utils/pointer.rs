@@ -4,7 +4,7 @@ use std::cell::Cell;
 use std::ffi::c_void;
 use std::ptr::{null_mut, NonNull};
 
-use bxt_patterns::Patterns;
+use bxt_patterns::{MaskByte, Patterns};
 
 use crate::utils::*;
 
@@ -145,7 +145,7 @@ impl<P> Pointer<P> {
     /// Creates an empty `Pointer` with the given symbol name.
     pub const fn empty(symbol: &'static [u8]) -> Self {
         // https://github.com/rust-lang/rust/issues/64992
-        const EMPTY_SLICE: &[&[Option<u8>]] = &[];
+        const EMPTY_SLICE: &[&[MaskByte]] = &[];
         Self::empty_patterns(symbol, Patterns(EMPTY_SLICE), null_mut())
     }
 